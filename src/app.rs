@@ -11,8 +11,14 @@ use signal_hook::iterator::Signals;
 use crate::{
     fm::{
         archive_mounter::ArchiveEntry,
-        cp_mv_rm::database::{DBDirListEntry, DBFileEntry, DBJobEntry, DBJobOperation, OnConflict},
+        compare_dirs::CompareBy,
+        cp_mv_rm::{
+            database::{DBDirListEntry, DBFileEntry, DBJobEntry, DBJobOperation, OnConflict},
+            dlg_report::ReportFormat,
+            hooks::{HookError, PreJobNext},
+        },
         entry::{Entry, HiddenFiles, SortBy, SortOrder},
+        tag_criteria::TagCriteria,
     },
     viewer::{dlg_goto::GotoType, dlg_hex_search::HexSearch, dlg_text_search::TextSearch},
 };
@@ -72,25 +78,34 @@ pub enum PubSub {
     // File panel events
     SelectedEntry(Option<Entry>),
     ChangedDirectory(PathBuf),
+    TabSwitched,
     DirCreated(PathBuf),
     ViewFile(PathBuf, PathBuf),
     EditFile(PathBuf, PathBuf),
     Leader(Option<char>),
     SortFiles(SortBy, SortOrder),
+    ToggleSortDirs,
     ToggleHidden,
     Reload,
     PromptFileFilter(String),
     PromptTagGlob,
     PromptUntagGlob,
+    DlgTag,
     PromptMkdir,
     PromptRename(String, usize),
     PromptShell(PathBuf),
+    PromptPanelize(PathBuf),
     MountArchive(PathBuf),
     Rm(PathBuf, Vec<Entry>),
     Cp(PathBuf, Vec<Entry>),
     Mv(PathBuf, Vec<Entry>),
     Fzf(PathBuf, Vec<Entry>, HiddenFiles),
     SelectFile(PathBuf),
+    GotoDirectory(PathBuf),
+    HistoryDirs(Vec<PathBuf>),
+    FrecentDirs(Vec<PathBuf>, Vec<PathBuf>),
+    CompareDirs(CompareBy),
+    DirsCompared(PathBuf, PathBuf, Vec<PathBuf>),
 
     // Quick view events
     ToggleQuickView(Option<Entry>),
@@ -104,7 +119,17 @@ pub enum PubSub {
     Mkdir(String),
     Rename(String),
     Shell(PathBuf, String),
-    SaveReport(PathBuf, String),
+    Panelize(PathBuf, String),
+    Panelized(PathBuf, String, Vec<PathBuf>),
+    SaveReport(PathBuf, String, ReportFormat),
+
+    // Dialog Tag events
+    TagMatching(TagCriteria),
+    UntagMatching(TagCriteria),
+
+    // Dialog Basket events
+    CpFlat(PathBuf, Vec<Entry>),
+    MvFlat(PathBuf, Vec<Entry>),
 
     // Dialog MountArchive events
     ArchiveMounted(PathBuf, PathBuf),
@@ -115,16 +140,28 @@ pub enum PubSub {
     DoRm(DBJobEntry, Vec<DBFileEntry>, Vec<ArchiveEntry>),
     DoCp(DBJobEntry, Vec<DBFileEntry>, Vec<ArchiveEntry>),
     DoMv(DBJobEntry, Vec<DBFileEntry>, Vec<ArchiveEntry>),
+    DirscanAborted,
 
     // Dialog CpMv events
     DoDirscan(PathBuf, Vec<Entry>, String, OnConflict, DBJobOperation),
+    DoDirscanFlat(PathBuf, Vec<Entry>, String, OnConflict, DBJobOperation),
 
     // Dialog Progress events
     JobCompleted(DBJobEntry, Vec<DBFileEntry>, Vec<DBDirListEntry>),
 
+    // Dialog Hook events
+    PreJobHookDone(DBJobEntry, PreJobNext, Option<HookError>),
+    PostJobHookDone(
+        DBJobEntry,
+        Vec<DBFileEntry>,
+        Vec<DBDirListEntry>,
+        Option<HookError>,
+    ),
+
     // Dialog Report events
-    PromptSaveReport(PathBuf, PathBuf),
-    DoSaveReport(PathBuf),
+    PromptSaveReport(PathBuf, PathBuf, ReportFormat),
+    DoSaveReport(PathBuf, ReportFormat),
+    RetryJob(DBJobEntry, Vec<DBFileEntry>, Vec<(PathBuf, PathBuf)>),
 
     // Dialog PendingJob events
     MountArchivesForJob(DBJobEntry),
@@ -230,7 +267,7 @@ pub fn centered_rect(width: u16, height: u16, r: &Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length((r.height.saturating_sub(height) + 1) / 2),
+            Constraint::Length(r.height.saturating_sub(height).div_ceil(2)),
             Constraint::Length(height),
             Constraint::Min(0),
         ])
@@ -239,7 +276,7 @@ pub fn centered_rect(width: u16, height: u16, r: &Rect) -> Rect {
     Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length((r.width.saturating_sub(width) + 1) / 2),
+            Constraint::Length(r.width.saturating_sub(width).div_ceil(2)),
             Constraint::Length(width),
             Constraint::Min(0),
         ])
@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use ratatui::prelude::*;
 
 use clap::crate_name;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Options {
@@ -15,6 +15,13 @@ pub struct Options {
     pub show_button_bar: bool,
     pub use_shadows: bool,
     pub use_internal_viewer: bool,
+
+    #[serde(default)]
+    pub restore_session: bool,
+    #[serde(default)]
+    pub flat_depth: usize,
+    #[serde(default)]
+    pub sort_per_directory: bool,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone)]
@@ -77,6 +84,28 @@ pub struct FileManager {
     pub archive_fg: Color,
 }
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(default)]
+pub struct Git {
+    pub modified_fg: Color,
+    pub staged_fg: Color,
+    pub untracked_fg: Color,
+    pub ignored_fg: Color,
+    pub conflicted_fg: Color,
+}
+
+impl Default for Git {
+    fn default() -> Self {
+        Git {
+            modified_fg: Color::LightYellow,
+            staged_fg: Color::LightGreen,
+            untracked_fg: Color::LightCyan,
+            ignored_fg: Color::DarkGray,
+            conflicted_fg: Color::LightRed,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Copy, Clone)]
 pub struct Viewer {
     pub tab_size: u8,
@@ -109,6 +138,99 @@ pub struct Highlight {
     pub base0f: Color,
 }
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(default)]
+pub struct CpMv {
+    pub retries: u32,
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for CpMv {
+    fn default() -> Self {
+        CpMv {
+            retries: 3,
+            retry_backoff_ms: 1000,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum DesktopNotification {
+    #[default]
+    None,
+    Osc9,
+    Osc777,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Notifications {
+    pub bell: bool,
+    pub desktop: DesktopNotification,
+    pub command: String,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Hooks {
+    pub pre_job: String,
+    pub post_job: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Name,
+    Size,
+    Mtime,
+    Atime,
+    Ctime,
+    Permissions,
+    Octal,
+    Git,
+    Owner,
+    Group,
+    Inode,
+    Links,
+    LinkTarget,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ListingMode {
+    Brief,
+    #[default]
+    Full,
+    Long,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Columns {
+    pub listing_mode: ListingMode,
+    pub full: Vec<Column>,
+    pub long: Vec<Column>,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Columns {
+            listing_mode: ListingMode::Full,
+            full: vec![Column::Git, Column::Name, Column::Size, Column::Mtime],
+            long: vec![
+                Column::Permissions,
+                Column::Links,
+                Column::Owner,
+                Column::Group,
+                Column::Size,
+                Column::Mtime,
+                Column::Name,
+            ],
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub options: Options,
@@ -117,8 +239,18 @@ pub struct Config {
     pub error: Error,
     pub dialog: Dialog,
     pub file_manager: FileManager,
+    #[serde(default)]
+    pub git: Git,
     pub viewer: Viewer,
     pub highlight: Highlight,
+    #[serde(default)]
+    pub cp_mv: CpMv,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default)]
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub columns: Columns,
 }
 
 pub fn load_config() -> Result<Config> {
@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+
+use path_clean::PathClean;
+
+/// An archive mounted on a directory, so that its content can be accessed as a regular directory.
+///
+/// The engine always receives paths as seen by the user, that is with the archive file
+/// as a component, and maps them to the mount directory before touching the filesystem.
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub archive_file: PathBuf,
+    pub temp_dir: PathBuf,
+}
+
+pub fn unarchive_path_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
+    archive_dirs
+        .iter()
+        .rev()
+        .find(|entry| file.starts_with(&entry.archive_file))
+        .map(|entry| {
+            entry
+                .temp_dir
+                .join(file.strip_prefix(&entry.archive_file).unwrap())
+        })
+        .unwrap_or_else(|| PathBuf::from(file))
+        .clean()
+}
+
+pub fn archive_path_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
+    archive_dirs
+        .iter()
+        .rev()
+        .find(|entry| file.starts_with(&entry.temp_dir))
+        .map(|entry| {
+            entry
+                .archive_file
+                .join(file.strip_prefix(&entry.temp_dir).unwrap())
+        })
+        .unwrap_or_else(|| PathBuf::from(file))
+        .clean()
+}
+
+pub fn unarchive_parent_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
+    match (file.parent(), file.file_name()) {
+        (Some(parent), Some(file_name)) => unarchive_path_map(parent, archive_dirs).join(file_name),
+        _ => PathBuf::from(file),
+    }
+}
+
+pub fn archive_parent_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
+    match (file.parent(), file.file_name()) {
+        (Some(parent), Some(file_name)) => archive_path_map(parent, archive_dirs).join(file_name),
+        _ => PathBuf::from(file),
+    }
+}
@@ -1,33 +1,27 @@
 use std::{
-    fs,
     io::ErrorKind,
-    os::unix::fs::{lchown, symlink, MetadataExt},
+    os::{fd::AsFd, unix::fs::MetadataExt},
     path::{Path, PathBuf},
-    time::{Duration, Instant},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{Context, Result};
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
 
 use pathdiff::diff_paths;
 use rustix::{
-    fs::{
-        copy_file_range, fadvise, fallocate, fstat, fsync, open, seek, sendfile, sync, Advice,
-        FallocateFlags, Mode, OFlags, SeekFrom,
-    },
-    io::{read, write, Errno},
+    fs::{Advice, FallocateFlags, Mode, OFlags, SeekFrom},
+    io::Errno,
 };
 
-use crate::{
-    app::PubSub,
-    fm::{
-        archive_mounter::{unarchive_parent_map, unarchive_path_map, ArchiveEntry},
-        cp_mv_rm::database::{
-            DBDirListEntry, DBFileEntry, DBFileStatus, DBJobOperation, DBJobStatus,
-            DBRenameDirEntry, DBSkipDirEntry, DataBase, OnConflict,
-        },
+use crate::engine::{
+    archive::{unarchive_parent_map, unarchive_path_map, ArchiveEntry},
+    database::{
+        DBDirListEntry, DBFileEntry, DBFileStatus, DBJobOperation, DBJobStatus, DBRenameDirEntry,
+        DBSkipDirEntry, DataBase, OnConflict,
     },
-    shutil,
+    fs_ops::{error_code, FsOps},
 };
 
 #[derive(Debug, Clone)]
@@ -64,6 +58,33 @@ pub struct CpMvResult {
     pub status: DBJobStatus,
 }
 
+/// How `cp_mv` retries copying a file that failed with a transient I/O error
+/// (EIO, ETIMEDOUT or ESTALE), as it happens on network filesystems.
+///
+/// Every retry reopens both files and resumes from the last fsynced block, after
+/// waiting `backoff`, that doubles at every retry of the same file.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1 << retry.saturating_sub(1).min(16))
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Timers {
     pub start: Instant,
@@ -71,6 +92,11 @@ struct Timers {
     pub cur_start: Instant,
 }
 
+/// Copy or move the files listed by `dirscan` from `cwd` to `dest`.
+///
+/// Entries whose status is `InProgress` are resumed, and entries that are already
+/// `Error`, `Skipped` or `Done` are left untouched.
+/// When `db_file` is given, the progress of the job is recorded there.
 #[allow(clippy::too_many_arguments)]
 pub fn cp_mv(
     job_id: i64,
@@ -81,10 +107,11 @@ pub fn cp_mv(
     replace_first_path: bool,
     entries: &[DBFileEntry],
     ev_rx: Receiver<CpMvEvent>,
-    info_tx: Sender<CpMvInfo>,
-    pubsub_tx: Sender<PubSub>,
+    on_info: &mut dyn FnMut(CpMvInfo),
     db_file: Option<&Path>,
     archive_dirs: &[ArchiveEntry],
+    retry_policy: RetryPolicy,
+    fs_ops: &dyn FsOps,
 ) -> CpMvResult {
     let mut job_status_result = DBJobStatus::InProgress;
 
@@ -94,7 +121,7 @@ pub fn cp_mv(
     let actual_dest = unarchive_path_map(dest, archive_dirs);
 
     let default_block_size: i64 = 128 * 1024;
-    let block_size = match fs::metadata(&actual_dest) {
+    let block_size = match fs_ops.metadata(&actual_dest) {
         Ok(metadata) => {
             let fs_block_size = metadata.blksize() as i64;
 
@@ -152,6 +179,8 @@ pub fn cp_mv(
             _ => {}
         }
 
+        info.cur_bytes = 0;
+
         match cp_mv_entry(
             job_id,
             operation,
@@ -160,8 +189,7 @@ pub fn cp_mv(
             dest,
             on_conflict,
             &ev_rx,
-            &info_tx,
-            &pubsub_tx,
+            on_info,
             block_size,
             &mut info,
             &mut dir_list,
@@ -171,9 +199,13 @@ pub fn cp_mv(
             &mut timers,
             &mut database,
             archive_dirs,
+            retry_policy,
+            fs_ops,
         ) {
             Ok((file_status, job_status)) => {
                 entry.status = file_status;
+                entry.end_time = Some(SystemTime::now());
+                entry.bytes = info.cur_bytes;
 
                 if let Some(db) = &database {
                     db.set_file_status(entry);
@@ -190,8 +222,14 @@ pub fn cp_mv(
                 }
             }
             Err(e) => {
-                entry.message = format!("({}) {}", e, e.root_cause());
+                entry.message = match entry.retries {
+                    0 => format!("({}) {}", e, e.root_cause()),
+                    retries => format!("({}) {} -- Retried {} times", e, e.root_cause(), retries),
+                };
                 entry.status = DBFileStatus::Error;
+                entry.error_code = error_code(&e);
+                entry.end_time = Some(SystemTime::now());
+                entry.bytes = info.cur_bytes;
 
                 if let Some(db) = &database {
                     db.set_file_status(entry);
@@ -212,21 +250,24 @@ pub fn cp_mv(
             _ => {}
         }
 
+        entry.start_time.get_or_insert_with(SystemTime::now);
+
         match handle_dir_entry(
             job_id,
             operation,
             entry,
             cwd,
             &ev_rx,
-            &info_tx,
-            &pubsub_tx,
+            on_info,
             &mut info,
             &mut timers,
             &mut database,
             archive_dirs,
+            fs_ops,
         ) {
             Ok((file_status, job_status)) => {
                 entry.status = file_status;
+                entry.end_time = Some(SystemTime::now());
 
                 if let Some(db) = &database {
                     db.set_dir_list_entry_status(entry);
@@ -245,6 +286,8 @@ pub fn cp_mv(
             Err(e) => {
                 entry.message = format!("({}) {}", e, e.root_cause());
                 entry.status = DBFileStatus::Error;
+                entry.error_code = error_code(&e);
+                entry.end_time = Some(SystemTime::now());
 
                 if let Some(db) = &database {
                     db.set_dir_list_entry_status(entry);
@@ -262,7 +305,7 @@ pub fn cp_mv(
     }
 
     if database.is_none() {
-        sync();
+        fs_ops.sync();
     }
 
     CpMvResult {
@@ -281,8 +324,7 @@ fn cp_mv_entry(
     dest: &Path,
     on_conflict: OnConflict,
     ev_rx: &Receiver<CpMvEvent>,
-    info_tx: &Sender<CpMvInfo>,
-    pubsub_tx: &Sender<PubSub>,
+    on_info: &mut dyn FnMut(CpMvInfo),
     block_size: u64,
     info: &mut CpMvInfo,
     dir_list: &mut Vec<DBDirListEntry>,
@@ -292,9 +334,13 @@ fn cp_mv_entry(
     timers: &mut Timers,
     database: &mut Option<DataBase>,
     archive_dirs: &[ArchiveEntry],
+    retry_policy: RetryPolicy,
+    fs_ops: &dyn FsOps,
 ) -> Result<(DBFileStatus, DBJobStatus)> {
     timers.cur_start = Instant::now();
 
+    entry.start_time.get_or_insert_with(SystemTime::now);
+
     let cur_file = PathBuf::from(&entry.file);
 
     let rel_file = diff_paths(&cur_file, cwd).unwrap();
@@ -340,6 +386,11 @@ fn cp_mv_entry(
         }
     }
 
+    // A retried entry starts over in the target of its directory, chosen by the failed job
+    if let (DBFileStatus::ToDo, Some(x)) = (&entry.status, &entry.cur_target) {
+        cur_target = x.clone();
+    }
+
     let mut actual_file = unarchive_parent_map(&cur_file, archive_dirs);
     let mut actual_target = unarchive_parent_map(&cur_target, archive_dirs);
 
@@ -354,7 +405,33 @@ fn cp_mv_entry(
                 actual_target = unarchive_parent_map(&cur_target, archive_dirs);
             }
 
-            if actual_target.exists() {
+            if fs_ops.exists(&actual_target) {
+                if matches!(operation, DBJobOperation::Mv) && !fs_ops.exists(&actual_file) {
+                    // The source has already been renamed to the target
+                    if entry.is_dir && !skip_dir {
+                        skip_dir_stack.push(DBSkipDirEntry {
+                            id: 0,
+                            job_id,
+                            file: cur_file.clone(),
+                        });
+
+                        if let Some(db) = &database {
+                            db.push_skip_dir_stack(skip_dir_stack.last_mut().unwrap());
+                        }
+                    }
+
+                    return Ok((DBFileStatus::Done, DBJobStatus::InProgress));
+                }
+
+                if entry.is_dir
+                    && dir_list
+                        .iter()
+                        .any(|dir| dir.file.id == entry.id && dir.cur_file == cur_file)
+                {
+                    // The directory has already been created and added to the dir list
+                    return Ok((DBFileStatus::Done, DBJobStatus::InProgress));
+                }
+
                 resume = true;
 
                 if entry.message.is_empty() {
@@ -365,12 +442,12 @@ fn cp_mv_entry(
             }
         }
         _ => {
-            if actual_target.exists() && !skip_dir {
-                target_is_dir = actual_target.is_dir();
-                target_is_symlink = actual_target.is_symlink();
+            if fs_ops.exists(&actual_target) && !skip_dir {
+                target_is_dir = fs_ops.is_dir(&actual_target);
+                target_is_symlink = fs_ops.is_symlink(&actual_target);
 
                 if !(entry.is_dir && target_is_dir) {
-                    if same_file(&actual_file, &actual_target).context("samefile")?
+                    if same_file(&actual_file, &actual_target, fs_ops).context("samefile")?
                         && (matches!(operation, DBJobOperation::Mv)
                             || !(matches!(on_conflict, OnConflict::RenameExisting)
                                 || matches!(on_conflict, OnConflict::RenameCopy)))
@@ -382,15 +459,17 @@ fn cp_mv_entry(
                     match on_conflict {
                         OnConflict::Overwrite => {
                             if target_is_dir && !target_is_symlink {
-                                fs::remove_dir(&actual_target).context("rmdir")?;
+                                fs_ops.remove_dir(&actual_target).context("rmdir")?;
                             } else {
-                                fs::remove_file(&actual_target).context("remove")?;
+                                fs_ops.remove_file(&actual_target).context("remove")?;
                             }
 
                             if let Some(_db) = &database {
                                 fsync_parent(
-                                    &fs::canonicalize(actual_target.parent().unwrap())
+                                    &fs_ops
+                                        .canonicalize(actual_target.parent().unwrap())
                                         .context("fsync")?,
+                                    fs_ops,
                                 )
                                 .context("fsync")?;
                             }
@@ -405,22 +484,28 @@ fn cp_mv_entry(
                                 .to_string_lossy()
                                 .to_string();
                             let mut existing_target = actual_target.clone();
-                            while existing_target.exists() {
+                            while fs_ops.exists(&existing_target) {
                                 let new_name = format!("{}.fcdsave{}", name, i);
                                 existing_target = existing_target.parent().unwrap().join(new_name);
                                 i += 1;
                             }
 
-                            if same_file(&actual_file, &actual_target).context("samefile")? {
+                            if same_file(&actual_file, &actual_target, fs_ops)
+                                .context("samefile")?
+                            {
                                 actual_file = existing_target.clone();
                             }
 
-                            fs::rename(&actual_target, &existing_target).context("rename")?;
+                            fs_ops
+                                .rename(&actual_target, &existing_target)
+                                .context("rename")?;
 
                             if let Some(_db) = &database {
                                 fsync_parent(
-                                    &fs::canonicalize(existing_target.parent().unwrap())
+                                    &fs_ops
+                                        .canonicalize(existing_target.parent().unwrap())
                                         .context("fsync")?,
+                                    fs_ops,
                                 )
                                 .context("fsync")?;
                             }
@@ -438,7 +523,7 @@ fn cp_mv_entry(
                                 .to_string_lossy()
                                 .to_string();
                             let existing_target = cur_target.clone();
-                            while unarchive_path_map(&cur_target, archive_dirs).exists() {
+                            while fs_ops.exists(&unarchive_path_map(&cur_target, archive_dirs)) {
                                 let new_name = format!("{}.fcdnew{}", name, i);
                                 cur_target = cur_target.parent().unwrap().join(new_name);
                                 i += 1;
@@ -494,11 +579,11 @@ fn cp_mv_entry(
                     timers.start += dt;
                 }
                 CpMvEvent::Skip => {
-                    let _ = fs::remove_file(&actual_target);
+                    let _ = fs_ops.remove_file(&actual_target);
                     return Ok((DBFileStatus::Skipped, DBJobStatus::InProgress));
                 }
                 CpMvEvent::Abort => {
-                    let _ = fs::remove_file(&actual_target);
+                    let _ = fs_ops.remove_file(&actual_target);
                     return Ok((DBFileStatus::ToDo, DBJobStatus::Aborted));
                 }
                 CpMvEvent::NoDb => {
@@ -521,20 +606,67 @@ fn cp_mv_entry(
         timers.last_write = Instant::now();
         info.cur_time = timers.last_write.duration_since(timers.cur_start);
         info.total_time = timers.last_write.duration_since(timers.start);
-        let _ = info_tx.send(info.clone());
-        let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+        on_info(info.clone());
     }
 
     if skip_dir {
         return Ok((DBFileStatus::Done, DBJobStatus::InProgress));
     }
 
-    let parent_dir = fs::canonicalize(actual_target.parent().unwrap()).context("parent_dir")?;
+    // Files picked from different directories keep their path relative to cwd, so their parent
+    // directories may not exist yet in the destination. They are added to the dir list, with the
+    // entry that needed them, to get the mode and mtime of the source (and be removed on Mv)
+    let mut missing_dirs = Vec::new();
+    let mut parent_file = cur_file.parent().unwrap();
+    let mut parent_target = cur_target.parent().unwrap();
+    while parent_file.starts_with(cwd)
+        && parent_file != cwd
+        && !fs_ops.exists(&unarchive_path_map(parent_target, archive_dirs))
+    {
+        missing_dirs.push((PathBuf::from(parent_file), PathBuf::from(parent_target)));
+
+        parent_file = parent_file.parent().unwrap();
+        parent_target = parent_target.parent().unwrap();
+    }
+
+    if !fs_ops.exists(&unarchive_path_map(parent_target, archive_dirs)) {
+        fs_ops
+            .create_dir_all(&unarchive_path_map(parent_target, archive_dirs))
+            .context("makedirs")?;
+    }
+
+    for (dir_file, dir_target) in missing_dirs.into_iter().rev() {
+        fs_ops
+            .create_dir_all(&unarchive_path_map(&dir_target, archive_dirs))
+            .context("makedirs")?;
+
+        dir_list.push(DBDirListEntry {
+            id: 0,
+            job_id,
+            file: entry.clone(),
+            cur_file: dir_file,
+            cur_target: dir_target,
+            new_dir: true,
+            status: DBFileStatus::ToDo,
+            message: String::from(""),
+            start_time: None,
+            end_time: None,
+            error_code: None,
+        });
+
+        if let Some(db) = &database {
+            db.push_dir_list(dir_list.last_mut().unwrap());
+        }
+    }
+
+    let parent_dir = fs_ops
+        .canonicalize(actual_target.parent().unwrap())
+        .context("parent_dir")?;
 
     let mut perform_copy = true;
     if matches!(operation, DBJobOperation::Mv) && !target_is_dir {
         perform_copy = false;
-        match fs::rename(&actual_file, &actual_target) {
+        match fs_ops.rename(&actual_file, &actual_target) {
             Ok(_) => {
                 if entry.is_dir {
                     skip_dir_stack.push(DBSkipDirEntry {
@@ -544,12 +676,13 @@ fn cp_mv_entry(
                     });
 
                     if let Some(db) = &database {
-                        fsync_parent(&parent_dir).context("fsync")?;
+                        fsync_parent(&parent_dir, fs_ops).context("fsync")?;
 
-                        let source_parent = fs::canonicalize(actual_file.parent().unwrap())
+                        let source_parent = fs_ops
+                            .canonicalize(actual_file.parent().unwrap())
                             .context("parent_dir")?;
 
-                        fsync_parent(&source_parent).context("fsync")?;
+                        fsync_parent(&source_parent, fs_ops).context("fsync")?;
 
                         db.push_skip_dir_stack(skip_dir_stack.last_mut().unwrap());
                     }
@@ -563,13 +696,19 @@ fn cp_mv_entry(
 
     if perform_copy {
         if entry.is_symlink {
-            fs::read_link(&actual_file)
-                .and_then(|link_target| symlink(&link_target, &actual_target))
+            if resume {
+                // The symlink may have already been created before being interrupted
+                let _ = fs_ops.remove_file(&actual_target);
+            }
+
+            fs_ops
+                .read_link(&actual_file)
+                .and_then(|link_target| fs_ops.symlink(&link_target, &actual_target))
                 .context("symlink")?;
         } else if entry.is_dir {
             let mut new_dir = false;
             if !target_is_dir {
-                fs::create_dir_all(&actual_target).context("makedirs")?;
+                fs_ops.create_dir_all(&actual_target).context("makedirs")?;
                 new_dir = true;
             }
 
@@ -582,36 +721,83 @@ fn cp_mv_entry(
                 new_dir,
                 status: DBFileStatus::ToDo,
                 message: String::from(""),
+                start_time: None,
+                end_time: None,
+                error_code: None,
             });
 
             if let Some(db) = &database {
                 db.push_dir_list(dir_list.last_mut().unwrap());
             }
         } else if entry.is_file {
-            match copy_file(
-                job_id,
-                &actual_file,
-                &actual_target,
-                entry.size,
-                block_size,
-                resume,
-                ev_rx,
-                info_tx,
-                pubsub_tx,
-                info,
-                timers,
-                database,
-            ) {
-                Ok((DBFileStatus::Skipped, _)) => {
-                    let _ = fs::remove_file(&actual_target);
-                    return Ok((DBFileStatus::Skipped, DBJobStatus::InProgress));
-                }
-                Ok((_, DBJobStatus::Aborted)) => {
-                    let _ = fs::remove_file(&actual_target);
-                    return Ok((DBFileStatus::ToDo, DBJobStatus::Aborted));
+            let mut resume = resume;
+            let mut retries = 0;
+
+            loop {
+                let result = match copy_file(
+                    job_id,
+                    &actual_file,
+                    &actual_target,
+                    entry.size,
+                    block_size,
+                    resume,
+                    ev_rx,
+                    on_info,
+                    info,
+                    timers,
+                    database,
+                    fs_ops,
+                ) {
+                    Err(e) if retries < retry_policy.max_retries && is_transient(&e) => {
+                        retries += 1;
+                        entry.retries += 1;
+
+                        if let Some(db) = &database {
+                            db.update_file(entry);
+                        }
+
+                        // The retry resumes from the last fsynced block, so the progress
+                        // of the failed attempt is counted again
+                        info.total_bytes -= info.cur_bytes;
+                        info.cur_bytes = 0;
+
+                        resume = resume || fs_ops.exists(&actual_target);
+
+                        match wait_backoff(
+                            job_id,
+                            retry_policy.backoff(retries),
+                            ev_rx,
+                            timers,
+                            database,
+                        ) {
+                            Some(result) => Ok(result),
+                            None => continue,
+                        }
+                    }
+                    result => result,
+                };
+
+                match result {
+                    Ok((DBFileStatus::Skipped, _)) => {
+                        let _ = fs_ops.remove_file(&actual_target);
+                        return Ok((DBFileStatus::Skipped, DBJobStatus::InProgress));
+                    }
+                    Ok((_, DBJobStatus::Aborted)) => {
+                        let _ = fs_ops.remove_file(&actual_target);
+                        return Ok((DBFileStatus::ToDo, DBJobStatus::Aborted));
+                    }
+                    Ok(_) => break,
+                    Err(e) => return Err(e),
                 }
-                Ok(_) => {}
-                Err(e) => return Err(e),
+            }
+
+            if retries > 0 {
+                let retried = format!("Retried {} times", entry.retries);
+
+                entry.message = match entry.message.is_empty() {
+                    true => retried,
+                    false => format!("{} -- {}", entry.message, retried),
+                };
             }
         } else {
             entry.message = String::from("Special file");
@@ -619,10 +805,10 @@ fn cp_mv_entry(
         }
 
         if !entry.is_dir {
-            if let Err(e) = lchown(&actual_target, Some(entry.uid), Some(entry.gid)) {
+            if let Err(e) = fs_ops.lchown(&actual_target, Some(entry.uid), Some(entry.gid)) {
                 match e.kind() {
                     ErrorKind::PermissionDenied => {
-                        if let Err(e) = lchown(&actual_target, None, Some(entry.gid)) {
+                        if let Err(e) = fs_ops.lchown(&actual_target, None, Some(entry.gid)) {
                             match e.kind() {
                                 ErrorKind::PermissionDenied | ErrorKind::Unsupported => {}
                                 _ => return Err(e).context("lchown"),
@@ -634,22 +820,25 @@ fn cp_mv_entry(
                 }
             }
 
-            shutil::copystat(&actual_file, &actual_target).context("copystat")?;
+            fs_ops
+                .copystat(&actual_file, &actual_target)
+                .context("copystat")?;
         }
 
         if let Some(_db) = &database {
-            fsync_parent(&parent_dir).context("fsync")?;
+            fsync_parent(&parent_dir, fs_ops).context("fsync")?;
         }
     }
 
     if matches!(operation, DBJobOperation::Mv) && perform_copy && !entry.is_dir {
-        fs::remove_file(&actual_file).context("remove")?;
+        fs_ops.remove_file(&actual_file).context("remove")?;
 
         if let Some(_db) = &database {
-            let source_parent =
-                fs::canonicalize(actual_file.parent().unwrap()).context("parent_dir")?;
+            let source_parent = fs_ops
+                .canonicalize(actual_file.parent().unwrap())
+                .context("parent_dir")?;
 
-            fsync_parent(&source_parent).context("fsync")?;
+            fsync_parent(&source_parent, fs_ops).context("fsync")?;
         }
     }
 
@@ -663,12 +852,12 @@ fn handle_dir_entry(
     entry: &DBDirListEntry,
     cwd: &Path,
     ev_rx: &Receiver<CpMvEvent>,
-    info_tx: &Sender<CpMvInfo>,
-    pubsub_tx: &Sender<PubSub>,
+    on_info: &mut dyn FnMut(CpMvInfo),
     info: &mut CpMvInfo,
     timers: &mut Timers,
     database: &mut Option<DataBase>,
     archive_dirs: &[ArchiveEntry],
+    fs_ops: &dyn FsOps,
 ) -> Result<(DBFileStatus, DBJobStatus)> {
     timers.cur_start = Instant::now();
 
@@ -714,15 +903,30 @@ fn handle_dir_entry(
         timers.last_write = Instant::now();
         info.cur_time = timers.last_write.duration_since(timers.cur_start);
         info.total_time = timers.last_write.duration_since(timers.start);
-        let _ = info_tx.send(info.clone());
-        let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+        on_info(info.clone());
+    }
+
+    if matches!(operation, DBJobOperation::Mv) && !fs_ops.exists(&actual_file) {
+        // The source directory has already been removed before being interrupted
+        return Ok((DBFileStatus::Done, DBJobStatus::InProgress));
     }
 
+    // A parent directory created for the entry, instead of the entry itself
+    let is_parent = entry.cur_file != entry.file.file;
+
     if entry.new_dir {
-        if let Err(e) = lchown(&actual_target, Some(entry.file.uid), Some(entry.file.gid)) {
+        let (uid, gid) = match is_parent {
+            true => fs_ops
+                .metadata(&actual_file)
+                .map(|metadata| (metadata.uid(), metadata.gid()))
+                .context("metadata")?,
+            false => (entry.file.uid, entry.file.gid),
+        };
+
+        if let Err(e) = fs_ops.lchown(&actual_target, Some(uid), Some(gid)) {
             match e.kind() {
                 ErrorKind::PermissionDenied => {
-                    if let Err(e) = lchown(&actual_target, None, Some(entry.file.gid)) {
+                    if let Err(e) = fs_ops.lchown(&actual_target, None, Some(gid)) {
                         match e.kind() {
                             ErrorKind::PermissionDenied | ErrorKind::Unsupported => {}
                             _ => return Err(e).context("lchown"),
@@ -734,24 +938,34 @@ fn handle_dir_entry(
             }
         }
 
-        shutil::copystat(&actual_file, &actual_target).context("copystat")?;
+        fs_ops
+            .copystat(&actual_file, &actual_target)
+            .context("copystat")?;
 
         if let Some(_db) = &database {
-            let parent_dir =
-                fs::canonicalize(actual_target.parent().unwrap()).context("parent_dir")?;
+            let parent_dir = fs_ops
+                .canonicalize(actual_target.parent().unwrap())
+                .context("parent_dir")?;
 
-            fsync_parent(&parent_dir).context("fsync")?;
+            fsync_parent(&parent_dir, fs_ops).context("fsync")?;
         }
     }
 
     if let DBJobOperation::Mv = operation {
-        fs::remove_dir(&actual_file).context("rmdir")?;
+        match fs_ops.remove_dir(&actual_file) {
+            // The parent directory keeps the files that have not been moved
+            Err(e) if is_parent && e.kind() == ErrorKind::DirectoryNotEmpty => {
+                return Ok((DBFileStatus::Done, DBJobStatus::InProgress));
+            }
+            result => result.context("rmdir")?,
+        }
 
         if let Some(_db) = &database {
-            let source_parent =
-                fs::canonicalize(actual_file.parent().unwrap()).context("parent_dir")?;
+            let source_parent = fs_ops
+                .canonicalize(actual_file.parent().unwrap())
+                .context("parent_dir")?;
 
-            fsync_parent(&source_parent).context("fsync")?;
+            fsync_parent(&source_parent, fs_ops).context("fsync")?;
         }
     }
 
@@ -767,40 +981,43 @@ fn copy_file(
     block_size: u64,
     resume: bool,
     ev_rx: &Receiver<CpMvEvent>,
-    info_tx: &Sender<CpMvInfo>,
-    pubsub_tx: &Sender<PubSub>,
+    on_info: &mut dyn FnMut(CpMvInfo),
     info: &mut CpMvInfo,
     timers: &mut Timers,
     database: &mut Option<DataBase>,
+    fs_ops: &dyn FsOps,
 ) -> Result<(DBFileStatus, DBJobStatus)> {
-    let source_fd = open(actual_file, OFlags::RDONLY, Mode::RUSR).context("source_fd")?;
+    let source_fd = fs_ops
+        .open(actual_file, OFlags::RDONLY, Mode::RUSR)
+        .context("source_fd")?;
 
     let target_fd = match resume {
-        true => match open(actual_target, OFlags::WRONLY, Mode::WUSR) {
+        true => match fs_ops.open(actual_target, OFlags::WRONLY, Mode::WUSR) {
             Ok(fd) => fd,
-            Err(Errno::OPNOTSUPP) => {
-                open(actual_target, OFlags::TRUNC | OFlags::WRONLY, Mode::WUSR)
-                    .context("target_fd")?
-            }
+            Err(Errno::OPNOTSUPP) => fs_ops
+                .open(actual_target, OFlags::TRUNC | OFlags::WRONLY, Mode::WUSR)
+                .context("target_fd")?,
             Err(e) => return Err(e).context("target_fd"),
         },
         false => {
-            let fd = open(
-                actual_target,
-                OFlags::CREATE | OFlags::EXCL | OFlags::TRUNC | OFlags::WRONLY,
-                Mode::RUSR | Mode::WUSR | Mode::RGRP | Mode::WGRP | Mode::ROTH | Mode::WOTH,
-            )
-            .context("target_fd")?;
+            let fd = fs_ops
+                .open(
+                    actual_target,
+                    OFlags::CREATE | OFlags::EXCL | OFlags::TRUNC | OFlags::WRONLY,
+                    Mode::RUSR | Mode::WUSR | Mode::RGRP | Mode::WGRP | Mode::ROTH | Mode::WOTH,
+                )
+                .context("target_fd")?;
 
-            let _ = fallocate(&fd, FallocateFlags::KEEP_SIZE, 0, file_size);
+            let _ = fs_ops.fallocate(fd.as_fd(), FallocateFlags::KEEP_SIZE, 0, file_size);
 
             if let Some(_db) = &database {
-                fsync(&fd).context("fsync")?;
+                fs_ops.fsync(fd.as_fd()).context("fsync")?;
 
-                let parent_dir =
-                    fs::canonicalize(actual_target.parent().unwrap()).context("parent_dir")?;
+                let parent_dir = fs_ops
+                    .canonicalize(actual_target.parent().unwrap())
+                    .context("parent_dir")?;
 
-                fsync_parent(&parent_dir).context("fsync")?;
+                fsync_parent(&parent_dir, fs_ops).context("fsync")?;
             }
 
             fd
@@ -809,12 +1026,16 @@ fn copy_file(
 
     let mut bytes_written = match resume {
         true => {
-            let size = fstat(&target_fd)?.st_size as u64;
+            let size = fs_ops.fstat(target_fd.as_fd())?.st_size as u64;
             let pos = (size / block_size).saturating_sub(1) * block_size;
 
             if pos != 0 {
-                seek(&source_fd, SeekFrom::Start(pos)).context("lseek")?;
-                seek(&target_fd, SeekFrom::Start(pos)).context("lseek")?;
+                fs_ops
+                    .seek(source_fd.as_fd(), SeekFrom::Start(pos))
+                    .context("lseek")?;
+                fs_ops
+                    .seek(target_fd.as_fd(), SeekFrom::Start(pos))
+                    .context("lseek")?;
                 info.cur_bytes += pos;
                 info.total_bytes += pos;
             }
@@ -824,7 +1045,7 @@ fn copy_file(
         false => 0,
     };
 
-    let _ = fadvise(&source_fd, bytes_written, 0, Advice::Sequential);
+    let _ = fs_ops.fadvise(source_fd.as_fd(), bytes_written, 0, Advice::Sequential);
 
     let mut copy_method = CopyMethod::CopyFileRange;
 
@@ -861,7 +1082,11 @@ fn copy_file(
 
         let (bytes_copied, done) = match copy_method {
             CopyMethod::CopyFileRange => {
-                match copy_file_range(&source_fd, None, &target_fd, None, block_size as usize) {
+                match fs_ops.copy_file_range(
+                    source_fd.as_fd(),
+                    target_fd.as_fd(),
+                    block_size as usize,
+                ) {
                     Ok(bytes_copied) => (bytes_copied, bytes_copied == 0),
                     Err(_) => {
                         copy_method = CopyMethod::Sendfile;
@@ -871,7 +1096,7 @@ fn copy_file(
                 }
             }
             CopyMethod::Sendfile => {
-                match sendfile(&target_fd, &source_fd, None, block_size as usize) {
+                match fs_ops.sendfile(target_fd.as_fd(), source_fd.as_fd(), block_size as usize) {
                     Ok(bytes_copied) => (bytes_copied, bytes_copied == 0),
                     Err(_) => {
                         copy_method = CopyMethod::ReadWrite;
@@ -883,11 +1108,12 @@ fn copy_file(
             CopyMethod::ReadWrite => {
                 let mut bytes_copied = 0;
 
-                let bytes_read = read(&source_fd, &mut buf).context("read")?;
+                let bytes_read = fs_ops.read(source_fd.as_fd(), &mut buf).context("read")?;
                 if bytes_read != 0 {
                     while bytes_copied < bytes_read {
-                        bytes_copied +=
-                            write(&target_fd, &buf[bytes_copied..bytes_read]).context("write")?;
+                        bytes_copied += fs_ops
+                            .write(target_fd.as_fd(), &buf[bytes_copied..bytes_read])
+                            .context("write")?;
                     }
                 }
 
@@ -900,7 +1126,7 @@ fn copy_file(
         }
 
         if let Some(_db) = &database {
-            fsync(&target_fd).context("fsync")?
+            fs_ops.fsync(target_fd.as_fd()).context("fsync")?
         }
 
         bytes_written += bytes_copied as u64;
@@ -912,25 +1138,75 @@ fn copy_file(
             timers.last_write = Instant::now();
             info.cur_time = timers.last_write.duration_since(timers.cur_start);
             info.total_time = timers.last_write.duration_since(timers.start);
-            let _ = info_tx.send(info.clone());
-            let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            on_info(info.clone());
         }
     }
 
     Ok((DBFileStatus::Done, DBJobStatus::InProgress))
 }
 
-fn same_file(file1: &Path, file2: &Path) -> Result<bool> {
+fn is_transient(e: &anyhow::Error) -> bool {
+    matches!(
+        error_code(e),
+        Some(libc::EIO) | Some(libc::ETIMEDOUT) | Some(libc::ESTALE)
+    )
+}
+
+/// Waits before retrying a file, while still handling the events, so that the file
+/// can be skipped and the job aborted during the wait.
+fn wait_backoff(
+    job_id: i64,
+    backoff: Duration,
+    ev_rx: &Receiver<CpMvEvent>,
+    timers: &mut Timers,
+    database: &mut Option<DataBase>,
+) -> Option<(DBFileStatus, DBJobStatus)> {
+    let deadline = Instant::now() + backoff;
+
+    loop {
+        match ev_rx.recv_deadline(deadline) {
+            Ok(CpMvEvent::Suspend(suspend_rx)) => {
+                let t1 = Instant::now();
+                let _ = suspend_rx.recv();
+                let t2 = Instant::now();
+                let dt = t2.duration_since(t1);
+                timers.cur_start += dt;
+                timers.start += dt;
+            }
+            Ok(CpMvEvent::Skip) => {
+                return Some((DBFileStatus::Skipped, DBJobStatus::InProgress));
+            }
+            Ok(CpMvEvent::Abort) => {
+                return Some((DBFileStatus::InProgress, DBJobStatus::Aborted));
+            }
+            Ok(CpMvEvent::NoDb) => {
+                if let Some(db) = &database {
+                    db.delete_job(job_id);
+                }
+
+                *database = None;
+            }
+            Err(RecvTimeoutError::Timeout) => return None,
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(deadline.saturating_duration_since(Instant::now()));
+
+                return None;
+            }
+        }
+    }
+}
+
+fn same_file(file1: &Path, file2: &Path, fs_ops: &dyn FsOps) -> Result<bool> {
     // TODO: Instead of canonicalizing the path it would be more reliable to check the device number and inode number
-    match (fs::canonicalize(file1), fs::canonicalize(file2)) {
+    match (fs_ops.canonicalize(file1), fs_ops.canonicalize(file2)) {
         (Ok(file), Ok(target)) if file == target => Ok(true),
         (e @ Err(_), _) | (_, e @ Err(_)) => Ok(e.map(|_| false)?),
         _ => Ok(false),
     }
 }
 
-fn fsync_parent(parent_dir: &Path) -> rustix::io::Result<()> {
-    let parent_fd = open(parent_dir, OFlags::RDONLY | OFlags::DIRECTORY, Mode::RUSR)?;
+fn fsync_parent(parent_dir: &Path, fs_ops: &dyn FsOps) -> rustix::io::Result<()> {
+    let parent_fd = fs_ops.open(parent_dir, OFlags::RDONLY | OFlags::DIRECTORY, Mode::RUSR)?;
 
-    fsync(parent_fd)
+    fs_ops.fsync(parent_fd.as_fd())
 }
@@ -52,6 +52,12 @@ CREATE TABLE IF NOT EXISTS files (
     target_is_dir INTEGER NOT NULL,
     target_is_symlink INTEGER NOT NULL,
     cur_target TEXT,
+
+    start_time INTEGER,
+    end_time INTEGER,
+    bytes INTEGER NOT NULL,
+    error_code INTEGER,
+    retries INTEGER NOT NULL,
     FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE
 ) STRICT;
 
@@ -64,6 +70,9 @@ CREATE TABLE IF NOT EXISTS dir_list (
     new_dir INTEGER NOT NULL,
     status TEXT NOT NULL,
     message TEXT NOT NULL,
+    start_time INTEGER,
+    end_time INTEGER,
+    error_code INTEGER,
     FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE,
     FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE CASCADE
 ) STRICT;
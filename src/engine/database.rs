@@ -1,18 +1,19 @@
 use std::{
     fmt, fs,
     path::{Path, PathBuf},
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{bail, Result};
 
 use rusqlite::{
-    self,
+    self, params,
     types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
     Connection,
 };
 
 const DB_SIGNATURE: &str = "fcd";
-const DB_VERSION: &str = "1";
+const DB_VERSION: &str = "4";
 
 #[derive(Debug, Clone, Copy)]
 pub enum OnConflict {
@@ -190,6 +191,13 @@ pub struct DBFileEntry {
     pub target_is_dir: bool,
     pub target_is_symlink: bool,
     pub cur_target: Option<PathBuf>,
+
+    // These are set while processing the file
+    pub start_time: Option<SystemTime>,
+    pub end_time: Option<SystemTime>,
+    pub bytes: u64,
+    pub error_code: Option<i32>,
+    pub retries: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -202,6 +210,9 @@ pub struct DBDirListEntry {
     pub new_dir: bool,
     pub status: DBFileStatus,
     pub message: String,
+    pub start_time: Option<SystemTime>,
+    pub end_time: Option<SystemTime>,
+    pub error_code: Option<i32>,
 }
 
 #[derive(Debug, Clone)]
@@ -233,6 +244,16 @@ pub struct DBJobEntry {
     pub archives: Vec<PathBuf>,
 }
 
+// Timestamps are stored as milliseconds since the Unix epoch
+fn to_millis(t: Option<SystemTime>) -> Option<i64> {
+    t.and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+}
+
+fn from_millis(t: Option<i64>) -> Option<SystemTime> {
+    t.map(|t| SystemTime::UNIX_EPOCH + Duration::from_millis(t.max(0) as u64))
+}
+
 #[derive(Debug)]
 pub struct DataBase {
     conn: Connection,
@@ -567,7 +588,12 @@ impl DataBase {
                         message,
                         target_is_dir,
                         target_is_symlink,
-                        cur_target
+                        cur_target,
+                        start_time,
+                        end_time,
+                        bytes,
+                        error_code,
+                        retries
                 FROM files
                 WHERE job_id = ?1
                 ORDER BY id",
@@ -589,6 +615,11 @@ impl DataBase {
                         target_is_dir: row.get(10)?,
                         target_is_symlink: row.get(11)?,
                         cur_target: row.get::<usize, Option<String>>(12)?.map(PathBuf::from),
+                        start_time: from_millis(row.get(13)?),
+                        end_time: from_millis(row.get(14)?),
+                        bytes: row.get(15)?,
+                        error_code: row.get(16)?,
+                        retries: row.get(17)?,
                     })
                 })
                 .and_then(|rows| rows.collect())
@@ -616,7 +647,12 @@ impl DataBase {
                     message,
                     target_is_dir,
                     target_is_symlink,
-                    cur_target
+                    cur_target,
+                    start_time,
+                    end_time,
+                    bytes,
+                    error_code,
+                    retries
                 ) VALUES (
                     ?1,
                     ?2,
@@ -630,14 +666,19 @@ impl DataBase {
                     ?10,
                     ?11,
                     ?12,
-                    ?13
+                    ?13,
+                    ?14,
+                    ?15,
+                    ?16,
+                    ?17,
+                    ?18
                 )",
             ) else {
                 return;
             };
 
             for entry in files.iter_mut() {
-                match stmt.execute((
+                match stmt.execute(params![
                     job_id,
                     entry.file.to_string_lossy(),
                     entry.is_file,
@@ -654,7 +695,12 @@ impl DataBase {
                         .cur_target
                         .as_ref()
                         .map(|cur_target| cur_target.to_string_lossy()),
-                )) {
+                    to_millis(entry.start_time),
+                    to_millis(entry.end_time),
+                    entry.bytes,
+                    entry.error_code,
+                    entry.retries,
+                ]) {
                     Ok(_) => {
                         entry.id = tx.last_insert_rowid();
                         entry.job_id = job_id;
@@ -685,15 +731,29 @@ impl DataBase {
             let Ok(mut stmt) = tx.prepare(
                 "UPDATE files
                 SET status = ?1,
-                    message = ?2
-                WHERE id = ?3",
+                    message = ?2,
+                    start_time = ?3,
+                    end_time = ?4,
+                    bytes = ?5,
+                    error_code = ?6,
+                    retries = ?7
+                WHERE id = ?8",
             ) else {
                 return;
             };
 
             for entry in files.iter() {
                 if stmt
-                    .execute((entry.status, &entry.message, entry.id))
+                    .execute((
+                        entry.status,
+                        &entry.message,
+                        to_millis(entry.start_time),
+                        to_millis(entry.end_time),
+                        entry.bytes,
+                        entry.error_code,
+                        entry.retries,
+                        entry.id,
+                    ))
                     .is_err()
                 {
                     return;
@@ -711,8 +771,10 @@ impl DataBase {
                 message = ?2,
                 target_is_dir = ?3,
                 target_is_symlink = ?4,
-                cur_target = ?5
-            WHERE id = ?6",
+                cur_target = ?5,
+                start_time = ?6,
+                retries = ?7
+            WHERE id = ?8",
         ) {
             let _ = stmt.execute((
                 file.status,
@@ -720,17 +782,35 @@ impl DataBase {
                 file.target_is_dir,
                 file.target_is_symlink,
                 file.cur_target.as_ref().map(|x| x.to_string_lossy()),
+                to_millis(file.start_time),
+                file.retries,
                 file.id,
             ));
         }
     }
 
     pub fn set_file_status(&self, file: &DBFileEntry) {
-        if let Ok(mut stmt) = self
-            .conn
-            .prepare_cached("UPDATE files SET status = ?1, message = ?2 WHERE id = ?3")
-        {
-            let _ = stmt.execute((file.status, &file.message, file.id));
+        if let Ok(mut stmt) = self.conn.prepare_cached(
+            "UPDATE files
+            SET status = ?1,
+                message = ?2,
+                start_time = ?3,
+                end_time = ?4,
+                bytes = ?5,
+                error_code = ?6,
+                retries = ?7
+            WHERE id = ?8",
+        ) {
+            let _ = stmt.execute((
+                file.status,
+                &file.message,
+                to_millis(file.start_time),
+                to_millis(file.end_time),
+                file.bytes,
+                file.error_code,
+                file.retries,
+                file.id,
+            ));
         }
     }
 
@@ -743,6 +823,9 @@ impl DataBase {
                         dir_list.new_dir,
                         dir_list.status,
                         dir_list.message,
+                        dir_list.start_time,
+                        dir_list.end_time,
+                        dir_list.error_code,
                         files.id,
                         files.file,
                         files.is_file,
@@ -755,7 +838,12 @@ impl DataBase {
                         files.message,
                         files.target_is_dir,
                         files.target_is_symlink,
-                        files.cur_target
+                        files.cur_target,
+                        files.start_time,
+                        files.end_time,
+                        files.bytes,
+                        files.error_code,
+                        files.retries
                 FROM dir_list
                 JOIN files ON files.id = dir_list.file_id
                 WHERE dir_list.job_id = ?1
@@ -771,21 +859,29 @@ impl DataBase {
                         new_dir: row.get(3)?,
                         status: row.get(4)?,
                         message: row.get(5)?,
+                        start_time: from_millis(row.get(6)?),
+                        end_time: from_millis(row.get(7)?),
+                        error_code: row.get(8)?,
                         file: DBFileEntry {
-                            id: row.get(6)?,
+                            id: row.get(9)?,
                             job_id,
-                            file: PathBuf::from(row.get::<usize, String>(7)?),
-                            is_file: row.get(8)?,
-                            is_dir: row.get(9)?,
-                            is_symlink: row.get(10)?,
-                            size: row.get(11)?,
-                            uid: row.get(12)?,
-                            gid: row.get(13)?,
-                            status: row.get(14)?,
-                            message: row.get(15)?,
-                            target_is_dir: row.get(16)?,
-                            target_is_symlink: row.get(17)?,
-                            cur_target: row.get::<usize, Option<String>>(18)?.map(PathBuf::from),
+                            file: PathBuf::from(row.get::<usize, String>(10)?),
+                            is_file: row.get(11)?,
+                            is_dir: row.get(12)?,
+                            is_symlink: row.get(13)?,
+                            size: row.get(14)?,
+                            uid: row.get(15)?,
+                            gid: row.get(16)?,
+                            status: row.get(17)?,
+                            message: row.get(18)?,
+                            target_is_dir: row.get(19)?,
+                            target_is_symlink: row.get(20)?,
+                            cur_target: row.get::<usize, Option<String>>(21)?.map(PathBuf::from),
+                            start_time: from_millis(row.get(22)?),
+                            end_time: from_millis(row.get(23)?),
+                            bytes: row.get(24)?,
+                            error_code: row.get(25)?,
+                            retries: row.get(26)?,
                         },
                     })
                 })
@@ -804,7 +900,10 @@ impl DataBase {
                     cur_target,
                     new_dir,
                     status,
-                    message
+                    message,
+                    start_time,
+                    end_time,
+                    error_code
                 ) VALUES (
                     ?1,
                     ?2,
@@ -812,7 +911,10 @@ impl DataBase {
                     ?4,
                     ?5,
                     ?6,
-                    ?7
+                    ?7,
+                    ?8,
+                    ?9,
+                    ?10
                 )",
             )
             .and_then(|mut stmt| {
@@ -824,6 +926,9 @@ impl DataBase {
                     dir_list_entry.new_dir,
                     dir_list_entry.status,
                     &dir_list_entry.message,
+                    to_millis(dir_list_entry.start_time),
+                    to_millis(dir_list_entry.end_time),
+                    dir_list_entry.error_code,
                 ))
             })
             .map(|_| {
@@ -837,13 +942,21 @@ impl DataBase {
     }
 
     pub fn set_dir_list_entry_status(&self, dir_list_entry: &DBDirListEntry) {
-        if let Ok(mut stmt) = self
-            .conn
-            .prepare_cached("UPDATE dir_list SET status = ?1, message = ?2 WHERE id = ?3")
-        {
+        if let Ok(mut stmt) = self.conn.prepare_cached(
+            "UPDATE dir_list
+            SET status = ?1,
+                message = ?2,
+                start_time = ?3,
+                end_time = ?4,
+                error_code = ?5
+            WHERE id = ?6",
+        ) {
             let _ = stmt.execute((
                 dir_list_entry.status,
                 &dir_list_entry.message,
+                to_millis(dir_list_entry.start_time),
+                to_millis(dir_list_entry.end_time),
+                dir_list_entry.error_code,
                 dir_list_entry.id,
             ));
         }
@@ -1,19 +1,16 @@
 use std::{
-    fs,
     os::unix::fs::MetadataExt,
     path::{Path, PathBuf},
     time::Instant,
 };
 
 use anyhow::Result;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::Receiver;
 
-use crate::{
-    app::PubSub,
-    fm::{
-        archive_mounter::{self, ArchiveEntry},
-        cp_mv_rm::database::{DBEntriesEntry, DBFileEntry, DBFileStatus},
-    },
+use crate::engine::{
+    archive::{self, ArchiveEntry},
+    database::{DBEntriesEntry, DBFileEntry, DBFileStatus},
+    fs_ops::{error_code, FsOps},
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -36,14 +33,18 @@ pub struct DirScanInfo {
     pub total_size: Option<u64>,
 }
 
+/// Recursively list the files below `entries`, parents before their children.
+///
+/// Returns `None` if the scan gets aborted.
+#[allow(clippy::too_many_arguments)]
 pub fn dirscan(
     cwd: &Path,
     entries: &[DBEntriesEntry],
     archive_dirs: &[ArchiveEntry],
     read_metadata: ReadMetadata,
     ev_rx: Receiver<DirScanEvent>,
-    info_tx: Sender<DirScanInfo>,
-    pubsub_tx: Sender<PubSub>,
+    on_info: &mut dyn FnMut(DirScanInfo),
+    fs_ops: &dyn FsOps,
 ) -> Option<Vec<DBFileEntry>> {
     let mut result = Vec::new();
 
@@ -81,6 +82,11 @@ pub fn dirscan(
                             target_is_dir: false,
                             target_is_symlink: false,
                             cur_target: None,
+                            start_time: None,
+                            end_time: None,
+                            bytes: 0,
+                            error_code: None,
+                            retries: 0,
                         });
                         info.num_files = 1;
                         info.total_size = match read_metadata {
@@ -103,8 +109,7 @@ pub fn dirscan(
 
         if last_write.elapsed().as_millis() >= 50 {
             last_write = Instant::now();
-            let _ = info_tx.send(info.clone());
-            let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            on_info(info.clone());
         }
 
         result.push(DBFileEntry {
@@ -122,6 +127,11 @@ pub fn dirscan(
             target_is_dir: false,
             target_is_symlink: false,
             cur_target: None,
+            start_time: None,
+            end_time: None,
+            bytes: 0,
+            error_code: None,
+            retries: 0,
         });
 
         if entry.is_dir {
@@ -132,8 +142,8 @@ pub fn dirscan(
                 &mut info,
                 last_write,
                 ev_rx.clone(),
-                info_tx.clone(),
-                pubsub_tx.clone(),
+                on_info,
+                fs_ops,
             ) {
                 Ok(Some((recursive_result, recursive_last_write))) => {
                     if let Some(last_result) = recursive_result.last() {
@@ -150,6 +160,7 @@ pub fn dirscan(
                     let last_result = result.last_mut().unwrap();
                     last_result.message = format!("(dirscan) {}", e);
                     last_result.status = DBFileStatus::Error;
+                    last_result.error_code = error_code(&e);
                 }
             }
         }
@@ -166,8 +177,8 @@ fn recursive_dirscan(
     info: &mut DirScanInfo,
     old_last_write: Instant,
     ev_rx: Receiver<DirScanEvent>,
-    info_tx: Sender<DirScanInfo>,
-    pubsub_tx: Sender<PubSub>,
+    on_info: &mut dyn FnMut(DirScanInfo),
+    fs_ops: &dyn FsOps,
 ) -> Result<Option<(Vec<DBFileEntry>, Instant)>> {
     let mut result = Vec::new();
 
@@ -175,7 +186,7 @@ fn recursive_dirscan(
     let old_total_size = info.total_size;
 
     let mut last_write = old_last_write;
-    for entry in fs::read_dir(archive_mounter::unarchive_path_map(cwd, archive_dirs))? {
+    for entry in fs_ops.read_dir(&archive::unarchive_path_map(cwd, archive_dirs))? {
         if !ev_rx.is_empty() {
             if let Ok(event) = ev_rx.try_recv() {
                 match event {
@@ -199,6 +210,11 @@ fn recursive_dirscan(
                             target_is_dir: false,
                             target_is_symlink: false,
                             cur_target: None,
+                            start_time: None,
+                            end_time: None,
+                            bytes: 0,
+                            error_code: None,
+                            retries: 0,
                         });
                         info.num_files = old_num_files;
                         info.total_size = old_total_size;
@@ -219,10 +235,7 @@ fn recursive_dirscan(
                                 result.push(DBFileEntry {
                                     id: 0,
                                     job_id: 0,
-                                    file: archive_mounter::archive_parent_map(
-                                        &entry.path(),
-                                        archive_dirs,
-                                    ),
+                                    file: archive::archive_parent_map(&entry.path(), archive_dirs),
                                     is_file: false,
                                     is_dir: false,
                                     is_symlink: false,
@@ -234,6 +247,11 @@ fn recursive_dirscan(
                                     target_is_dir: false,
                                     target_is_symlink: false,
                                     cur_target: None,
+                                    start_time: None,
+                                    end_time: None,
+                                    bytes: 0,
+                                    error_code: e.raw_os_error(),
+                                    retries: 0,
                                 });
                                 info.num_files += 1;
                                 continue;
@@ -253,11 +271,10 @@ fn recursive_dirscan(
 
                     if last_write.elapsed().as_millis() >= 50 {
                         last_write = Instant::now();
-                        let _ = info_tx.send(info.clone());
-                        let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+                        on_info(info.clone());
                     }
 
-                    let file = archive_mounter::archive_parent_map(&entry.path(), archive_dirs);
+                    let file = archive::archive_parent_map(&entry.path(), archive_dirs);
 
                     result.push(DBFileEntry {
                         id: 0,
@@ -283,6 +300,11 @@ fn recursive_dirscan(
                         target_is_dir: false,
                         target_is_symlink: false,
                         cur_target: None,
+                        start_time: None,
+                        end_time: None,
+                        bytes: 0,
+                        error_code: None,
+                        retries: 0,
                     });
 
                     if file_type.is_dir() {
@@ -293,8 +315,8 @@ fn recursive_dirscan(
                             info,
                             last_write,
                             ev_rx.clone(),
-                            info_tx.clone(),
-                            pubsub_tx.clone(),
+                            on_info,
+                            fs_ops,
                         ) {
                             Ok(Some((recursive_result, recursive_last_write))) => {
                                 if let Some(last_result) = recursive_result.last() {
@@ -312,6 +334,7 @@ fn recursive_dirscan(
                                 last_result.message = format!("(dirscan) {}", e);
                                 last_result.message = e.to_string();
                                 last_result.status = DBFileStatus::Error;
+                                last_result.error_code = error_code(&e);
                             }
                         }
                     }
@@ -320,7 +343,7 @@ fn recursive_dirscan(
                     result.push(DBFileEntry {
                         id: 0,
                         job_id: 0,
-                        file: archive_mounter::archive_parent_map(&entry.path(), archive_dirs),
+                        file: archive::archive_parent_map(&entry.path(), archive_dirs),
                         is_file: false,
                         is_dir: false,
                         is_symlink: false,
@@ -332,6 +355,11 @@ fn recursive_dirscan(
                         target_is_dir: false,
                         target_is_symlink: false,
                         cur_target: None,
+                        start_time: None,
+                        end_time: None,
+                        bytes: 0,
+                        error_code: e.raw_os_error(),
+                        retries: 0,
                     });
                     info.num_files += 1;
                 }
@@ -0,0 +1,281 @@
+use std::{
+    collections::HashSet,
+    fs::{Metadata, ReadDir},
+    io,
+    os::fd::{AsRawFd, BorrowedFd, OwnedFd, RawFd},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use rustix::{
+    fs::{Advice, FallocateFlags, Mode, OFlags, SeekFrom, Stat},
+    io::Errno,
+};
+
+use crate::engine::fs_ops::{FsOps, RealFs};
+
+pub const CRASH_MESSAGE: &str = "injected crash";
+
+/// A filesystem that forwards everything to `RealFs`, while injecting failures.
+///
+/// Every call counts as a step, and the step given to `crash_at` panics with
+/// `CRASH_MESSAGE` before touching the filesystem, simulating a process that gets
+/// killed at that point.
+#[derive(Debug, Default)]
+pub struct FaultFs {
+    steps: AtomicUsize,
+    crash_at: Option<usize>,
+    enospc_after: Option<u64>,
+    bytes_written: AtomicU64,
+    eio_on_read: Option<PathBuf>,
+    eio_reads: Option<AtomicUsize>,
+    eio_fds: Mutex<HashSet<RawFd>>,
+    eio_on_read_dir: Option<PathBuf>,
+}
+
+impl FaultFs {
+    pub fn new() -> FaultFs {
+        FaultFs::default()
+    }
+
+    pub fn crash_at(mut self, step: usize) -> FaultFs {
+        self.crash_at = Some(step);
+        self
+    }
+
+    pub fn enospc_after(mut self, bytes: u64) -> FaultFs {
+        self.enospc_after = Some(bytes);
+        self
+    }
+
+    pub fn eio_on_read(mut self, file: &Path) -> FaultFs {
+        self.eio_on_read = Some(PathBuf::from(file));
+        self
+    }
+
+    /// Like `eio_on_read`, but only the first `times` reads fail, as it happens with
+    /// the transient errors of network filesystems.
+    pub fn transient_eio_on_read(mut self, file: &Path, times: usize) -> FaultFs {
+        self.eio_on_read = Some(PathBuf::from(file));
+        self.eio_reads = Some(AtomicUsize::new(times));
+        self
+    }
+
+    pub fn eio_on_read_dir(mut self, dir: &Path) -> FaultFs {
+        self.eio_on_read_dir = Some(PathBuf::from(dir));
+        self
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps.load(Ordering::SeqCst)
+    }
+
+    fn step(&self) {
+        let step = self.steps.fetch_add(1, Ordering::SeqCst);
+
+        if self.crash_at == Some(step) {
+            panic!("{}", CRASH_MESSAGE);
+        }
+    }
+
+    fn check_read(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<()> {
+        if !self.eio_fds.lock().unwrap().contains(&fd.as_raw_fd()) {
+            return Ok(());
+        }
+
+        match &self.eio_reads {
+            Some(eio_reads) => {
+                match eio_reads
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                {
+                    Ok(_) => Err(Errno::IO),
+                    Err(_) => Ok(()),
+                }
+            }
+            None => Err(Errno::IO),
+        }
+    }
+
+    fn check_write(&self, len: usize) -> rustix::io::Result<usize> {
+        let Some(limit) = self.enospc_after else {
+            return Ok(len);
+        };
+
+        let bytes_written = self.bytes_written.load(Ordering::SeqCst);
+        match limit.saturating_sub(bytes_written) {
+            0 => Err(Errno::NOSPC),
+            available => Ok(len.min(available as usize)),
+        }
+    }
+
+    fn written(&self, result: rustix::io::Result<usize>) -> rustix::io::Result<usize> {
+        if let Ok(bytes) = result {
+            self.bytes_written.fetch_add(bytes as u64, Ordering::SeqCst);
+        }
+
+        result
+    }
+}
+
+impl FsOps for FaultFs {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.step();
+        RealFs.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.step();
+        RealFs.symlink_metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.step();
+        RealFs.canonicalize(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<ReadDir> {
+        self.step();
+
+        if self.eio_on_read_dir.as_deref() == Some(path) {
+            return Err(io::Error::from_raw_os_error(Errno::IO.raw_os_error()));
+        }
+
+        RealFs.read_dir(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.step();
+        RealFs.read_link(path)
+    }
+
+    fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+        self.step();
+        RealFs.symlink(original, link)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.step();
+        RealFs.create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.step();
+        RealFs.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.step();
+        RealFs.remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.step();
+        RealFs.remove_dir(path)
+    }
+
+    fn lchown(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        self.step();
+        RealFs.lchown(path, uid, gid)
+    }
+
+    fn copystat(&self, src: &Path, dst: &Path) -> rustix::io::Result<()> {
+        self.step();
+        RealFs.copystat(src, dst)
+    }
+
+    fn open(&self, path: &Path, flags: OFlags, mode: Mode) -> rustix::io::Result<OwnedFd> {
+        self.step();
+
+        let fd = RealFs.open(path, flags, mode)?;
+
+        // File descriptors get reused, so forget about the ones that have been closed
+        let mut eio_fds = self.eio_fds.lock().unwrap();
+        match self.eio_on_read.as_deref() == Some(path) {
+            true => eio_fds.insert(fd.as_raw_fd()),
+            false => eio_fds.remove(&fd.as_raw_fd()),
+        };
+
+        Ok(fd)
+    }
+
+    fn fstat(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<Stat> {
+        self.step();
+        RealFs.fstat(fd)
+    }
+
+    fn fsync(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<()> {
+        self.step();
+        RealFs.fsync(fd)
+    }
+
+    fn fallocate(
+        &self,
+        fd: BorrowedFd<'_>,
+        flags: FallocateFlags,
+        offset: u64,
+        len: u64,
+    ) -> rustix::io::Result<()> {
+        self.step();
+        RealFs.fallocate(fd, flags, offset, len)
+    }
+
+    fn fadvise(
+        &self,
+        fd: BorrowedFd<'_>,
+        offset: u64,
+        len: u64,
+        advice: Advice,
+    ) -> rustix::io::Result<()> {
+        self.step();
+        RealFs.fadvise(fd, offset, len, advice)
+    }
+
+    fn seek(&self, fd: BorrowedFd<'_>, pos: SeekFrom) -> rustix::io::Result<u64> {
+        self.step();
+        RealFs.seek(fd, pos)
+    }
+
+    fn copy_file_range(
+        &self,
+        fd_in: BorrowedFd<'_>,
+        fd_out: BorrowedFd<'_>,
+        len: usize,
+    ) -> rustix::io::Result<usize> {
+        self.step();
+        self.check_read(fd_in)?;
+        let len = self.check_write(len)?;
+        self.written(RealFs.copy_file_range(fd_in, fd_out, len))
+    }
+
+    fn sendfile(
+        &self,
+        out_fd: BorrowedFd<'_>,
+        in_fd: BorrowedFd<'_>,
+        count: usize,
+    ) -> rustix::io::Result<usize> {
+        self.step();
+        self.check_read(in_fd)?;
+        let count = self.check_write(count)?;
+        self.written(RealFs.sendfile(out_fd, in_fd, count))
+    }
+
+    fn read(&self, fd: BorrowedFd<'_>, buf: &mut [u8]) -> rustix::io::Result<usize> {
+        self.step();
+        self.check_read(fd)?;
+        RealFs.read(fd, buf)
+    }
+
+    fn write(&self, fd: BorrowedFd<'_>, buf: &[u8]) -> rustix::io::Result<usize> {
+        self.step();
+        let len = self.check_write(buf.len())?;
+        self.written(RealFs.write(fd, &buf[..len]))
+    }
+
+    fn sync(&self) {
+        self.step();
+        RealFs.sync();
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    fs::{self, Metadata, ReadDir},
+    io,
+    os::{
+        fd::{BorrowedFd, OwnedFd},
+        unix::fs::{lchown, symlink},
+    },
+    path::{Path, PathBuf},
+};
+
+use rustix::fs::{Advice, FallocateFlags, Mode, OFlags, SeekFrom, Stat};
+
+use crate::shutil;
+
+/// The filesystem operations performed by the dirscan, copy/move and delete engines.
+///
+/// Every access to the filesystem made by `dirscan`, `cp_mv` and `rm` goes through
+/// this trait, so that the engines can run on top of an implementation that injects
+/// failures, in order to exercise their error and resume paths.
+pub trait FsOps: Send + Sync {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata>;
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    fn read_dir(&self, path: &Path) -> io::Result<ReadDir>;
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+    fn symlink(&self, original: &Path, link: &Path) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+    fn lchown(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()>;
+    fn copystat(&self, src: &Path, dst: &Path) -> rustix::io::Result<()>;
+
+    fn open(&self, path: &Path, flags: OFlags, mode: Mode) -> rustix::io::Result<OwnedFd>;
+    fn fstat(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<Stat>;
+    fn fsync(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<()>;
+    fn fallocate(
+        &self,
+        fd: BorrowedFd<'_>,
+        flags: FallocateFlags,
+        offset: u64,
+        len: u64,
+    ) -> rustix::io::Result<()>;
+    fn fadvise(
+        &self,
+        fd: BorrowedFd<'_>,
+        offset: u64,
+        len: u64,
+        advice: Advice,
+    ) -> rustix::io::Result<()>;
+    fn seek(&self, fd: BorrowedFd<'_>, pos: SeekFrom) -> rustix::io::Result<u64>;
+    fn copy_file_range(
+        &self,
+        fd_in: BorrowedFd<'_>,
+        fd_out: BorrowedFd<'_>,
+        len: usize,
+    ) -> rustix::io::Result<usize>;
+    fn sendfile(
+        &self,
+        out_fd: BorrowedFd<'_>,
+        in_fd: BorrowedFd<'_>,
+        count: usize,
+    ) -> rustix::io::Result<usize>;
+    fn read(&self, fd: BorrowedFd<'_>, buf: &mut [u8]) -> rustix::io::Result<usize>;
+    fn write(&self, fd: BorrowedFd<'_>, buf: &[u8]) -> rustix::io::Result<usize>;
+    fn sync(&self);
+
+    /// Like `Path::exists`, but without following symlinks, so that dangling symlinks exist.
+    fn exists(&self, path: &Path) -> bool {
+        self.symlink_metadata(path).is_ok()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path)
+            .map(|metadata| metadata.is_dir())
+            .unwrap_or(false)
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.symlink_metadata(path)
+            .map(|metadata| metadata.is_symlink())
+            .unwrap_or(false)
+    }
+}
+
+/// The real filesystem, as seen by std and rustix.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FsOps for RealFs {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::symlink_metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<ReadDir> {
+        fs::read_dir(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+        symlink(original, link)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn lchown(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        lchown(path, uid, gid)
+    }
+
+    fn copystat(&self, src: &Path, dst: &Path) -> rustix::io::Result<()> {
+        shutil::copystat(src, dst)
+    }
+
+    fn open(&self, path: &Path, flags: OFlags, mode: Mode) -> rustix::io::Result<OwnedFd> {
+        rustix::fs::open(path, flags, mode)
+    }
+
+    fn fstat(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<Stat> {
+        rustix::fs::fstat(fd)
+    }
+
+    fn fsync(&self, fd: BorrowedFd<'_>) -> rustix::io::Result<()> {
+        rustix::fs::fsync(fd)
+    }
+
+    fn fallocate(
+        &self,
+        fd: BorrowedFd<'_>,
+        flags: FallocateFlags,
+        offset: u64,
+        len: u64,
+    ) -> rustix::io::Result<()> {
+        rustix::fs::fallocate(fd, flags, offset, len)
+    }
+
+    fn fadvise(
+        &self,
+        fd: BorrowedFd<'_>,
+        offset: u64,
+        len: u64,
+        advice: Advice,
+    ) -> rustix::io::Result<()> {
+        rustix::fs::fadvise(fd, offset, len, advice)
+    }
+
+    fn seek(&self, fd: BorrowedFd<'_>, pos: SeekFrom) -> rustix::io::Result<u64> {
+        rustix::fs::seek(fd, pos)
+    }
+
+    fn copy_file_range(
+        &self,
+        fd_in: BorrowedFd<'_>,
+        fd_out: BorrowedFd<'_>,
+        len: usize,
+    ) -> rustix::io::Result<usize> {
+        rustix::fs::copy_file_range(fd_in, None, fd_out, None, len)
+    }
+
+    fn sendfile(
+        &self,
+        out_fd: BorrowedFd<'_>,
+        in_fd: BorrowedFd<'_>,
+        count: usize,
+    ) -> rustix::io::Result<usize> {
+        rustix::fs::sendfile(out_fd, in_fd, None, count)
+    }
+
+    fn read(&self, fd: BorrowedFd<'_>, buf: &mut [u8]) -> rustix::io::Result<usize> {
+        rustix::io::read(fd, buf)
+    }
+
+    fn write(&self, fd: BorrowedFd<'_>, buf: &[u8]) -> rustix::io::Result<usize> {
+        rustix::io::write(fd, buf)
+    }
+
+    fn sync(&self) {
+        rustix::fs::sync();
+    }
+}
+
+/// Returns the errno of the first OS error found in the chain of `e`, if any.
+pub fn error_code(e: &anyhow::Error) -> Option<i32> {
+    e.chain().find_map(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .and_then(|e| e.raw_os_error())
+            .or_else(|| {
+                cause
+                    .downcast_ref::<rustix::io::Errno>()
+                    .map(|e| e.raw_os_error())
+            })
+    })
+}
@@ -0,0 +1,11 @@
+pub mod archive;
+pub mod cp_mv;
+pub mod database;
+pub mod dirscan;
+#[cfg(test)]
+pub mod fault_fs;
+pub mod fs_ops;
+pub mod rm;
+
+#[cfg(test)]
+mod tests;
@@ -1,20 +1,15 @@
 use std::{
-    fs,
     io::ErrorKind,
     path::PathBuf,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::Receiver;
 
-use rustix::fs::sync;
-
-use crate::{
-    app::PubSub,
-    fm::{
-        archive_mounter::{unarchive_parent_map, ArchiveEntry},
-        cp_mv_rm::database::{DBFileEntry, DBFileStatus, DBJobStatus},
-    },
+use crate::engine::{
+    archive::{unarchive_parent_map, ArchiveEntry},
+    database::{DBFileEntry, DBFileStatus, DBJobStatus},
+    fs_ops::FsOps,
 };
 
 #[derive(Debug, Clone)]
@@ -31,12 +26,13 @@ pub struct RmInfo {
     pub total_time: Duration,
 }
 
+/// Delete the files listed by `dirscan`, children before their parents.
 pub fn rm(
     entries: &[DBFileEntry],
     ev_rx: Receiver<RmEvent>,
-    info_tx: Sender<RmInfo>,
-    pubsub_tx: Sender<PubSub>,
+    on_info: &mut dyn FnMut(RmInfo),
     archive_dirs: &[ArchiveEntry],
+    fs_ops: &dyn FsOps,
 ) -> (Vec<DBFileEntry>, DBJobStatus) {
     let mut job_status_result = DBJobStatus::InProgress;
 
@@ -89,15 +85,16 @@ pub fn rm(
         if last_write.elapsed().as_millis() >= 50 {
             last_write = Instant::now();
             info.total_time = last_write.duration_since(start);
-            let _ = info_tx.send(info.clone());
-            let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            on_info(info.clone());
         }
 
         let actual_file = unarchive_parent_map(&entry.file, archive_dirs);
 
+        entry.start_time = Some(SystemTime::now());
+
         let rm_result = match entry.is_dir {
-            true => fs::remove_dir(&actual_file),
-            false => fs::remove_file(&actual_file),
+            true => fs_ops.remove_dir(&actual_file),
+            false => fs_ops.remove_file(&actual_file),
         };
 
         match rm_result {
@@ -111,11 +108,14 @@ pub fn rm(
                     _ => {
                         entry.message = format!("(rm) {}", e);
                         entry.status = DBFileStatus::Error;
+                        entry.error_code = e.raw_os_error();
                     }
                 }
             }
         }
 
+        entry.end_time = Some(SystemTime::now());
+
         info.num_files += 1;
     }
 
@@ -123,7 +123,7 @@ pub fn rm(
         job_status_result = DBJobStatus::Done;
     }
 
-    sync();
+    fs_ops.sync();
 
     (file_list, job_status_result)
 }
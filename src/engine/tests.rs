@@ -0,0 +1,545 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    os::unix::fs::{symlink, MetadataExt, PermissionsExt},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Once,
+    time::Duration,
+};
+
+use tempfile::TempDir;
+
+use crate::engine::{
+    cp_mv::{cp_mv, RetryPolicy},
+    database::{
+        DBEntriesEntry, DBFileEntry, DBFileStatus, DBJobEntry, DBJobOperation, DBJobStatus,
+        DataBase, OnConflict,
+    },
+    dirscan::{dirscan, ReadMetadata},
+    fault_fs::{FaultFs, CRASH_MESSAGE},
+    fs_ops::{FsOps, RealFs},
+    rm::rm,
+};
+
+const BLOCK_SIZE: usize = 128 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    File(Vec<u8>),
+    Dir,
+    Symlink(PathBuf),
+}
+
+type Tree = BTreeMap<PathBuf, Node>;
+
+struct Job {
+    _temp_dir: TempDir,
+    src: PathBuf,
+    dst: PathBuf,
+    db_file: PathBuf,
+    job: DBJobEntry,
+    files: Vec<DBFileEntry>,
+    expected: Tree,
+    retry_policy: RetryPolicy,
+}
+
+fn silence_injected_crashes() {
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if info.payload().downcast_ref::<String>().map(String::as_str) != Some(CRASH_MESSAGE) {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+fn contents(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8 ^ seed).collect()
+}
+
+fn make_source_tree(src: &Path) {
+    fs::create_dir_all(src.join("a/sub")).unwrap();
+    fs::create_dir_all(src.join("a/empty")).unwrap();
+    fs::write(src.join("a/big.bin"), contents(3 * BLOCK_SIZE + 1000, 1)).unwrap();
+    fs::write(src.join("a/small.txt"), b"small").unwrap();
+    fs::write(src.join("a/sub/nested.bin"), contents(BLOCK_SIZE + 10, 2)).unwrap();
+    symlink("small.txt", src.join("a/link")).unwrap();
+    fs::write(src.join("top.txt"), b"top").unwrap();
+}
+
+fn snapshot(root: &Path) -> Tree {
+    fn walk(root: &Path, dir: &Path, tree: &mut Tree) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let rel_path = path.strip_prefix(root).unwrap().to_path_buf();
+            let metadata = fs::symlink_metadata(&path).unwrap();
+
+            if metadata.is_symlink() {
+                tree.insert(rel_path, Node::Symlink(fs::read_link(&path).unwrap()));
+            } else if metadata.is_dir() {
+                tree.insert(rel_path, Node::Dir);
+                walk(root, &path, tree);
+            } else {
+                tree.insert(rel_path, Node::File(fs::read(&path).unwrap()));
+            }
+        }
+    }
+
+    let mut tree = Tree::new();
+    walk(root, root, &mut tree);
+    tree
+}
+
+fn new_job(operation: DBJobOperation, prepare_dst: fn(&Path)) -> Job {
+    new_job_with_files(operation, prepare_dst, &[])
+}
+
+/// Like `new_job`, but with the given files relative to the source directory, instead of all the
+/// top level entries
+fn new_job_with_files(
+    operation: DBJobOperation,
+    prepare_dst: fn(&Path),
+    rel_files: &[&str],
+) -> Job {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let src = temp_dir.path().join("src");
+    let dst = temp_dir.path().join("dst");
+    let db_file = temp_dir.path().join("fcd.db");
+
+    make_source_tree(&src);
+    fs::create_dir(&dst).unwrap();
+    prepare_dst(&dst);
+
+    let expected = snapshot(&src);
+
+    let files: Vec<PathBuf> = match rel_files.is_empty() {
+        true => fs::read_dir(&src)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect(),
+        false => rel_files.iter().map(|file| src.join(file)).collect(),
+    };
+
+    let mut entries: Vec<DBEntriesEntry> = files
+        .into_iter()
+        .map(|file| {
+            let lstat = fs::symlink_metadata(&file).unwrap();
+
+            DBEntriesEntry {
+                id: 0,
+                job_id: 0,
+                file,
+                is_file: lstat.is_file(),
+                is_dir: lstat.is_dir(),
+                is_symlink: lstat.is_symlink(),
+                size: lstat.len(),
+                uid: lstat.uid(),
+                gid: lstat.gid(),
+            }
+        })
+        .collect();
+    entries.sort_unstable_by(|a, b| a.file.cmp(&b.file));
+
+    let mut job = DBJobEntry {
+        id: 0,
+        pid: std::process::id(),
+        operation,
+        cwd: src.clone(),
+        dest: match operation {
+            DBJobOperation::Rm => None,
+            _ => Some(dst.clone()),
+        },
+        on_conflict: match operation {
+            DBJobOperation::Rm => None,
+            _ => Some(OnConflict::Overwrite),
+        },
+        replace_first_path: false,
+        status: DBJobStatus::Dirscan,
+        entries,
+        archives: Vec::new(),
+    };
+
+    let mut db = DataBase::new(&db_file).unwrap();
+    db.new_job(&mut job);
+
+    let mut files = scan(&job, &RealFs);
+    db.set_file_list(job.id, &mut files);
+    job.status = DBJobStatus::InProgress;
+
+    Job {
+        _temp_dir: temp_dir,
+        src,
+        dst,
+        db_file,
+        job,
+        files,
+        expected,
+        retry_policy: RetryPolicy {
+            max_retries: 0,
+            backoff: Duration::ZERO,
+        },
+    }
+}
+
+fn scan(job: &DBJobEntry, fs_ops: &dyn FsOps) -> Vec<DBFileEntry> {
+    let (_ev_tx, ev_rx) = crossbeam_channel::unbounded();
+
+    let read_metadata = match job.operation {
+        DBJobOperation::Rm => ReadMetadata::No,
+        _ => ReadMetadata::Yes,
+    };
+
+    dirscan(
+        &job.cwd,
+        &job.entries,
+        &[],
+        read_metadata,
+        ev_rx,
+        &mut |_info| {},
+        fs_ops,
+    )
+    .unwrap()
+}
+
+impl Job {
+    fn run(&self, files: &[DBFileEntry], fs_ops: &dyn FsOps) -> (Vec<DBFileEntry>, DBJobStatus) {
+        match self.job.operation {
+            DBJobOperation::Cp | DBJobOperation::Mv => {
+                let (_ev_tx, ev_rx) = crossbeam_channel::unbounded();
+
+                let result = cp_mv(
+                    self.job.id,
+                    self.job.operation,
+                    &self.job.cwd,
+                    self.job.dest.as_deref().unwrap(),
+                    self.job.on_conflict.unwrap(),
+                    self.job.replace_first_path,
+                    files,
+                    ev_rx,
+                    &mut |_info| {},
+                    Some(&self.db_file),
+                    &[],
+                    self.retry_policy,
+                    fs_ops,
+                );
+
+                let mut files = result.files;
+                files.extend(result.dirs.into_iter().map(|dir| DBFileEntry {
+                    status: dir.status,
+                    message: dir.message,
+                    ..dir.file
+                }));
+
+                (files, result.status)
+            }
+            DBJobOperation::Rm => {
+                let (_ev_tx, ev_rx) = crossbeam_channel::unbounded();
+
+                let (files, status) = rm(files, ev_rx, &mut |_info| {}, &[], fs_ops);
+
+                let mut db = DataBase::new(&self.db_file).unwrap();
+                db.update_file_list(&files);
+                db.set_job_status(self.job.id, status);
+
+                (files, status)
+            }
+        }
+    }
+
+    fn resume(&self) -> (Vec<DBFileEntry>, DBJobStatus) {
+        let files = DataBase::new(&self.db_file)
+            .unwrap()
+            .get_file_list(self.job.id);
+
+        self.run(&files, &RealFs)
+    }
+
+    fn retry_errors(&self) -> (Vec<DBFileEntry>, DBJobStatus) {
+        let db = DataBase::new(&self.db_file).unwrap();
+
+        let mut files = db.get_file_list(self.job.id);
+        for file in files.iter_mut() {
+            if let DBFileStatus::Error = file.status {
+                file.status = match file.cur_target {
+                    Some(_) => DBFileStatus::InProgress,
+                    None => DBFileStatus::ToDo,
+                };
+                file.message.clear();
+                db.update_file(file);
+            }
+        }
+
+        self.run(&files, &RealFs)
+    }
+
+    fn assert_final_tree(&self) {
+        match self.job.operation {
+            DBJobOperation::Cp => {
+                assert_eq!(snapshot(&self.src), self.expected);
+                assert_eq!(snapshot(&self.dst), self.expected);
+            }
+            DBJobOperation::Mv => {
+                assert_eq!(snapshot(&self.src), Tree::new());
+                assert_eq!(snapshot(&self.dst), self.expected);
+            }
+            DBJobOperation::Rm => {
+                assert_eq!(snapshot(&self.src), Tree::new());
+            }
+        }
+    }
+}
+
+fn assert_no_errors(files: &[DBFileEntry], status: DBJobStatus) {
+    assert!(matches!(status, DBJobStatus::Done), "{:?}", status);
+
+    for file in files {
+        assert!(
+            matches!(file.status, DBFileStatus::Done),
+            "{}: {:?} {}",
+            file.file.display(),
+            file.status,
+            file.message
+        );
+    }
+}
+
+fn crash_at_every_step(operation: DBJobOperation, prepare_dst: fn(&Path)) {
+    silence_injected_crashes();
+
+    let num_steps = {
+        let job = new_job(operation, prepare_dst);
+        let fault_fs = FaultFs::new();
+        let (files, status) = job.run(&job.files, &fault_fs);
+        assert_no_errors(&files, status);
+        job.assert_final_tree();
+
+        fault_fs.steps()
+    };
+
+    assert!(num_steps > 0);
+
+    for step in 0..num_steps {
+        let job = new_job(operation, prepare_dst);
+        let fault_fs = FaultFs::new().crash_at(step);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| job.run(&job.files, &fault_fs)));
+        assert!(result.is_err(), "no crash at step {}", step);
+
+        let (files, status) = job.resume();
+        assert_no_errors(&files, status);
+        job.assert_final_tree();
+    }
+}
+
+#[test]
+fn cp_resumes_after_crash_at_every_step() {
+    crash_at_every_step(DBJobOperation::Cp, |_dst| {});
+}
+
+#[test]
+fn mv_resumes_after_crash_at_every_step() {
+    crash_at_every_step(DBJobOperation::Mv, |_dst| {});
+}
+
+#[test]
+fn mv_into_existing_dir_resumes_after_crash_at_every_step() {
+    crash_at_every_step(DBJobOperation::Mv, |dst| {
+        fs::create_dir_all(dst.join("a/sub")).unwrap();
+    });
+}
+
+#[test]
+fn cp_creates_missing_parent_dirs() {
+    let job = new_job_with_files(
+        DBJobOperation::Cp,
+        |_dst| {},
+        &["a/small.txt", "a/sub/nested.bin"],
+    );
+
+    let (files, status) = job.run(&job.files, &RealFs);
+    assert_no_errors(&files, status);
+
+    let expected: Tree = job
+        .expected
+        .iter()
+        .filter(|(path, _node)| {
+            ["a", "a/small.txt", "a/sub", "a/sub/nested.bin"]
+                .iter()
+                .any(|file| path.as_path() == Path::new(file))
+        })
+        .map(|(path, node)| (path.clone(), node.clone()))
+        .collect();
+
+    assert_eq!(snapshot(&job.dst), expected);
+}
+
+#[test]
+fn mv_creates_missing_parent_dirs_like_the_source() {
+    let job = new_job_with_files(DBJobOperation::Mv, |_dst| {}, &["a/sub/nested.bin"]);
+
+    fs::set_permissions(job.src.join("a/sub"), fs::Permissions::from_mode(0o700)).unwrap();
+
+    let (files, status) = job.run(&job.files, &RealFs);
+    assert_no_errors(&files, status);
+
+    let mode = |dir: &Path| fs::metadata(dir).unwrap().mode() & 0o7777;
+    assert_eq!(mode(&job.dst.join("a/sub")), 0o700);
+    assert_eq!(mode(&job.dst.join("a")), mode(&job.src.join("a")));
+
+    // The emptied parent directories are removed, the others are kept
+    assert!(!job.src.join("a/sub").exists());
+    assert!(job.src.join("a/small.txt").exists());
+    assert!(job.dst.join("a/sub/nested.bin").exists());
+}
+
+#[test]
+fn retried_file_starts_over_in_the_renamed_dir() {
+    let mut job = new_job(DBJobOperation::Cp, |dst| {
+        fs::write(dst.join("a"), b"not a dir").unwrap();
+    });
+    job.job.on_conflict = Some(OnConflict::RenameCopy);
+
+    let (files, status) = job.run(&job.files, &RealFs);
+    assert_no_errors(&files, status);
+
+    let renamed_file = job.dst.join("a.fcdnew0/small.txt");
+    assert!(renamed_file.exists());
+    fs::remove_file(&renamed_file).unwrap();
+
+    // As retried after an error, before the target was chosen, with the target of the
+    // renamed directory
+    let mut small = files
+        .iter()
+        .find(|file| file.file.ends_with("a/small.txt"))
+        .unwrap()
+        .clone();
+    small.status = DBFileStatus::ToDo;
+
+    let (files, status) = job.run(&[small], &RealFs);
+    assert_no_errors(&files, status);
+    assert_eq!(fs::read(&renamed_file).unwrap(), b"small");
+}
+
+#[test]
+fn rm_resumes_after_crash_at_every_step() {
+    crash_at_every_step(DBJobOperation::Rm, |_dst| {});
+}
+
+#[test]
+fn cp_resumes_after_enospc() {
+    let job = new_job(DBJobOperation::Cp, |_dst| {});
+
+    let fault_fs = FaultFs::new().enospc_after(2 * BLOCK_SIZE as u64);
+    let (files, status) = job.run(&job.files, &fault_fs);
+    assert!(matches!(status, DBJobStatus::Done));
+
+    let big = files
+        .iter()
+        .find(|file| file.file.ends_with("a/big.bin"))
+        .unwrap();
+    assert!(matches!(big.status, DBFileStatus::Error));
+    assert!(big.message.starts_with("(write)"), "{}", big.message);
+
+    let (files, status) = job.retry_errors();
+    assert_no_errors(&files, status);
+    job.assert_final_tree();
+}
+
+#[test]
+fn cp_resumes_after_eio_on_read() {
+    let job = new_job(DBJobOperation::Cp, |_dst| {});
+
+    let fault_fs = FaultFs::new().eio_on_read(&job.src.join("a/sub/nested.bin"));
+    let (files, status) = job.run(&job.files, &fault_fs);
+    assert!(matches!(status, DBJobStatus::Done));
+
+    for file in files.iter() {
+        match file.file.ends_with("a/sub/nested.bin") {
+            true => {
+                assert!(matches!(file.status, DBFileStatus::Error));
+                assert!(file.message.starts_with("(read)"), "{}", file.message);
+                assert_eq!(file.error_code, Some(libc::EIO));
+            }
+            false => assert!(matches!(file.status, DBFileStatus::Done)),
+        }
+    }
+
+    let (files, status) = job.retry_errors();
+    assert_no_errors(&files, status);
+    job.assert_final_tree();
+}
+
+#[test]
+fn cp_retries_transient_eio_on_read() {
+    let mut job = new_job(DBJobOperation::Cp, |_dst| {});
+    job.retry_policy = RetryPolicy {
+        max_retries: 3,
+        backoff: Duration::from_millis(1),
+    };
+
+    // Every attempt fails 3 times, as copy_file_range and sendfile fall back to the next
+    // copy method on error, so this fails the first 2 attempts
+    let fault_fs = FaultFs::new().transient_eio_on_read(&job.src.join("a/big.bin"), 6);
+    let (files, status) = job.run(&job.files, &fault_fs);
+    assert_no_errors(&files, status);
+    job.assert_final_tree();
+
+    let big = files
+        .iter()
+        .find(|file| file.file.ends_with("a/big.bin"))
+        .unwrap();
+    assert_eq!(big.retries, 2);
+    assert_eq!(big.message, "Retried 2 times");
+
+    let db = DataBase::new(&job.db_file).unwrap();
+    let big = db
+        .get_file_list(job.job.id)
+        .into_iter()
+        .find(|file| file.file.ends_with("a/big.bin"))
+        .unwrap();
+    assert_eq!(big.retries, 2);
+}
+
+#[test]
+fn cp_gives_up_after_max_retries() {
+    let mut job = new_job(DBJobOperation::Cp, |_dst| {});
+    job.retry_policy = RetryPolicy {
+        max_retries: 2,
+        backoff: Duration::from_millis(1),
+    };
+
+    let fault_fs = FaultFs::new().eio_on_read(&job.src.join("a/big.bin"));
+    let (files, status) = job.run(&job.files, &fault_fs);
+    assert!(matches!(status, DBJobStatus::Done));
+
+    let big = files
+        .iter()
+        .find(|file| file.file.ends_with("a/big.bin"))
+        .unwrap();
+    assert!(matches!(big.status, DBFileStatus::Error));
+    assert_eq!(big.retries, 2);
+    assert!(big.message.ends_with("Retried 2 times"), "{}", big.message);
+}
+
+#[test]
+fn dirscan_reports_eio_on_read_dir() {
+    let job = new_job(DBJobOperation::Cp, |_dst| {});
+
+    let fault_fs = FaultFs::new().eio_on_read_dir(&job.src.join("a/sub"));
+    let files = scan(&job.job, &fault_fs);
+
+    let sub = files
+        .iter()
+        .find(|file| file.file.ends_with("a/sub"))
+        .unwrap();
+    assert!(matches!(sub.status, DBFileStatus::Error));
+    assert_eq!(sub.error_code, Some(libc::EIO));
+    assert!(!files
+        .iter()
+        .any(|file| file.file.ends_with("a/sub/nested.bin")));
+    assert!(files.iter().any(|file| file.file.ends_with("a/big.bin")));
+}
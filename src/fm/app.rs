@@ -1,6 +1,7 @@
 use std::{
     cell::RefCell,
     cmp::max,
+    collections::HashSet,
     env, fs,
     io::{self, Write},
     os::unix::fs::MetadataExt,
@@ -8,7 +9,8 @@ use std::{
     process,
     process::Command,
     rc::Rc,
-    time::SystemTime,
+    slice, thread,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::Result;
@@ -24,6 +26,8 @@ use signal_hook::consts::signal::*;
 use unicode_normalization::UnicodeNormalization;
 use uzers::{get_current_uid, get_effective_uid, get_user_by_uid, os::unix::UserExt};
 
+use fcd::shutil::{expanduser, which};
+
 use crate::{
     app::{self, start_inputs, Action, Events, Inputs, PubSub},
     button_bar::ButtonBar,
@@ -40,28 +44,43 @@ use crate::{
             filter::Filter,
             leader::Leader,
         },
+        compare_dirs::compare_dirs,
         cp_mv_rm::{
+            cp_mv::RetryPolicy,
             database::{
-                DBEntriesEntry, DBFileStatus, DBJobEntry, DBJobOperation, DBJobStatus, DataBase,
-                OnConflict,
+                DBDirListEntry, DBEntriesEntry, DBFileEntry, DBFileStatus, DBJobEntry,
+                DBJobOperation, DBJobStatus, DataBase, OnConflict,
             },
             dlg_cp_mv::DlgCpMv,
             dlg_cp_mv_progress::DlgCpMvProgress,
             dlg_dirscan::DlgDirscan,
+            dlg_hook::DlgHook,
             dlg_pending_job::DlgPendingJob,
             dlg_question::DlgQuestion,
-            dlg_report::DlgReport,
+            dlg_report::{DlgReport, Report, ReportFormat},
             dlg_rm_progress::DlgRmProgress,
+            hooks::{Hook, HookError, PreJobNext},
+            notify::notify_job_completed,
         },
+        dir_history::DirHistory,
+        dir_sorts::DirSorts,
+        dlg_basket::DlgBasket,
+        dlg_dir_list::DlgDirList,
         dlg_fzf::DlgFzf,
         dlg_mount_archive::DlgMountArchive,
+        dlg_panelize::DlgPanelize,
+        dlg_tag::DlgTag,
         entry::Entry,
-        file_panel::FilePanel,
+        frecency::Frecency,
+        miller::Miller,
         panel::PanelComponent,
         quickview::QuickView,
+        session::Session,
+        tabs::{PanelTabsState, Tabs},
+        tag_criteria::{matching_files, TagCriteria},
+        tag_sets::TagSets,
     },
     palette::Palette,
-    shutil::{expanduser, which},
     template,
     terminal_restorer::{ENTER_MOUSE_SEQUENCE, EXIT_MOUSE_SEQUENCE},
     viewer::{
@@ -106,13 +125,21 @@ pub struct App {
     quickviewer_position: usize,
     printwd: Option<PathBuf>,
     db_file: Option<PathBuf>,
+    session_file: Option<PathBuf>,
+    dir_history: DirHistory,
+    frecency: Frecency,
+    tag_sets: Rc<RefCell<TagSets>>,
+    tag_criteria: TagCriteria,
+    basket: Rc<RefCell<Vec<PathBuf>>>,
     vertical: bool,
+    miller: Option<Miller>,
     tabsize: u8,
     ctrl_o: bool,
     archive_mounter_command_tx: Option<Sender<ArchiveMounterCommand>>,
     pending_jobs: Vec<DBJobEntry>,
     pending_job: Option<DBJobEntry>,
     pending_archives: Vec<PathBuf>,
+    queued_dirscans: Vec<PubSub>,
     panel_rects: Vec<Rect>,
     command_bar_rect: Rect,
     button_bar_rect: Rect,
@@ -125,17 +152,25 @@ impl App {
         palette: &Rc<Palette>,
         events_tx: &Sender<Events>,
         bookmarks: &Rc<RefCell<Bookmarks>>,
+        dir_sorts: &Rc<RefCell<DirSorts>>,
         raw_output: &Rc<RawTerminal<io::Stdout>>,
         stop_inputs_tx: &Sender<Inputs>,
         stop_inputs_rx: &Receiver<Inputs>,
         initial_path: &Path,
         printwd: Option<&Path>,
         db_file: Option<&Path>,
+        session_file: Option<&Path>,
+        history_file: Option<&Path>,
+        frecency_file: Option<&Path>,
+        tag_sets_file: Option<&Path>,
+        restore_session: bool,
         vertical: bool,
         tabsize: u8,
     ) -> Result<App> {
         let (pubsub_tx, pubsub_rx) = crossbeam_channel::unbounded();
 
+        let session = session_file.and_then(Session::load).unwrap_or_default();
+
         let archive_mounter_command_tx = archive_mounter::start();
 
         let pending_jobs: Vec<DBJobEntry> = db_file
@@ -145,7 +180,7 @@ impl App {
 
         pubsub_tx.send(PubSub::NextPendingJob).unwrap();
 
-        Ok(App {
+        let mut app = App {
             config: Rc::clone(config),
             palette: Rc::clone(palette),
             events_tx: events_tx.clone(),
@@ -155,31 +190,37 @@ impl App {
             stop_inputs_tx: stop_inputs_tx.clone(),
             stop_inputs_rx: stop_inputs_rx.clone(),
             panels: vec![
-                Box::new(FilePanel::new(
+                Box::new(Tabs::new(
                     palette,
                     events_tx,
                     bookmarks,
+                    dir_sorts,
                     raw_output,
                     stop_inputs_tx,
                     stop_inputs_rx,
                     pubsub_tx.clone(),
-                    &config.options.opener,
+                    config,
                     initial_path,
                     archive_mounter_command_tx.clone(),
                     Focus::Focused,
+                    session.panels.first(),
+                    restore_session,
                 )),
-                Box::new(FilePanel::new(
+                Box::new(Tabs::new(
                     palette,
                     events_tx,
                     bookmarks,
+                    dir_sorts,
                     raw_output,
                     stop_inputs_tx,
                     stop_inputs_rx,
                     pubsub_tx.clone(),
-                    &config.options.opener,
+                    config,
                     initial_path,
                     archive_mounter_command_tx.clone(),
                     Focus::Normal,
+                    session.panels.get(1),
+                    restore_session,
                 )),
                 Box::new(QuickView::new(
                     palette,
@@ -196,17 +237,31 @@ impl App {
             quickviewer_position: 2,
             printwd: printwd.map(PathBuf::from),
             db_file: db_file.map(PathBuf::from),
+            session_file: session_file.map(PathBuf::from),
+            dir_history: DirHistory::new(history_file),
+            frecency: Frecency::new(frecency_file),
+            tag_sets: Rc::new(RefCell::new(TagSets::new(tag_sets_file))),
+            tag_criteria: TagCriteria::default(),
+            basket: Rc::new(RefCell::new(Vec::new())),
             vertical,
+            miller: None,
             tabsize,
             ctrl_o: false,
             archive_mounter_command_tx,
             pending_jobs,
             pending_job: None,
             pending_archives: Vec::new(),
+            queued_dirscans: Vec::new(),
             panel_rects: vec![Rect::default(), Rect::default()],
             command_bar_rect: Rect::default(),
             button_bar_rect: Rect::default(),
-        })
+        };
+
+        if restore_session {
+            app.restore_session(&session);
+        }
+
+        Ok(app)
     }
 
     fn handle_event(&mut self, event: &Events) -> Action {
@@ -262,6 +317,10 @@ impl App {
                                             self.panels[self.panel_focus_position].get_shown_cwd()
                                         };
 
+                                        // Save the session while the archive paths can still be
+                                        // resolved
+                                        self.save_session();
+
                                         if let Some(command_tx) = &self.archive_mounter_command_tx {
                                             archive_mounter::umount_all(command_tx);
                                         }
@@ -295,6 +354,8 @@ impl App {
 
                                         self.panels[self.panel_focus_position]
                                             .change_focus(Focus::Focused);
+
+                                        self.update_miller();
                                     }
                                     Key::Char('\t') => {
                                         self.panels[self.panel_focus_position]
@@ -305,6 +366,8 @@ impl App {
 
                                         self.panels[self.panel_focus_position]
                                             .change_focus(Focus::Focused);
+
+                                        self.update_miller();
                                     }
                                     Key::Ctrl('u') => {
                                         // This assumes that there are always 2 panels visible
@@ -314,39 +377,16 @@ impl App {
                                         if self.quickviewer_position < 2 {
                                             self.quickviewer_position ^= 1;
                                         }
+
+                                        self.update_miller();
                                     }
                                     Key::Alt('q') => {
-                                        // This assumes that there are always 2 panels visible
-                                        if self.quickviewer_position < 2 {
-                                            let quickviewer_position = self.quickviewer_position;
-
-                                            if self.panel_focus_position == quickviewer_position {
-                                                self.panels[self.panel_focus_position]
-                                                    .change_focus(Focus::Normal);
-                                            }
-
-                                            self.panels.swap(self.quickviewer_position, 2);
-
-                                            self.quickviewer_position = 2;
-
-                                            if self.panel_focus_position == quickviewer_position {
-                                                self.panels[self.panel_focus_position]
-                                                    .change_focus(Focus::Focused);
-                                            }
-                                        } else {
-                                            self.quickviewer_position =
-                                                self.panel_focus_position ^ 1;
-
-                                            self.panels.swap(self.quickviewer_position, 2);
+                                        // The Miller columns layout has its own preview
+                                        if self.miller.is_none() {
+                                            self.toggle_quickview();
                                         }
-
-                                        self.pubsub_tx
-                                            .send(PubSub::ToggleQuickView(
-                                                self.panels[self.panel_focus_position]
-                                                    .get_selected_entry(),
-                                            ))
-                                            .unwrap();
                                     }
+                                    Key::Alt('m') => self.toggle_miller(),
                                     Key::Alt('i') => {
                                         // This assumes that there are always 2 panels visible
                                         let other_panel = match self.quickviewer_position {
@@ -387,6 +427,56 @@ impl App {
                                         }
                                     }
                                     Key::Alt('v') => self.vertical = !self.vertical,
+                                    Key::Alt('h') => {
+                                        let dirs = self.dir_history.get();
+                                        let pubsub_tx = self.pubsub_tx.clone();
+
+                                        // The directories may be on slow or unreachable mounts
+                                        thread::spawn(move || {
+                                            let dirs = dirs
+                                                .into_iter()
+                                                .filter(|dir| dir.is_dir())
+                                                .collect();
+
+                                            let _ = pubsub_tx.send(PubSub::HistoryDirs(dirs));
+                                        });
+                                    }
+                                    Key::Alt('j') => {
+                                        let dirs = self.frecency.get();
+                                        let pubsub_tx = self.pubsub_tx.clone();
+
+                                        // The directories may be on slow or unreachable mounts
+                                        thread::spawn(move || {
+                                            let (dirs, missing_dirs) =
+                                                dirs.into_iter().partition(|dir| dir.is_dir());
+
+                                            let _ = pubsub_tx
+                                                .send(PubSub::FrecentDirs(dirs, missing_dirs));
+                                        });
+                                    }
+                                    Key::Alt('a') => {
+                                        let mut basket = self.basket.borrow_mut();
+
+                                        for entry in self.panels[self.panel_focus_position]
+                                            .get_selected_files()
+                                        {
+                                            if !basket.contains(&entry.file) {
+                                                basket.push(entry.file);
+                                            }
+                                        }
+                                    }
+                                    Key::Alt('b') => {
+                                        // The files moved or deleted in the meantime are dropped
+                                        self.basket
+                                            .borrow_mut()
+                                            .retain(|file| file.symlink_metadata().is_ok());
+
+                                        self.dialog = Some(Box::new(DlgBasket::new(
+                                            &self.palette,
+                                            self.pubsub_tx.clone(),
+                                            &self.basket,
+                                        )));
+                                    }
                                     _ => {
                                         #[cfg(debug_assertions)]
                                         log::debug!("{:?}", key);
@@ -488,6 +578,18 @@ impl App {
             dlg.handle_pubsub(pubsub);
         }
 
+        if let Some(miller) = &mut self.miller {
+            miller.handle_pubsub(pubsub);
+        }
+
+        if let PubSub::SelectedEntry(_)
+        | PubSub::ChangedDirectory(_)
+        | PubSub::TabSwitched
+        | PubSub::Reload = pubsub
+        {
+            self.update_miller();
+        }
+
         match pubsub {
             PubSub::Error(msg, next_action) => {
                 self.dialog = Some(Box::new(DlgError::new(
@@ -619,6 +721,64 @@ impl App {
                     1,
                 )));
             }
+            PubSub::DlgTag => {
+                self.dialog = Some(Box::new(DlgTag::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    &self.tag_sets,
+                    &self.tag_criteria,
+                )));
+            }
+            PubSub::TagMatching(criteria) | PubSub::UntagMatching(criteria) => {
+                self.tag_criteria = criteria.clone();
+
+                // This assumes that there are always 2 panels visible
+                let other_panel = match self.quickviewer_position {
+                    2 => self.panel_focus_position ^ 1,
+                    _ => 2,
+                };
+
+                let panel = &self.panels[self.panel_focus_position];
+                let other_panel = &self.panels[other_panel];
+
+                let other_file_list = other_panel.get_cwd().zip(other_panel.get_file_list());
+
+                let result = match panel.get_cwd().zip(panel.get_file_list()) {
+                    Some(_) if criteria.newer && other_file_list.is_none() => Err(String::from(
+                        "The other panel must show a directory listing",
+                    )),
+                    Some((cwd, entries)) => matching_files(
+                        criteria,
+                        &cwd,
+                        &entries,
+                        other_file_list.as_ref().map(|(other_cwd, other_entries)| {
+                            (other_cwd.as_path(), other_entries.as_slice())
+                        }),
+                    ),
+                    None => Err(String::from("The panel must show a directory listing")),
+                };
+
+                match result {
+                    Ok(files) => {
+                        let mut tagged_files: Vec<PathBuf> = panel
+                            .get_tagged_files()
+                            .into_iter()
+                            .map(|entry| entry.file)
+                            .collect();
+
+                        if let PubSub::TagMatching(_) = pubsub {
+                            tagged_files.extend(files);
+                        } else {
+                            let files: HashSet<PathBuf> = HashSet::from_iter(files);
+
+                            tagged_files.retain(|file| !files.contains(file));
+                        }
+
+                        self.panels[self.panel_focus_position].set_tagged_files(&tagged_files);
+                    }
+                    Err(e) => self.pubsub_tx.send(PubSub::Error(e, None)).unwrap(),
+                }
+            }
             PubSub::PromptUntagGlob => {
                 self.command_bar = Some(Box::new(CmdBar::new(
                     &self.palette,
@@ -753,7 +913,7 @@ impl App {
                             // we need to umount that archive before renaming.
                             archive_mounter::umount_parents(
                                 command_tx,
-                                &[selected_entry.file.clone()],
+                                slice::from_ref(&selected_entry.file),
                             );
                         }
 
@@ -844,12 +1004,7 @@ impl App {
                     _ => "$",
                 };
 
-                let shell = env::var_os("SHELL").and_then(which).unwrap_or_else(|| {
-                    match get_user_by_uid(get_current_uid()) {
-                        Some(user) => PathBuf::from(user.shell()),
-                        None => PathBuf::from("sh"),
-                    }
-                });
+                let shell = user_shell();
 
                 self.stop_inputs_tx.send(Inputs::Stop).unwrap();
                 raw_output_suspend(&self.raw_output);
@@ -869,6 +1024,27 @@ impl App {
 
                 action = Action::Redraw;
             }
+            PubSub::PromptPanelize(cwd) => {
+                self.command_bar = Some(Box::new(CmdBar::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    CmdBarType::Panelize(cwd.clone()),
+                    "panelize: ",
+                    "",
+                    0,
+                )));
+            }
+            PubSub::Panelize(cwd, cmd) => {
+                let cmd = self.apply_template(cmd, Quote::Yes);
+
+                self.dialog = Some(Box::new(DlgPanelize::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    cwd,
+                    &cmd,
+                    &user_shell(),
+                )));
+            }
             PubSub::MountArchive(archive) => {
                 if let Some(command_tx) = &self.archive_mounter_command_tx {
                     self.dialog = Some(Box::new(DlgMountArchive::new(
@@ -933,13 +1109,7 @@ impl App {
                     .and_then(|db_file| DataBase::new(db_file).ok())
                     .map(|mut db| db.new_job(&mut job));
 
-                self.dialog = Some(Box::new(DlgDirscan::new(
-                    &self.palette,
-                    self.pubsub_tx.clone(),
-                    &job,
-                    &archive_dirs,
-                    self.db_file.as_deref(),
-                )));
+                self.run_pre_job_hook(job, PreJobNext::Dirscan(archive_dirs));
             }
             PubSub::DoRm(job, files, archive_dirs) => {
                 self.dialog = Some(Box::new(DlgRmProgress::new(
@@ -951,7 +1121,10 @@ impl App {
                     self.db_file.as_deref(),
                 )));
             }
-            PubSub::Cp(cwd, entries) | PubSub::Mv(cwd, entries) => {
+            PubSub::Cp(cwd, entries)
+            | PubSub::Mv(cwd, entries)
+            | PubSub::CpFlat(cwd, entries)
+            | PubSub::MvFlat(cwd, entries) => {
                 let other_position = match self.quickviewer_position {
                     2 => self.panel_focus_position ^ 1,
                     _ => 2,
@@ -966,9 +1139,11 @@ impl App {
                     .to_string_lossy()
                     .replace('%', "%%");
 
-                let operation = match pubsub {
-                    PubSub::Cp(_cwd, _entries) => DBJobOperation::Cp,
-                    PubSub::Mv(_cwd, _entries) => DBJobOperation::Mv,
+                let (operation, flatten) = match pubsub {
+                    PubSub::Cp(_cwd, _entries) => (DBJobOperation::Cp, false),
+                    PubSub::Mv(_cwd, _entries) => (DBJobOperation::Mv, false),
+                    PubSub::CpFlat(_cwd, _entries) => (DBJobOperation::Cp, true),
+                    PubSub::MvFlat(_cwd, _entries) => (DBJobOperation::Mv, true),
                     _ => unreachable!(),
                 };
 
@@ -979,22 +1154,54 @@ impl App {
                     entries,
                     &dest,
                     operation,
+                    flatten,
                 )));
             }
-            PubSub::DoDirscan(cwd, entries, str_dest, on_conflict, operation) => {
-                let archive_dest =
-                    expanduser(&PathBuf::from(&self.apply_template(str_dest, Quote::No)));
-
-                let archive_dest = match archive_dest.is_absolute() {
-                    true => archive_dest.clean(),
-                    false => {
-                        let mut archive_cwd = self.archive_path(cwd);
+            PubSub::DoDirscanFlat(cwd, entries, str_dest, on_conflict, operation) => {
+                let archive_dest = self.archive_dest(cwd, str_dest);
 
-                        archive_cwd.push(archive_dest);
+                match self.unarchive_path(&archive_dest).is_dir() {
+                    true => {
+                        // One job for every directory the entries are in, so that all the entries
+                        // end up directly in dest
+                        let str_dest = archive_dest.to_string_lossy().replace('%', "%%");
 
-                        archive_cwd.clean()
+                        let mut dirscans: Vec<PubSub> = entries
+                            .iter()
+                            .into_group_map_by(|entry| {
+                                PathBuf::from(entry.file.parent().unwrap_or(Path::new("/")))
+                            })
+                            .into_iter()
+                            .sorted_by(|(cwd_a, _), (cwd_b, _)| cwd_b.cmp(cwd_a))
+                            .map(|(cwd, entries)| {
+                                PubSub::DoDirscan(
+                                    cwd,
+                                    entries.into_iter().cloned().collect(),
+                                    str_dest.clone(),
+                                    *on_conflict,
+                                    *operation,
+                                )
+                            })
+                            .collect();
+
+                        // The next one starts when the previous job is done
+                        if let Some(dirscan) = dirscans.pop() {
+                            self.queued_dirscans = dirscans;
+                            self.pubsub_tx.send(dirscan).unwrap();
+                        }
                     }
-                };
+                    false => {
+                        self.pubsub_tx
+                            .send(PubSub::Error(
+                                format!("{} is not a directory", archive_dest.to_string_lossy()),
+                                None,
+                            ))
+                            .unwrap();
+                    }
+                }
+            }
+            PubSub::DoDirscan(cwd, entries, str_dest, on_conflict, operation) => {
+                let archive_dest = self.archive_dest(cwd, str_dest);
 
                 let archive_dest_parent = archive_dest
                     .parent()
@@ -1010,15 +1217,13 @@ impl App {
                     true => {
                         match (fs::canonicalize(cwd), fs::canonicalize(&dest)) {
                             (Ok(canonical_cwd), Ok(canonical_dest))
-                                if canonical_cwd == canonical_dest =>
+                                if (canonical_cwd == canonical_dest)
+                                    && (matches!(operation, DBJobOperation::Mv)
+                                        || matches!(on_conflict, OnConflict::Overwrite)
+                                        || matches!(on_conflict, OnConflict::Skip)) =>
                             {
-                                if matches!(operation, DBJobOperation::Mv)
-                                    || matches!(on_conflict, OnConflict::Overwrite)
-                                    || matches!(on_conflict, OnConflict::Skip)
-                                {
-                                    // no-op
-                                    do_dirscan = false;
-                                }
+                                // no-op
+                                do_dirscan = false;
                             }
                             _ => {}
                         }
@@ -1035,15 +1240,20 @@ impl App {
                                             fs::canonicalize(&dest_parent),
                                         ) {
                                             (Ok(canonical_cwd), Ok(canonical_dest))
-                                                if canonical_cwd == canonical_dest =>
+                                                if (canonical_cwd == canonical_dest)
+                                                    && (matches!(
+                                                        operation,
+                                                        DBJobOperation::Mv
+                                                    ) || matches!(
+                                                        on_conflict,
+                                                        OnConflict::Overwrite
+                                                    ) || matches!(
+                                                        on_conflict,
+                                                        OnConflict::Skip
+                                                    )) =>
                                             {
-                                                if matches!(operation, DBJobOperation::Mv)
-                                                    || matches!(on_conflict, OnConflict::Overwrite)
-                                                    || matches!(on_conflict, OnConflict::Skip)
-                                                {
-                                                    // no-op
-                                                    do_dirscan = false;
-                                                }
+                                                // no-op
+                                                do_dirscan = false;
                                             }
                                             _ => {}
                                         }
@@ -1060,6 +1270,7 @@ impl App {
                                         ))
                                         .unwrap();
 
+                                    self.queued_dirscans.clear();
                                     do_dirscan = false;
                                 }
                             }
@@ -1074,6 +1285,7 @@ impl App {
                                 ))
                                 .unwrap();
 
+                            self.queued_dirscans.clear();
                             do_dirscan = false;
                         }
                     }
@@ -1129,13 +1341,9 @@ impl App {
                         .and_then(|db_file| DataBase::new(db_file).ok())
                         .map(|mut db| db.new_job(&mut job));
 
-                    self.dialog = Some(Box::new(DlgDirscan::new(
-                        &self.palette,
-                        self.pubsub_tx.clone(),
-                        &job,
-                        &archive_dirs,
-                        self.db_file.as_deref(),
-                    )));
+                    self.run_pre_job_hook(job, PreJobNext::Dirscan(archive_dirs));
+                } else if let Some(dirscan) = self.queued_dirscans.pop() {
+                    self.pubsub_tx.send(dirscan).unwrap();
                 }
             }
             PubSub::DoCp(job, files, archive_dirs) | PubSub::DoMv(job, files, archive_dirs) => {
@@ -1153,13 +1361,33 @@ impl App {
                     archive_dirs,
                     self.db_file.as_deref(),
                     operation,
+                    RetryPolicy {
+                        max_retries: self.config.cp_mv.retries,
+                        backoff: Duration::from_millis(self.config.cp_mv.retry_backoff_ms),
+                    },
                 )));
             }
             PubSub::JobCompleted(job, files, dirs) => {
                 self.pubsub_tx.send(PubSub::Reload).unwrap();
 
+                notify_job_completed(
+                    &self.config.notifications,
+                    job,
+                    &self.unarchive_path(&job.cwd),
+                    files,
+                );
+
+                self.run_post_job_hook(job, files, dirs);
+            }
+            PubSub::PostJobHookDone(job, files, dirs, hook_error) => {
+                let hook_errors: Vec<HookError> = hook_error.iter().cloned().collect();
+
                 let job_aborted = matches!(job.status, DBJobStatus::Aborted);
 
+                if job_aborted {
+                    self.queued_dirscans.clear();
+                }
+
                 let skipped_files = files
                     .iter()
                     .any(|entry| matches!(entry.status, DBFileStatus::Skipped));
@@ -1171,7 +1399,13 @@ impl App {
                 let messages_files = files.iter().any(|entry| !entry.message.is_empty());
                 let messages_dirs = dirs.iter().any(|entry| !entry.message.is_empty());
 
-                if job_aborted || skipped_files || skipped_dirs || messages_files || messages_dirs {
+                if job_aborted
+                    || skipped_files
+                    || skipped_dirs
+                    || messages_files
+                    || messages_dirs
+                    || !hook_errors.is_empty()
+                {
                     self.dialog = Some(Box::new(DlgReport::new(
                         &self.palette,
                         self.pubsub_tx.clone(),
@@ -1179,6 +1413,7 @@ impl App {
                         files,
                         dirs,
                         self.db_file.as_deref(),
+                        &hook_errors,
                     )));
                 } else {
                     self.db_file
@@ -1189,15 +1424,15 @@ impl App {
                     self.pubsub_tx.send(PubSub::NextPendingJob).unwrap();
                 }
             }
-            PubSub::PromptSaveReport(cwd, path) => {
+            PubSub::PromptSaveReport(cwd, path, format) => {
                 let str_path = path.to_string_lossy().replace('%', "%%");
                 let chars: Vec<char> = str_path.chars().collect();
 
                 self.command_bar = Some(Box::new(CmdBar::new(
                     &self.palette,
                     self.pubsub_tx.clone(),
-                    CmdBarType::SaveReport(cwd.clone()),
-                    "save: ",
+                    CmdBarType::SaveReport(cwd.clone(), *format),
+                    &format!("save as {}: ", format),
                     &str_path,
                     chars
                         .iter()
@@ -1205,7 +1440,7 @@ impl App {
                         .unwrap_or(str_path.len()),
                 )));
             }
-            PubSub::SaveReport(cwd, str_path) => {
+            PubSub::SaveReport(cwd, str_path, format) => {
                 let archive_path =
                     expanduser(&PathBuf::from(&self.apply_template(str_path, Quote::No)));
 
@@ -1219,7 +1454,123 @@ impl App {
                     }
                 };
 
-                self.pubsub_tx.send(PubSub::DoSaveReport(path)).unwrap();
+                self.pubsub_tx
+                    .send(PubSub::DoSaveReport(path, *format))
+                    .unwrap();
+            }
+            PubSub::RetryJob(job, files, target_dirs) => {
+                let mut job = DBJobEntry {
+                    id: 0,
+                    status: DBJobStatus::Dirscan,
+                    ..job.clone()
+                };
+
+                // Files that were interrupted after their target was chosen are resumed,
+                // the others start over, exactly as if the job got interrupted, but in the
+                // target of their directory, that may have been renamed (see RenameCopy)
+                let mut files: Vec<DBFileEntry> = files
+                    .iter()
+                    .map(|entry| DBFileEntry {
+                        id: 0,
+                        job_id: 0,
+                        status: match entry.cur_target {
+                            Some(_) => DBFileStatus::InProgress,
+                            None => DBFileStatus::ToDo,
+                        },
+                        cur_target: entry.cur_target.clone().or_else(|| {
+                            target_dirs
+                                .iter()
+                                .filter(|(dir, _target_dir)| {
+                                    entry.file.starts_with(dir) && entry.file != *dir
+                                })
+                                .max_by_key(|(dir, _target_dir)| dir.components().count())
+                                .map(|(dir, target_dir)| {
+                                    target_dir.join(entry.file.strip_prefix(dir).unwrap())
+                                })
+                        }),
+                        message: String::from(""),
+                        start_time: None,
+                        end_time: None,
+                        bytes: 0,
+                        error_code: None,
+                        retries: 0,
+                        ..entry.clone()
+                    })
+                    .collect();
+
+                if let Some(mut db) = self
+                    .db_file
+                    .as_deref()
+                    .and_then(|db_file| DataBase::new(db_file).ok())
+                {
+                    db.new_job(&mut job);
+                    db.set_file_list(job.id, &mut files);
+                }
+
+                self.run_pre_job_hook(job, PreJobNext::Retry(files));
+            }
+            PubSub::PreJobHookDone(job, _next, Some(hook_error)) => {
+                let mut job = job.clone();
+
+                self.queued_dirscans.clear();
+
+                job.status = DBJobStatus::Aborted;
+
+                self.db_file
+                    .as_deref()
+                    .and_then(|db_file| DataBase::new(db_file).ok())
+                    .map(|db| db.set_job_status(job.id, DBJobStatus::Aborted));
+
+                self.dialog = Some(Box::new(DlgReport::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    &job,
+                    &[],
+                    &[],
+                    self.db_file.as_deref(),
+                    slice::from_ref(hook_error),
+                )));
+            }
+            PubSub::PreJobHookDone(job, PreJobNext::Dirscan(archive_dirs), None) => {
+                self.dialog = Some(Box::new(DlgDirscan::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    job,
+                    archive_dirs,
+                    self.db_file.as_deref(),
+                )));
+            }
+            PubSub::PreJobHookDone(job, PreJobNext::Retry(files), None) => {
+                let mut job = job.clone();
+                let files = files.clone();
+
+                job.status = DBJobStatus::InProgress;
+
+                let archive_dirs: Vec<ArchiveEntry> = match &self.archive_mounter_command_tx {
+                    Some(command_tx) => archive_mounter::get_archive_dirs(command_tx),
+                    None => Vec::new(),
+                }
+                .into_iter()
+                .filter(|archive_dir| job.archives.contains(&archive_dir.archive_file))
+                .collect();
+
+                match job.operation {
+                    DBJobOperation::Cp => {
+                        self.pubsub_tx
+                            .send(PubSub::DoCp(job, files, archive_dirs))
+                            .unwrap();
+                    }
+                    DBJobOperation::Mv => {
+                        self.pubsub_tx
+                            .send(PubSub::DoMv(job, files, archive_dirs))
+                            .unwrap();
+                    }
+                    DBJobOperation::Rm => {
+                        self.pubsub_tx
+                            .send(PubSub::DoRm(job, files, archive_dirs))
+                            .unwrap();
+                    }
+                }
             }
             PubSub::CommandBarError(msg) => {
                 self.command_bar = Some(Box::new(CommandBarError::new(&self.palette, msg)));
@@ -1233,9 +1584,10 @@ impl App {
                         self.db_file.as_deref(),
                     )));
                 }
-                None => {
-                    self.umount_unrelated();
-                }
+                None => match self.queued_dirscans.pop() {
+                    Some(dirscan) => self.pubsub_tx.send(dirscan).unwrap(),
+                    None => self.umount_unrelated(),
+                },
             },
             PubSub::MountArchivesForJob(job) => {
                 self.pending_job = Some(job.clone());
@@ -1329,29 +1681,126 @@ impl App {
                     }
                 }
             },
-            PubSub::ArchiveMounted(_archive_file, _temp_dir) => {
-                if self.pending_job.is_some() {
-                    self.pubsub_tx.send(PubSub::NextPendingArchive).unwrap();
+            PubSub::ArchiveMounted(_archive_file, _temp_dir) if self.pending_job.is_some() => {
+                self.pubsub_tx.send(PubSub::NextPendingArchive).unwrap();
+            }
+            PubSub::ArchiveMountError(_archive_file, error) if self.pending_job.is_some() => {
+                self.pending_archives.clear();
+                self.pending_job = None;
+
+                self.pubsub_tx
+                    .send(PubSub::Error(
+                        String::from(error),
+                        Some(Box::new(PubSub::NextPendingJob)),
+                    ))
+                    .unwrap();
+            }
+            PubSub::ChangedDirectory(cwd) => {
+                // The directories inside archives can't be reached once the archive is unmounted
+                if self.archive_path(cwd) == *cwd {
+                    self.dir_history.add(cwd);
+                    self.frecency.add(cwd);
+                }
+
+                if self.pending_jobs.is_empty() {
+                    self.umount_unrelated();
                 }
             }
-            PubSub::ArchiveMountError(_archive_file, error) => {
-                if self.pending_job.is_some() {
-                    self.pending_archives.clear();
-                    self.pending_job = None;
+            PubSub::DirscanAborted => self.queued_dirscans.clear(),
+            PubSub::TabSwitched if self.pending_jobs.is_empty() => self.umount_unrelated(),
+            PubSub::HistoryDirs(dirs) => {
+                self.dialog = Some(Box::new(DlgDirList::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    "Directory history",
+                    dirs,
+                )));
+            }
+            PubSub::FrecentDirs(dirs, missing_dirs) => {
+                self.frecency.remove(missing_dirs);
 
+                self.dialog = Some(Box::new(DlgDirList::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    "Jump to directory",
+                    dirs,
+                )));
+            }
+            PubSub::CompareDirs(compare_by) => {
+                // This assumes that there are always 2 panels visible
+                let (focus_position, other_position) = match self.quickviewer_position {
+                    2 => (self.panel_focus_position, self.panel_focus_position ^ 1),
+                    pos if pos == self.panel_focus_position => (self.panel_focus_position ^ 1, 2),
+                    _ => (self.panel_focus_position, 2),
+                };
+
+                let panels = (
+                    self.panels[focus_position]
+                        .get_cwd()
+                        .zip(self.panels[focus_position].get_file_list()),
+                    self.panels[other_position]
+                        .get_cwd()
+                        .zip(self.panels[other_position].get_file_list()),
+                );
+
+                match panels {
+                    (Some((cwd_a, entries_a)), Some((cwd_b, entries_b))) => {
+                        let pubsub_tx = self.pubsub_tx.clone();
+                        let compare_by = *compare_by;
+
+                        // Comparing the content of the files may take a while
+                        thread::spawn(move || {
+                            let files =
+                                compare_dirs(&cwd_a, &entries_a, &cwd_b, &entries_b, compare_by);
+
+                            let _ = pubsub_tx.send(PubSub::DirsCompared(cwd_a, cwd_b, files));
+                        });
+                    }
+                    _ => {
+                        self.pubsub_tx
+                            .send(PubSub::Error(
+                                String::from("Both panels must show a directory listing"),
+                                None,
+                            ))
+                            .unwrap();
+                    }
+                }
+            }
+            PubSub::DirsCompared(cwd_a, cwd_b, files) => {
+                let (focus_position, other_position) = match self.quickviewer_position {
+                    2 => (self.panel_focus_position, self.panel_focus_position ^ 1),
+                    pos if pos == self.panel_focus_position => (self.panel_focus_position ^ 1, 2),
+                    _ => (self.panel_focus_position, 2),
+                };
+
+                let cwds = (
+                    self.panels[focus_position].get_cwd(),
+                    self.panels[other_position].get_cwd(),
+                );
+
+                // The result is stale if the panels have changed directory in the meantime
+                let up_to_date = match cwds {
+                    (Some(cwd_focus), Some(cwd_other)) => {
+                        (&cwd_focus, &cwd_other) == (cwd_a, cwd_b)
+                            || (&cwd_focus, &cwd_other) == (cwd_b, cwd_a)
+                    }
+                    _ => false,
+                };
+
+                if up_to_date {
+                    self.panels[focus_position].set_tagged_files(files);
+                    self.panels[other_position].set_tagged_files(files);
+                }
+
+                if up_to_date && files.is_empty() {
                     self.pubsub_tx
-                        .send(PubSub::Error(
-                            String::from(error),
-                            Some(Box::new(PubSub::NextPendingJob)),
+                        .send(PubSub::Info(
+                            String::from("Compare directories"),
+                            String::from("The directories are the same"),
                         ))
                         .unwrap();
                 }
             }
-            PubSub::ChangedDirectory(_cwd) => {
-                if self.pending_jobs.is_empty() {
-                    self.umount_unrelated();
-                }
-            }
             PubSub::Fzf(cwd, file_list, hidden_files) => {
                 self.dialog = Some(Box::new(DlgFzf::new(
                     &self.palette,
@@ -1374,6 +1823,22 @@ impl App {
         }
     }
 
+    /// The destination typed in the CP/MV dialog, as an absolute path
+    fn archive_dest(&self, cwd: &Path, str_dest: &str) -> PathBuf {
+        let archive_dest = expanduser(&PathBuf::from(&self.apply_template(str_dest, Quote::No)));
+
+        match archive_dest.is_absolute() {
+            true => archive_dest.clean(),
+            false => {
+                let mut archive_cwd = self.archive_path(cwd);
+
+                archive_cwd.push(archive_dest);
+
+                archive_cwd.clean()
+            }
+        }
+    }
+
     fn archive_path(&self, file: &Path) -> PathBuf {
         match &self.archive_mounter_command_tx {
             Some(command_tx) => archive_mounter::archive_path(command_tx, file),
@@ -1381,6 +1846,114 @@ impl App {
         }
     }
 
+    fn toggle_quickview(&mut self) {
+        // This assumes that there are always 2 panels visible
+        if self.quickviewer_position < 2 {
+            let quickviewer_position = self.quickviewer_position;
+
+            if self.panel_focus_position == quickviewer_position {
+                self.panels[self.panel_focus_position].change_focus(Focus::Normal);
+            }
+
+            self.panels.swap(self.quickviewer_position, 2);
+
+            self.quickviewer_position = 2;
+
+            if self.panel_focus_position == quickviewer_position {
+                self.panels[self.panel_focus_position].change_focus(Focus::Focused);
+            }
+        } else {
+            self.quickviewer_position = self.panel_focus_position ^ 1;
+
+            self.panels.swap(self.quickviewer_position, 2);
+        }
+
+        self.pubsub_tx
+            .send(PubSub::ToggleQuickView(
+                self.panels[self.panel_focus_position].get_selected_entry(),
+            ))
+            .unwrap();
+    }
+
+    // Outside of render, as the preview reads the selected file
+    fn update_miller(&mut self) {
+        if let Some(miller) = &mut self.miller {
+            let panel = &self.panels[self.panel_focus_position];
+
+            miller.update(
+                panel.get_cwd().as_deref(),
+                panel.get_selected_entry().as_ref(),
+            );
+        }
+    }
+
+    fn toggle_miller(&mut self) {
+        self.miller = match self.miller {
+            Some(_) => None,
+            None => {
+                // The Miller columns layout has its own preview, so there's no need for the quick
+                // view
+                if self.quickviewer_position < 2 {
+                    self.toggle_quickview();
+                }
+
+                Some(Miller::new(
+                    &self.palette,
+                    self.pubsub_tx.clone(),
+                    self.tabsize,
+                ))
+            }
+        };
+
+        self.update_miller();
+    }
+
+    fn restore_session(&mut self, session: &Session) {
+        self.vertical = self.vertical || session.vertical;
+
+        // This assumes that there are always 2 panels visible
+        if let Some(quickviewer_position) = session.quick_view.filter(|&pos| pos < 2) {
+            self.quickviewer_position = quickviewer_position;
+            self.panels.swap(self.quickviewer_position, 2);
+
+            self.pubsub_tx.send(PubSub::ToggleQuickView(None)).unwrap();
+        }
+
+        if session.panel_focus_position == 1 {
+            self.panels[self.panel_focus_position].change_focus(Focus::Normal);
+            self.panel_focus_position = 1;
+            self.panels[self.panel_focus_position].change_focus(Focus::Focused);
+        }
+
+        if session.miller {
+            self.toggle_miller();
+        }
+    }
+
+    fn save_session(&self) {
+        if let Some(session_file) = &self.session_file {
+            // The panel hidden by the quick view is the one at its position
+            let panels = match self.quickviewer_position {
+                0 => [2, 1],
+                1 => [0, 2],
+                _ => [0, 1],
+            };
+
+            let session = Session {
+                panels: panels
+                    .iter()
+                    .map(|&i| self.panels[i].get_tabs_state().unwrap_or_default())
+                    .collect::<Vec<PanelTabsState>>(),
+                panel_focus_position: self.panel_focus_position,
+                quick_view: (self.quickviewer_position < 2).then_some(self.quickviewer_position),
+                vertical: self.vertical,
+                miller: self.miller.is_some(),
+            };
+
+            let _ = session.save(session_file);
+        }
+    }
+
     fn umount_unrelated(&self) {
         if let Some(command_tx) = &self.archive_mounter_command_tx {
             let (focus_position, other_position) = match self.quickviewer_position {
@@ -1405,10 +1978,14 @@ impl App {
                 .get_old_cwd()
                 .expect("BUG: The other panel has no working directory set");
 
-            archive_mounter::umount_unrelated(
-                command_tx,
-                &[cwd, old_cwd, other_cwd, other_old_cwd],
-            );
+            let mut dirs = vec![cwd, old_cwd, other_cwd, other_old_cwd];
+
+            // Keep the archives that are open in the tabs in the background
+            for panel in &self.panels {
+                dirs.extend(panel.get_background_cwds());
+            }
+
+            archive_mounter::umount_unrelated(command_tx, &dirs);
         }
     }
 
@@ -1554,6 +2131,77 @@ impl App {
         template::substitute(s, mapping, '%')
     }
 
+    /// Runs the pre-job hook of a job that is about to start, then goes on with `next`.
+    ///
+    /// If the hook fails, the job is aborted and its report is shown.
+    fn run_pre_job_hook(&mut self, job: DBJobEntry, next: PreJobNext) {
+        if self.config.hooks.pre_job.is_empty() {
+            self.pubsub_tx
+                .send(PubSub::PreJobHookDone(job, next, None))
+                .unwrap();
+
+            return;
+        }
+
+        // The hook runs in the real directory, even if the job is inside an archive
+        let cwd = self.unarchive_path(&job.cwd);
+        let done_job = job.clone();
+
+        self.dialog = Some(Box::new(DlgHook::new(
+            &self.palette,
+            self.pubsub_tx.clone(),
+            Hook::PreJob,
+            &self.config.hooks.pre_job,
+            &job,
+            &cwd,
+            None,
+            move |hook_error| PubSub::PreJobHookDone(done_job, next, hook_error),
+        )));
+    }
+
+    fn run_post_job_hook(
+        &mut self,
+        job: &DBJobEntry,
+        files: &[DBFileEntry],
+        dirs: &[DBDirListEntry],
+    ) {
+        let (job, files, dirs) = (job.clone(), Vec::from(files), Vec::from(dirs));
+
+        if self.config.hooks.post_job.is_empty() {
+            self.pubsub_tx
+                .send(PubSub::PostJobHookDone(job, files, dirs, None))
+                .unwrap();
+
+            return;
+        }
+
+        // The report is available to the hook only while it runs
+        let report_file = tempfile::Builder::new()
+            .prefix("fcd-report-")
+            .suffix(".txt")
+            .tempfile()
+            .ok()
+            .filter(|report_file| {
+                Report::new(&job, &files, &dirs, &[])
+                    .save(report_file.path(), ReportFormat::Text)
+                    .is_ok()
+            });
+
+        let cwd = self.unarchive_path(&job.cwd);
+        let done_job = job.clone();
+
+        self.dialog = Some(Box::new(DlgHook::new(
+            &self.palette,
+            self.pubsub_tx.clone(),
+            Hook::PostJob,
+            &self.config.hooks.post_job,
+            &job,
+            &cwd,
+            report_file,
+            move |hook_error| PubSub::PostJobHookDone(done_job, files, dirs, hook_error),
+        )));
+    }
+
     fn db_entries_from_entries(
         &self,
         entries: &[Entry],
@@ -1631,33 +2279,57 @@ impl app::App for App {
             self.button_bar_rect = chunks[2];
         };
 
-        let panel_chunks = Layout::default()
-            .direction(match self.vertical {
-                true => Direction::Vertical,
-                false => Direction::Horizontal,
-            })
-            .constraints([Constraint::Percentage(50), Constraint::Min(1)])
-            .split(chunks[0]);
-
-        self.panel_rects[0] = panel_chunks[0];
-        self.panel_rects[1] = panel_chunks[1];
-
-        self.panels[0].render(
-            f,
-            &panel_chunks[0],
-            match self.panel_focus_position {
-                0 => Focus::Focused,
-                _ => Focus::Normal,
-            },
-        );
-        self.panels[1].render(
-            f,
-            &panel_chunks[1],
-            match self.panel_focus_position {
-                1 => Focus::Focused,
-                _ => Focus::Normal,
-            },
-        );
+        match &mut self.miller {
+            Some(miller) => {
+                // Only the focused panel is shown, between its parent directory and the preview
+                let column_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(40),
+                        Constraint::Min(1),
+                    ])
+                    .split(chunks[0]);
+
+                self.panel_rects[self.panel_focus_position] = column_chunks[1];
+                self.panel_rects[self.panel_focus_position ^ 1] = Rect::default();
+
+                let panel = &mut self.panels[self.panel_focus_position];
+
+                miller.render_parent(f, &column_chunks[0]);
+                panel.render(f, &column_chunks[1], Focus::Focused);
+                miller.render_preview(f, &column_chunks[2]);
+            }
+            None => {
+                let panel_chunks = Layout::default()
+                    .direction(match self.vertical {
+                        true => Direction::Vertical,
+                        false => Direction::Horizontal,
+                    })
+                    .constraints([Constraint::Percentage(50), Constraint::Min(1)])
+                    .split(chunks[0]);
+
+                self.panel_rects[0] = panel_chunks[0];
+                self.panel_rects[1] = panel_chunks[1];
+
+                self.panels[0].render(
+                    f,
+                    &panel_chunks[0],
+                    match self.panel_focus_position {
+                        0 => Focus::Focused,
+                        _ => Focus::Normal,
+                    },
+                );
+                self.panels[1].render(
+                    f,
+                    &panel_chunks[1],
+                    match self.panel_focus_position {
+                        1 => Focus::Focused,
+                        _ => Focus::Normal,
+                    },
+                );
+            }
+        }
 
         let focus_command_bar = self
             .command_bar
@@ -1815,3 +2487,13 @@ pub fn raw_output_suspend(raw_output: &RawTerminal<io::Stdout>) {
         .suspend_raw_mode()
         .expect("failed to suspend raw mode");
 }
+
+/// The shell of the user, to run the shell commands with
+fn user_shell() -> PathBuf {
+    env::var_os("SHELL").and_then(which).unwrap_or_else(|| {
+        match get_user_by_uid(get_current_uid()) {
+            Some(user) => PathBuf::from(user.shell()),
+            None => PathBuf::from("sh"),
+        }
+    })
+}
@@ -12,11 +12,13 @@ use std::{
 use anyhow::{anyhow, bail, Result};
 use crossbeam_channel::{Receiver, Sender};
 
-use path_clean::PathClean;
 use tempfile::tempdir;
 use wait_timeout::ChildExt;
 
-use crate::shutil::which;
+pub use fcd::engine::archive::{
+    archive_parent_map, archive_path_map, unarchive_path_map, ArchiveEntry,
+};
+use fcd::shutil::which;
 
 #[derive(Debug, Clone)]
 pub enum ArchiveMounterCommand {
@@ -30,12 +32,6 @@ pub enum ArchiveMounterCommand {
     UmountAll(Sender<()>),
 }
 
-#[derive(Debug, Clone)]
-pub struct ArchiveEntry {
-    pub archive_file: PathBuf,
-    temp_dir: PathBuf,
-}
-
 #[derive(Debug, Clone)]
 struct ArchiveMounter {
     executable: PathBuf,
@@ -202,48 +198,6 @@ pub fn get_archive_dirs(command_tx: &Sender<ArchiveMounterCommand>) -> Vec<Archi
     archive_dirs_rx.recv().unwrap()
 }
 
-pub fn unarchive_path_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
-    archive_dirs
-        .iter()
-        .rev()
-        .find(|entry| file.starts_with(&entry.archive_file))
-        .map(|entry| {
-            entry
-                .temp_dir
-                .join(file.strip_prefix(&entry.archive_file).unwrap())
-        })
-        .unwrap_or_else(|| PathBuf::from(file))
-        .clean()
-}
-
-pub fn archive_path_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
-    archive_dirs
-        .iter()
-        .rev()
-        .find(|entry| file.starts_with(&entry.temp_dir))
-        .map(|entry| {
-            entry
-                .archive_file
-                .join(file.strip_prefix(&entry.temp_dir).unwrap())
-        })
-        .unwrap_or_else(|| PathBuf::from(file))
-        .clean()
-}
-
-pub fn unarchive_parent_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
-    match (file.parent(), file.file_name()) {
-        (Some(parent), Some(file_name)) => unarchive_path_map(parent, archive_dirs).join(file_name),
-        _ => PathBuf::from(file),
-    }
-}
-
-pub fn archive_parent_map(file: &Path, archive_dirs: &[ArchiveEntry]) -> PathBuf {
-    match (file.parent(), file.file_name()) {
-        (Some(parent), Some(file_name)) => archive_path_map(parent, archive_dirs).join(file_name),
-        _ => PathBuf::from(file),
-    }
-}
-
 impl ArchiveMounter {
     pub fn new() -> Option<ArchiveMounter> {
         let executable = which("archivefs").or_else(|| which("archivemount"));
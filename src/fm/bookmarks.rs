@@ -72,6 +72,7 @@ impl Bookmarks {
         }
     }
 
+    #[allow(dead_code)]
     pub fn remove(&mut self, key: char) {
         let index = self.data.iter().position(|(k, _v)| *k == key);
 
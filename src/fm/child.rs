@@ -0,0 +1,64 @@
+use std::{
+    io::{self, Read},
+    os::unix::process::CommandExt,
+    process::{Command, Output, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::Receiver;
+use wait_timeout::ChildExt;
+
+/// Like `Command::output`, but the child gets killed as soon as something is sent on
+/// `cancel_rx`, in which case `None` is returned.
+///
+/// stdin is redirected to /dev/null, and the child runs in its own process group, so that
+/// canceling it kills also the processes it started (like the commands run by a shell).
+pub fn output_cancelable(
+    command: &mut Command,
+    cancel_rx: &Receiver<()>,
+) -> io::Result<Option<Output>> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()?;
+
+    // Reading while waiting, so that the child never blocks on a full pipe
+    let read_all = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut buf: Vec<u8> = Vec::new();
+
+            pipe.read_to_end(&mut buf).unwrap_or(0);
+
+            buf
+        })
+    };
+
+    let stdout_thread = read_all(Box::new(child.stdout.take().unwrap()));
+    let stderr_thread = read_all(Box::new(child.stderr.take().unwrap()));
+
+    let status = loop {
+        match child.wait_timeout(Duration::from_millis(50))? {
+            Some(status) => break status,
+            None => {
+                if !cancel_rx.is_empty() {
+                    unsafe {
+                        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+                    }
+
+                    let _ = child.wait();
+
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout_thread.join().unwrap_or_default(),
+        stderr: stderr_thread.join().unwrap_or_default(),
+    }))
+}
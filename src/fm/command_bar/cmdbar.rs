@@ -9,7 +9,10 @@ use unicode_width::UnicodeWidthStr;
 use crate::{
     app::PubSub,
     component::{Component, Focus},
-    fm::command_bar::component::{CommandBar, CommandBarComponent},
+    fm::{
+        command_bar::component::{CommandBar, CommandBarComponent},
+        cp_mv_rm::dlg_report::ReportFormat,
+    },
     palette::Palette,
     widgets::input::Input,
 };
@@ -21,7 +24,8 @@ pub enum CmdBarType {
     Mkdir,
     Rename,
     Shell(PathBuf),
-    SaveReport(PathBuf),
+    Panelize(PathBuf),
+    SaveReport(PathBuf, ReportFormat),
 }
 
 #[derive(Debug)]
@@ -89,9 +93,14 @@ impl Component for CmdBar {
                                 .send(PubSub::Shell(cwd.clone(), self.input.value()))
                                 .unwrap();
                         }
-                        CmdBarType::SaveReport(cwd) => {
+                        CmdBarType::Panelize(cwd) => {
                             self.pubsub_tx
-                                .send(PubSub::SaveReport(cwd.clone(), self.input.value()))
+                                .send(PubSub::Panelize(cwd.clone(), self.input.value()))
+                                .unwrap();
+                        }
+                        CmdBarType::SaveReport(cwd, format) => {
+                            self.pubsub_tx
+                                .send(PubSub::SaveReport(cwd.clone(), self.input.value(), *format))
                                 .unwrap();
                         }
                     }
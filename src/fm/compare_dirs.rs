@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::fm::entry::Entry;
+
+const BUFFER_SIZE: usize = 128 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareBy {
+    Name,
+    Size,
+    Mtime,
+    Content,
+}
+
+// Like read_exact, but stops at the end of the file, returning the number of bytes read
+fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+
+    while len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(len)
+}
+
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut file_a = File::open(a)?;
+    let mut file_b = File::open(b)?;
+
+    let mut buf_a = vec![0; BUFFER_SIZE];
+    let mut buf_b = vec![0; BUFFER_SIZE];
+
+    loop {
+        let len_a = read_full(&mut file_a, &mut buf_a)?;
+        let len_b = read_full(&mut file_b, &mut buf_b)?;
+
+        if buf_a[..len_a] != buf_b[..len_b] {
+            return Ok(false);
+        }
+
+        if len_a == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Compare the entries of 2 directories, and return the ones (of both directories) that are
+/// missing or different in the other directory.
+///
+/// The entries are matched by their path relative to their directory, so that flat listings
+/// can be compared too. Directories are only checked for existence, while the files are
+/// compared according to `compare_by` (by content, only the regular files are read): when
+/// comparing by modification time, only the newer file of the 2 is returned, so that it can be
+/// copied over the older one.
+pub fn compare_dirs(
+    cwd_a: &Path,
+    entries_a: &[Entry],
+    cwd_b: &Path,
+    entries_b: &[Entry],
+    compare_by: CompareBy,
+) -> Vec<PathBuf> {
+    let relative_entries = |cwd: &Path, entries: &[Entry]| -> HashMap<PathBuf, Entry> {
+        entries
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .file
+                    .strip_prefix(cwd)
+                    .ok()
+                    .map(|rel_file| (PathBuf::from(rel_file), entry.clone()))
+            })
+            .collect()
+    };
+
+    let rel_entries_a = relative_entries(cwd_a, entries_a);
+    let rel_entries_b = relative_entries(cwd_b, entries_b);
+
+    let mut different: Vec<PathBuf> = rel_entries_b
+        .iter()
+        .filter(|(rel_file, _entry)| !rel_entries_a.contains_key(*rel_file))
+        .map(|(_rel_file, entry)| entry.file.clone())
+        .collect();
+
+    for (rel_file, a) in &rel_entries_a {
+        let Some(b) = rel_entries_b.get(rel_file) else {
+            different.push(a.file.clone());
+            continue;
+        };
+
+        let (tag_a, tag_b) = match (a.stat.is_dir(), b.stat.is_dir()) {
+            (true, true) => (false, false),
+            (true, false) | (false, true) => (true, true),
+            (false, false) => match compare_by {
+                CompareBy::Name => (false, false),
+                CompareBy::Size => {
+                    let different_size = a.stat.len() != b.stat.len();
+
+                    (different_size, different_size)
+                }
+                CompareBy::Mtime => match (a.stat.modified().ok(), b.stat.modified().ok()) {
+                    (Some(mtime_a), Some(mtime_b)) if mtime_a != mtime_b => {
+                        (mtime_a > mtime_b, mtime_b > mtime_a)
+                    }
+                    _ => {
+                        let different_size = a.stat.len() != b.stat.len();
+
+                        (different_size, different_size)
+                    }
+                },
+                CompareBy::Content => {
+                    // Only the regular files are read (FIFOs and devices could block, or never
+                    // end), the others are compared by type. Files that can't be read are
+                    // considered different
+                    let different_content = match (a.stat.is_file(), b.stat.is_file()) {
+                        (true, true) => {
+                            (a.stat.len() != b.stat.len())
+                                || !same_content(&a.file, &b.file).unwrap_or(false)
+                        }
+                        _ => a.stat.file_type() != b.stat.file_type(),
+                    };
+
+                    (different_content, different_content)
+                }
+            },
+        };
+
+        if tag_a {
+            different.push(a.file.clone());
+        }
+
+        if tag_b {
+            different.push(b.file.clone());
+        }
+    }
+
+    different
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CString, fs, os::unix::ffi::OsStrExt};
+
+    use super::*;
+    use crate::{fm::entry::get_file_list, palette::get_monochrome_palette};
+
+    fn mkfifo(file: &Path) {
+        let path = CString::new(file.as_os_str().as_bytes()).unwrap();
+
+        assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o600) }, 0);
+    }
+
+    #[test]
+    fn test_compare_by_content_does_not_open_fifos() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cwd_a = temp_dir.path().join("a");
+        let cwd_b = temp_dir.path().join("b");
+
+        for cwd in [&cwd_a, &cwd_b] {
+            fs::create_dir(cwd).unwrap();
+            mkfifo(&cwd.join("fifo"));
+        }
+
+        fs::write(cwd_a.join("file"), b"same").unwrap();
+        fs::write(cwd_b.join("file"), b"same").unwrap();
+        fs::write(cwd_a.join("other"), b"a").unwrap();
+        mkfifo(&cwd_b.join("other"));
+
+        let palette = get_monochrome_palette();
+        let entries_a = get_file_list(&cwd_a, &palette, None).unwrap();
+        let entries_b = get_file_list(&cwd_b, &palette, None).unwrap();
+
+        let mut different =
+            compare_dirs(&cwd_a, &entries_a, &cwd_b, &entries_b, CompareBy::Content);
+        different.sort();
+
+        assert_eq!(different, vec![cwd_a.join("other"), cwd_b.join("other")]);
+    }
+}
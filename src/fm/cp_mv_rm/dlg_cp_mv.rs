@@ -29,6 +29,7 @@ pub struct DlgCpMv {
     cwd: PathBuf,
     entries: Vec<Entry>,
     operation: DBJobOperation,
+    flatten: bool,
     input: Input,
     radio: RadioBox,
     btn_ok: Button,
@@ -42,6 +43,8 @@ pub struct DlgCpMv {
 }
 
 impl DlgCpMv {
+    /// With `flatten`, the entries can be in different directories (not only in `cwd`), and
+    /// they all get copied (or moved) directly into the destination directory.
     pub fn new(
         palette: &Rc<Palette>,
         pubsub_tx: Sender<PubSub>,
@@ -49,6 +52,7 @@ impl DlgCpMv {
         entries: &[Entry],
         dest: &str,
         operation: DBJobOperation,
+        flatten: bool,
     ) -> DlgCpMv {
         DlgCpMv {
             palette: Rc::clone(palette),
@@ -56,6 +60,7 @@ impl DlgCpMv {
             cwd: PathBuf::from(cwd),
             entries: Vec::from(entries),
             operation,
+            flatten,
             input: Input::new(&palette.dialog_input, dest, dest.len()),
             radio: RadioBox::new(
                 ["Overwrite", "Skip", "Rename Existing", "Rename Copy"],
@@ -93,8 +98,13 @@ impl DlgCpMv {
             _ => unreachable!(),
         };
 
+        let do_dirscan = match self.flatten {
+            false => PubSub::DoDirscan,
+            true => PubSub::DoDirscanFlat,
+        };
+
         self.pubsub_tx
-            .send(PubSub::DoDirscan(
+            .send(do_dirscan(
                 self.cwd.clone(),
                 self.entries.clone(),
                 self.input.value(),
@@ -141,15 +151,11 @@ impl Component for DlgCpMv {
                 Key::Down | Key::Char('j') => {
                     self.section_focus_position = min(self.section_focus_position + 1, 2)
                 }
-                Key::Left | Key::Char('h') => {
-                    if self.section_focus_position == 2 {
-                        self.button_focus_position = 0;
-                    }
+                Key::Left | Key::Char('h') if self.section_focus_position == 2 => {
+                    self.button_focus_position = 0;
                 }
-                Key::Right | Key::Char('l') => {
-                    if self.section_focus_position == 2 {
-                        self.button_focus_position = 1;
-                    }
+                Key::Right | Key::Char('l') if self.section_focus_position == 2 => {
+                    self.button_focus_position = 1;
                 }
                 Key::Ctrl('c') => key_handled = false,
                 Key::Ctrl('l') => key_handled = false,
@@ -19,8 +19,9 @@ use crate::{
         app::{format_seconds, human_readable_size},
         archive_mounter::ArchiveEntry,
         cp_mv_rm::{
-            cp_mv::{cp_mv, CpMvEvent, CpMvInfo, CpMvResult},
+            cp_mv::{cp_mv, CpMvEvent, CpMvInfo, CpMvResult, RetryPolicy},
             database::{DBFileEntry, DBJobEntry, DBJobOperation},
+            fs_ops::RealFs,
         },
     },
     palette::Palette,
@@ -37,6 +38,7 @@ pub struct DlgCpMvProgress {
     archive_dirs: Vec<ArchiveEntry>,
     db_file: Option<PathBuf>,
     operation: DBJobOperation,
+    retry_policy: RetryPolicy,
     ev_tx: Sender<CpMvEvent>,
     info_rx: Receiver<CpMvInfo>,
     result_rx: Receiver<CpMvResult>,
@@ -62,6 +64,7 @@ pub struct DlgCpMvProgress {
 }
 
 impl DlgCpMvProgress {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         palette: &Rc<Palette>,
         pubsub_tx: Sender<PubSub>,
@@ -70,6 +73,7 @@ impl DlgCpMvProgress {
         archive_dirs: &[ArchiveEntry],
         db_file: Option<&Path>,
         operation: DBJobOperation,
+        retry_policy: RetryPolicy,
     ) -> DlgCpMvProgress {
         let (ev_tx, ev_rx) = crossbeam_channel::unbounded();
         let (info_tx, info_rx) = crossbeam_channel::unbounded();
@@ -83,6 +87,7 @@ impl DlgCpMvProgress {
             archive_dirs: Vec::from(archive_dirs),
             db_file: db_file.map(PathBuf::from),
             operation,
+            retry_policy,
             ev_tx,
             info_rx,
             result_rx,
@@ -158,6 +163,7 @@ impl DlgCpMvProgress {
 
         let db_file = self.db_file.clone();
         let archive_dirs = self.archive_dirs.clone();
+        let retry_policy = self.retry_policy;
 
         let pubsub_tx = self.pubsub_tx.clone();
 
@@ -171,10 +177,14 @@ impl DlgCpMvProgress {
                 replace_first_path,
                 &entries,
                 ev_rx,
-                info_tx,
-                pubsub_tx.clone(),
+                &mut |info| {
+                    let _ = info_tx.send(info);
+                    let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+                },
                 db_file.as_deref(),
                 &archive_dirs,
+                retry_policy,
+                &RealFs,
             );
 
             let _ = result_tx.send(result);
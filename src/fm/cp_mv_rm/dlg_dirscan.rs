@@ -20,6 +20,7 @@ use crate::{
         cp_mv_rm::{
             database::{DBFileEntry, DBJobEntry, DBJobOperation, DBJobStatus, DataBase},
             dirscan::{dirscan, DirScanEvent, DirScanInfo, ReadMetadata},
+            fs_ops::RealFs,
         },
     },
     palette::Palette,
@@ -129,8 +130,11 @@ impl DlgDirscan {
                 &archive_dirs,
                 read_metadata,
                 ev_rx,
-                info_tx,
-                pubsub_tx.clone(),
+                &mut |info| {
+                    let _ = info_tx.send(info);
+                    let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+                },
+                &RealFs,
             );
 
             let _ = result_tx.send(result);
@@ -291,6 +295,7 @@ impl Component for DlgDirscan {
                                 .and_then(|db_file| DataBase::new(db_file).ok())
                                 .map(|db| db.delete_job(self.job.id));
 
+                            self.pubsub_tx.send(PubSub::DirscanAborted).unwrap();
                             self.pubsub_tx.send(PubSub::NextPendingJob).unwrap();
                         }
                     }
@@ -0,0 +1,188 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    thread,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use ratatui::{prelude::*, widgets::*};
+use tempfile::NamedTempFile;
+use termion::event::*;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{centered_rect, render_shadow, PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::cp_mv_rm::{
+        database::DBJobEntry,
+        hooks::{run_hook, Hook, HookError},
+    },
+    palette::Palette,
+    tilde_layout::tilde_layout,
+    widgets::button::Button,
+};
+
+#[derive(Debug)]
+pub struct DlgHook {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    hook: Hook,
+    result_rx: Receiver<PubSub>,
+    cancel_tx: Sender<()>,
+    btn_cancel: Button,
+    btn_cancel_rect: Rect,
+}
+
+impl DlgHook {
+    /// Runs the hook `command` of `job` in a thread, and sends the PubSub returned by
+    /// `on_done` when the hook exits (or gets canceled).
+    ///
+    /// `report` is removed only after the hook has exited.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        hook: Hook,
+        command: &str,
+        job: &DBJobEntry,
+        cwd: &Path,
+        report: Option<NamedTempFile>,
+        on_done: impl FnOnce(Option<HookError>) -> PubSub + Send + 'static,
+    ) -> DlgHook {
+        let (result_tx, result_rx) = crossbeam_channel::unbounded();
+        let (cancel_tx, cancel_rx) = crossbeam_channel::unbounded();
+
+        let ps_tx = pubsub_tx.clone();
+        let command = String::from(command);
+        let job = job.clone();
+        let cwd = PathBuf::from(cwd);
+
+        thread::spawn(move || {
+            let report_path = report.as_ref().map(|report| report.path());
+
+            let result = run_hook(hook, &command, &job, &cwd, report_path, &cancel_rx);
+
+            let _ = result_tx.send(on_done(result.err()));
+            let _ = ps_tx.send(PubSub::ComponentThreadEvent);
+        });
+
+        DlgHook {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            hook,
+            result_rx,
+            cancel_tx,
+            btn_cancel: Button::new(
+                "Cancel",
+                &palette.dialog,
+                &palette.dialog_focus,
+                &palette.dialog_title,
+            ),
+            btn_cancel_rect: Rect::default(),
+        }
+    }
+
+    // The thread kills the hook, and reports it as failed
+    fn on_cancel(&mut self) {
+        let _ = self.cancel_tx.send(());
+    }
+}
+
+impl Component for DlgHook {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        match key {
+            Key::Esc | Key::Char('q') | Key::Char('Q') | Key::F(10) | Key::Char('0') => {
+                self.on_cancel();
+            }
+            Key::Char('\n') | Key::Char(' ') => self.on_cancel(),
+            Key::Ctrl('c') => key_handled = false,
+            Key::Ctrl('l') => key_handled = false,
+            Key::Ctrl('z') => key_handled = false,
+            Key::Ctrl('o') => key_handled = false,
+            _ => (),
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        if self.btn_cancel_rect.contains(mouse_position) {
+            if let MouseButton::Left = button {
+                self.on_cancel();
+            }
+        }
+    }
+
+    fn handle_pubsub(&mut self, event: &PubSub) {
+        if let PubSub::ComponentThreadEvent = event {
+            if let Ok(result) = self.result_rx.try_recv() {
+                self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+                self.pubsub_tx.send(result).unwrap();
+            }
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, _focus: Focus) {
+        let message = format!("Running the {}...", self.hook);
+
+        let area = centered_rect((message.width() + 6) as u16, 7, chunk);
+
+        f.render_widget(Clear, area);
+        f.render_widget(Block::default().style(self.palette.dialog), area);
+        if let Some(shadow) = self.palette.shadow {
+            render_shadow(f, &area, &shadow);
+        }
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Length(3)])
+            .split(centered_rect(
+                area.width.saturating_sub(2),
+                area.height.saturating_sub(2),
+                &area,
+            ));
+
+        // Upper section
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(Span::styled(
+                    tilde_layout(" Hook ", sections[0].width as usize),
+                    self.palette.dialog_title,
+                ))
+                .centered(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let upper_area = upper_block.inner(sections[0]);
+
+        let message = Paragraph::new(Span::raw(tilde_layout(&message, upper_area.width as usize)));
+
+        f.render_widget(upper_block, sections[0]);
+        f.render_widget(message, upper_area);
+
+        // Lower section
+
+        let lower_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.dialog);
+
+        let lower_area = centered_rect(
+            self.btn_cancel.width() as u16,
+            1,
+            &lower_block.inner(sections[1]),
+        );
+
+        self.btn_cancel_rect = lower_area;
+
+        f.render_widget(lower_block, sections[1]);
+        self.btn_cancel
+            .render(f, &self.btn_cancel_rect, Focus::Focused);
+    }
+}
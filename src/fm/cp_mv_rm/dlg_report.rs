@@ -1,7 +1,11 @@
 use std::{
-    io::{BufWriter, Write},
+    borrow::Cow,
+    cmp::min,
+    fmt,
+    io::{self, BufWriter, Write},
     path::{Path, PathBuf},
     rc::Rc,
+    time::{Duration, SystemTime},
 };
 
 use crossbeam_channel::Sender;
@@ -9,35 +13,377 @@ use ratatui::{prelude::*, widgets::*};
 use termion::event::*;
 
 use atomicwrites::{AllowOverwrite, AtomicFile};
+use chrono::{DateTime, Local, SecondsFormat};
+use itertools::Itertools;
 use pathdiff::diff_paths;
+use serde::Serialize;
 
 use crate::{
     app::{centered_rect, render_shadow, PubSub, MIDDLE_BORDER_SET},
     component::{Component, Focus},
     dlg_error::DialogType,
-    fm::cp_mv_rm::database::{
-        DBDirListEntry, DBFileEntry, DBFileStatus, DBJobEntry, DBJobStatus, DataBase,
+    fm::{
+        app::{format_seconds, human_readable_size},
+        cp_mv_rm::{
+            database::{
+                DBDirListEntry, DBFileEntry, DBFileStatus, DBJobEntry, DBJobStatus, DataBase,
+            },
+            hooks::HookError,
+        },
     },
     palette::Palette,
     tilde_layout::tilde_layout,
     widgets::button::Button,
 };
 
+#[derive(Debug, Clone)]
+struct ReportEntry {
+    status: &'static str,
+    message: String,
+    file: PathBuf,
+    source: PathBuf,
+    target: Option<PathBuf>,
+    size: u64,
+    error_code: Option<i32>,
+    start_time: Option<SystemTime>,
+    end_time: Option<SystemTime>,
+    bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportEntry<'a> {
+    status: &'a str,
+    message: &'a str,
+    source: Cow<'a, str>,
+    target: Option<Cow<'a, str>>,
+    size: u64,
+    error_code: Option<i32>,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    bytes: u64,
+}
+
+// RFC 3339, in local time, with milliseconds
+fn export_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+impl<'a> From<&'a ReportEntry> for ExportEntry<'a> {
+    fn from(entry: &'a ReportEntry) -> Self {
+        ExportEntry {
+            status: entry.status,
+            message: &entry.message,
+            source: entry.source.to_string_lossy(),
+            target: entry.target.as_ref().map(|target| target.to_string_lossy()),
+            size: entry.size,
+            error_code: entry.error_code,
+            start_time: entry.start_time.map(export_time),
+            end_time: entry.end_time.map(export_time),
+            bytes: entry.bytes,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ExportReport<'a> {
+    operation: String,
+    from: Cow<'a, str>,
+    to: Option<Cow<'a, str>>,
+    entries: Vec<ExportEntry<'a>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    const ALL: [ReportFormat; 3] = [ReportFormat::Text, ReportFormat::Csv, ReportFormat::Json];
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Text => "txt",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportFormat::Text => write!(f, "text"),
+            ReportFormat::Csv => write!(f, "CSV"),
+            ReportFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+impl fmt::Display for ReportEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            self.status,
+            self.message,
+            self.file.to_string_lossy()
+        )?;
+
+        if let (Some(start_time), Some(end_time)) = (self.start_time, self.end_time) {
+            let start_time: DateTime<Local> = DateTime::from(start_time);
+            let duration = end_time
+                .duration_since(start_time.into())
+                .unwrap_or_default();
+
+            write!(
+                f,
+                " ({}, {:.3}s, {})",
+                start_time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                duration.as_secs_f64(),
+                human_readable_size(self.bytes)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The outcome of a job, as shown by `DlgReport` and saved to a file.
+#[derive(Debug, Clone)]
+pub struct Report {
+    job: DBJobEntry,
+    summary: Vec<String>,
+    messages: Vec<ReportEntry>,
+}
+
 #[derive(Debug)]
 pub struct DlgReport {
     palette: Rc<Palette>,
     pubsub_tx: Sender<PubSub>,
-    job: DBJobEntry,
+    report: Report,
     db_file: Option<PathBuf>,
-    messages: Vec<String>,
+    retry_files: Vec<DBFileEntry>,
+    target_dirs: Vec<(PathBuf, PathBuf)>,
     dialog_type: DialogType,
     btn_close: Button,
-    btn_save: Button,
+    btn_save: [Button; 3],
+    btn_retry: Button,
     first_line: usize,
     focus_position: usize,
     rect: Rect,
     btn_close_rect: Rect,
-    btn_save_rect: Rect,
+    btn_save_rect: [Rect; 3],
+    btn_retry_rect: Rect,
+}
+
+fn report_status(status: DBFileStatus, message: &str, job_status: DBJobStatus) -> &'static str {
+    match status {
+        DBFileStatus::ToDo | DBFileStatus::InProgress => "ABORTED",
+        DBFileStatus::Error => "ERROR",
+        DBFileStatus::Skipped => "SKIPPED",
+        DBFileStatus::Done => match (message.is_empty(), job_status) {
+            (true, DBJobStatus::Aborted) => "DONE",
+            (true, _) => "",
+            (false, _) => "WARNING",
+        },
+    }
+}
+
+impl Report {
+    pub fn new(
+        job: &DBJobEntry,
+        files: &[DBFileEntry],
+        dirs: &[DBDirListEntry],
+        hook_errors: &[HookError],
+    ) -> Report {
+        let mut messages: Vec<ReportEntry> = files
+            .iter()
+            .map(|entry| ReportEntry {
+                status: report_status(entry.status, &entry.message, job.status),
+                message: entry.message.clone(),
+                file: diff_paths(&entry.file, &job.cwd).unwrap(),
+                source: entry.file.clone(),
+                target: entry.cur_target.clone(),
+                size: entry.size,
+                error_code: entry.error_code,
+                start_time: entry.start_time,
+                end_time: entry.end_time,
+                bytes: entry.bytes,
+            })
+            .chain(dirs.iter().map(|entry| ReportEntry {
+                status: report_status(entry.status, &entry.message, job.status),
+                message: entry.message.clone(),
+                file: diff_paths(&entry.file.file, &job.cwd).unwrap(),
+                source: entry.file.file.clone(),
+                target: Some(entry.cur_target.clone()),
+                size: entry.file.size,
+                error_code: entry.error_code,
+                start_time: entry.start_time,
+                end_time: entry.end_time,
+                bytes: 0,
+            }))
+            .collect();
+
+        let summary = Self::summary(&messages, files.len());
+
+        // Entries that completed without any message are not worth reporting
+        messages.retain(|message| !message.status.is_empty());
+
+        messages.splice(
+            0..0,
+            hook_errors.iter().map(|hook_error| ReportEntry {
+                status: "ERROR",
+                message: hook_error.message.clone(),
+                file: PathBuf::from(hook_error.hook.to_string()),
+                source: PathBuf::from(hook_error.hook.to_string()),
+                target: None,
+                size: 0,
+                error_code: None,
+                start_time: None,
+                end_time: None,
+                bytes: 0,
+            }),
+        );
+
+        // We want to show errors first
+        messages.sort_by_cached_key(|message| match message.status {
+            "ERROR" => 0,
+            _ => 1,
+        });
+
+        Report {
+            job: job.clone(),
+            summary,
+            messages,
+        }
+    }
+
+    /// Saves the report to `path`, whatever its extension.
+    pub fn save(&self, path: &Path, format: ReportFormat) -> io::Result<()> {
+        AtomicFile::new(path, AllowOverwrite)
+            .write(|f| {
+                let mut writer = BufWriter::new(f);
+
+                match format {
+                    ReportFormat::Text => self.write_text(&mut writer)?,
+                    ReportFormat::Csv => self.write_csv(&mut writer)?,
+                    ReportFormat::Json => self.write_json(&mut writer)?,
+                }
+
+                writer.flush()
+            })
+            .map_err(io::Error::from)
+    }
+
+    fn summary(entries: &[ReportEntry], num_files: usize) -> Vec<String> {
+        // Only the entries of the file list are counted, as the dir list repeats some of them
+        let files = &entries[..num_files];
+
+        let counts = ["DONE", "WARNING", "SKIPPED", "ERROR", "ABORTED"]
+            .iter()
+            .map(|status| {
+                let count = files
+                    .iter()
+                    .filter(|entry| match entry.status {
+                        "" => *status == "DONE",
+                        _ => entry.status == *status,
+                    })
+                    .count();
+
+                format!("{} {}", status, count)
+            })
+            .join(", ");
+
+        let total_bytes: u64 = files.iter().map(|entry| entry.bytes).sum();
+
+        let start_time = entries.iter().filter_map(|entry| entry.start_time).min();
+        let end_time = entries.iter().filter_map(|entry| entry.end_time).max();
+        let elapsed = match (start_time, end_time) {
+            (Some(start_time), Some(end_time)) => {
+                end_time.duration_since(start_time).unwrap_or_default()
+            }
+            _ => Duration::ZERO,
+        };
+
+        let throughput = match elapsed.is_zero() {
+            true => 0,
+            false => (total_bytes as f64 / elapsed.as_secs_f64()) as u64,
+        };
+
+        vec![
+            format!("Total: {} files ({})", num_files, counts),
+            format!(
+                "Transferred: {} in {} ({}/s)",
+                human_readable_size(total_bytes),
+                format_seconds(elapsed.as_secs()),
+                human_readable_size(throughput)
+            ),
+        ]
+    }
+
+    fn write_text(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "Operation: {}", self.job.operation)?;
+        writeln!(writer, "From: {}", self.job.cwd.to_string_lossy())?;
+
+        if let Some(dest) = &self.job.dest {
+            writeln!(writer, "To: {}", dest.to_string_lossy())?;
+        }
+
+        writeln!(writer, "Files:")?;
+
+        for entry in &self.job.entries {
+            writeln!(
+                writer,
+                "{}",
+                diff_paths(&entry.file, &self.job.cwd)
+                    .unwrap()
+                    .to_string_lossy()
+            )?;
+        }
+
+        writeln!(writer)?;
+
+        for line in &self.summary {
+            writeln!(writer, "{}", line)?;
+        }
+
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "------------------------------------------------------------------------------"
+        )?;
+        writeln!(writer)?;
+
+        for message in &self.messages {
+            writeln!(writer, "{}", message)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_csv(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+
+        for message in &self.messages {
+            writer.serialize(ExportEntry::from(message))?;
+        }
+
+        writer.flush()
+    }
+
+    fn write_json(&self, writer: &mut impl Write) -> io::Result<()> {
+        let report = ExportReport {
+            operation: self.job.operation.to_string(),
+            from: self.job.cwd.to_string_lossy(),
+            to: self.job.dest.as_ref().map(|dest| dest.to_string_lossy()),
+            entries: self.messages.iter().map(ExportEntry::from).collect(),
+        };
+
+        serde_json::to_writer_pretty(&mut *writer, &report)?;
+        writeln!(writer)
+    }
 }
 
 impl DlgReport {
@@ -48,93 +394,48 @@ impl DlgReport {
         files: &[DBFileEntry],
         dirs: &[DBDirListEntry],
         db_file: Option<&Path>,
+        hook_errors: &[HookError],
     ) -> DlgReport {
-        let mut messages: Vec<String> = files
+        let report = Report::new(job, files, dirs, hook_errors);
+
+        let failed = |status| {
+            matches!(
+                status,
+                DBFileStatus::Error | DBFileStatus::ToDo | DBFileStatus::InProgress
+            )
+        };
+
+        // A directory whose entry in the dir list failed (for example it could not be removed
+        // after moving its content) is retried as well
+        let retry_files: Vec<DBFileEntry> = files
             .iter()
-            .filter_map(|entry| match entry.status {
-                DBFileStatus::ToDo | DBFileStatus::InProgress => Some(format!(
-                    "ABORTED [{}] {}",
-                    entry.message,
-                    diff_paths(&entry.file, &job.cwd).unwrap().to_string_lossy()
-                )),
-                DBFileStatus::Error => Some(format!(
-                    "ERROR [{}] {}",
-                    entry.message,
-                    diff_paths(&entry.file, &job.cwd).unwrap().to_string_lossy()
-                )),
-                DBFileStatus::Skipped => Some(format!(
-                    "SKIPPED [{}] {}",
-                    entry.message,
-                    diff_paths(&entry.file, &job.cwd).unwrap().to_string_lossy()
-                )),
-                DBFileStatus::Done => match entry.message.is_empty() {
-                    true => match job.status {
-                        DBJobStatus::Aborted => Some(format!(
-                            "DONE [] {}",
-                            diff_paths(&entry.file, &job.cwd).unwrap().to_string_lossy()
-                        )),
-                        _ => None,
-                    },
-                    false => Some(format!(
-                        "WARNING [{}] {}",
-                        entry.message,
-                        diff_paths(&entry.file, &job.cwd).unwrap().to_string_lossy()
-                    )),
-                },
+            .filter(|entry| {
+                failed(entry.status)
+                    || dirs
+                        .iter()
+                        .any(|dir| dir.file.id == entry.id && failed(dir.status))
             })
-            .chain(dirs.iter().filter_map(|entry| match entry.status {
-                DBFileStatus::ToDo | DBFileStatus::InProgress => Some(format!(
-                            "ABORTED [{}] {}",
-                            entry.message,
-                            diff_paths(&entry.file.file, &job.cwd)
-                                .unwrap()
-                                .to_string_lossy()
-                        )),
-                DBFileStatus::Error => Some(format!(
-                            "ERROR [{}] {}",
-                            entry.message,
-                            diff_paths(&entry.file.file, &job.cwd)
-                                .unwrap()
-                                .to_string_lossy()
-                        )),
-                DBFileStatus::Skipped => Some(format!(
-                            "SKIPPED [{}] {}",
-                            entry.message,
-                            diff_paths(&entry.file.file, &job.cwd)
-                                .unwrap()
-                                .to_string_lossy()
-                        )),
-                DBFileStatus::Done => match entry.message.is_empty() {
-                    true => match job.status {
-                        DBJobStatus::Aborted => Some(format!(
-                                    "DONE [] {}",
-                                    diff_paths(&entry.file.file, &job.cwd)
-                                        .unwrap()
-                                        .to_string_lossy()
-                                )),
-                        _ => None,
-                    },
-                    false => Some(format!(
-                                "WARNING [{}] {}",
-                                entry.message,
-                                diff_paths(&entry.file.file, &job.cwd)
-                                    .unwrap()
-                                    .to_string_lossy()
-                            )),
-                },
-            }))
+            .cloned()
             .collect();
 
-        // We want to show errors first
-        messages.sort_by_cached_key(|message| match message.starts_with("ERROR") {
-            true => 0,
-            false => 1,
-        });
+        // The targets chosen for the directories (that may have been renamed), so that the
+        // retried files end up in the same directories as the other files
+        let target_dirs: Vec<(PathBuf, PathBuf)> = files
+            .iter()
+            .filter(|entry| entry.is_dir)
+            .filter_map(|entry| {
+                entry
+                    .cur_target
+                    .as_ref()
+                    .map(|cur_target| (entry.file.clone(), cur_target.clone()))
+            })
+            .collect();
 
         // Given that we show errors first, we only need to check if the first message is an error
-        let dialog_type = match messages
+        let dialog_type = match report
+            .messages
             .first()
-            .map(|message| message.starts_with("ERROR"))
+            .map(|message| message.status == "ERROR")
             .unwrap_or(false)
         {
             true => DialogType::Error,
@@ -151,47 +452,85 @@ impl DlgReport {
         DlgReport {
             palette: Rc::clone(palette),
             pubsub_tx,
-            job: job.clone(),
+            report,
             db_file: db_file.map(PathBuf::from),
-            messages,
+            retry_files,
+            target_dirs,
             dialog_type,
             btn_close: Button::new("Close", &style, &focused_style, &active_style),
-            btn_save: Button::new("Save", &style, &focused_style, &active_style),
+            btn_save: ReportFormat::ALL.map(|format| {
+                let label = match format {
+                    ReportFormat::Text => String::from("Save"),
+                    format => format!("Save {}", format),
+                };
+
+                Button::new(&label, &style, &focused_style, &active_style)
+            }),
+            btn_retry: Button::new("Retry failed", &style, &focused_style, &active_style),
             first_line: 0,
             focus_position: 0,
             rect: Rect::default(),
             btn_close_rect: Rect::default(),
-            btn_save_rect: Rect::default(),
+            btn_save_rect: [Rect::default(); 3],
+            btn_retry_rect: Rect::default(),
         }
     }
 
     fn clamp_first_line(&mut self) {
-        if (self.first_line + (self.rect.height as usize)) > self.messages.len() {
+        if (self.first_line + (self.rect.height as usize)) > self.report.messages.len() {
             self.first_line = self
+                .report
                 .messages
                 .len()
                 .saturating_sub(self.rect.height as usize);
         }
     }
 
-    fn close(&self) {
+    fn num_buttons(&self) -> usize {
+        match self.retry_files.is_empty() {
+            true => 4,
+            false => 5,
+        }
+    }
+
+    fn delete_job(&self) {
         self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
         self.pubsub_tx.send(PubSub::CloseCommandBar).unwrap();
 
         self.db_file
             .as_deref()
             .and_then(|db_file| DataBase::new(db_file).ok())
-            .map(|db| db.delete_job(self.job.id));
+            .map(|db| db.delete_job(self.report.job.id));
+    }
+
+    fn close(&self) {
+        self.delete_job();
 
         self.pubsub_tx.send(PubSub::NextPendingJob).unwrap();
     }
 
-    fn on_save(&mut self) {
-        let mut path = self.job.cwd.clone();
-        path.push("fcd-report.txt");
+    fn on_retry(&self) {
+        self.delete_job();
 
         self.pubsub_tx
-            .send(PubSub::PromptSaveReport(self.job.cwd.clone(), path))
+            .send(PubSub::RetryJob(
+                self.report.job.clone(),
+                self.retry_files.clone(),
+                self.target_dirs.clone(),
+            ))
+            .unwrap();
+    }
+
+    fn on_save(&mut self, format: ReportFormat) {
+        let mut path = self.report.job.cwd.clone();
+        path.push(format!("fcd-report.{}", format.extension()));
+
+        self.pubsub_tx
+            .send(PubSub::PromptSaveReport(
+                self.report.job.cwd.clone(),
+                path,
+                format,
+            ))
             .unwrap();
     }
 }
@@ -203,11 +542,16 @@ impl Component for DlgReport {
         match key {
             Key::Char('\n') | Key::Char(' ') => match self.focus_position {
                 0 => self.close(),
-                1 => self.on_save(),
+                1..=3 => self.on_save(ReportFormat::ALL[self.focus_position - 1]),
+                4 => self.on_retry(),
                 _ => unreachable!(),
             },
-            Key::Left | Key::Char('h') => self.focus_position = 0,
-            Key::Right | Key::Char('l') => self.focus_position = 1,
+            Key::Left | Key::Char('h') => {
+                self.focus_position = self.focus_position.saturating_sub(1)
+            }
+            Key::Right | Key::Char('l') => {
+                self.focus_position = min(self.focus_position + 1, self.num_buttons() - 1)
+            }
             Key::Up | Key::Char('k') => {
                 self.first_line = self.first_line.saturating_sub(1);
             }
@@ -219,7 +563,7 @@ impl Component for DlgReport {
                 self.first_line = 0;
             }
             Key::End | Key::CtrlEnd | Key::Char('G') => {
-                self.first_line = self.messages.len();
+                self.first_line = self.report.messages.len();
                 self.clamp_first_line();
             }
             Key::PageUp | Key::Ctrl('b') => {
@@ -254,11 +598,21 @@ impl Component for DlgReport {
                     }
                 }
 
-                if self.btn_save_rect.contains(mouse_position) {
-                    self.focus_position = 1;
+                for (i, format) in ReportFormat::ALL.into_iter().enumerate() {
+                    if self.btn_save_rect[i].contains(mouse_position) {
+                        self.focus_position = i + 1;
+
+                        if let MouseButton::Left = button {
+                            self.on_save(format);
+                        }
+                    }
+                }
+
+                if self.btn_retry_rect.contains(mouse_position) {
+                    self.focus_position = 4;
 
                     if let MouseButton::Left = button {
-                        self.on_save();
+                        self.on_retry();
                     }
                 }
             }
@@ -276,37 +630,8 @@ impl Component for DlgReport {
     fn handle_pubsub(&mut self, event: &PubSub) {
         #[allow(clippy::single_match)]
         match event {
-            PubSub::DoSaveReport(path) => {
-                let result = AtomicFile::new(path, AllowOverwrite).write(|f| {
-                    let mut writer = BufWriter::new(f);
-
-                    writeln!(writer, "Operation: {}", self.job.operation)?;
-                    writeln!(writer, "From: {}", self.job.cwd.to_string_lossy())?;
-
-                    if let Some(dest) = &self.job.dest {
-                        writeln!(writer, "To: {}", dest.to_string_lossy())?;
-                    }
-
-                    writeln!(writer, "Files:")?;
-
-                    for entry in &self.job.entries {
-                        writeln!(
-                            writer,
-                            "{}",
-                            diff_paths(&entry.file, &self.job.cwd).unwrap().to_string_lossy()
-                        )?;
-                    }
-
-                    writeln!(writer)?;
-                    writeln!(writer, "------------------------------------------------------------------------------")?;
-                    writeln!(writer)?;
-
-                    for message in &self.messages {
-                        writeln!(writer, "{}", message)?;
-                    }
-
-                    Ok::<(), std::io::Error>(())
-                });
+            PubSub::DoSaveReport(path, format) => {
+                let result = self.report.save(path, *format);
 
                 match result {
                     Ok(()) => {
@@ -367,23 +692,39 @@ impl Component for DlgReport {
             .padding(Padding::horizontal(1))
             .style(style);
 
-        let upper_area = upper_block.inner(sections[0]);
+        let upper_area = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(self.report.summary.len() as u16 + 1),
+                Constraint::Min(1),
+            ])
+            .split(upper_block.inner(sections[0]));
 
-        self.rect = upper_area;
+        self.rect = upper_area[1];
         self.clamp_first_line();
 
         let items: Vec<ListItem> = self
+            .report
             .messages
             .iter()
             .skip(self.first_line)
-            .take(upper_area.height.into())
-            .map(|message| ListItem::new::<&str>(message))
+            .take(self.rect.height.into())
+            .map(|message| ListItem::new(message.to_string()))
             .collect();
 
         let list = List::new(items);
 
+        let summary = Paragraph::new(
+            self.report
+                .summary
+                .iter()
+                .map(|line| Line::from(line.as_str()))
+                .collect::<Vec<Line>>(),
+        );
+
         f.render_widget(upper_block, sections[0]);
-        f.render_widget(list, upper_area);
+        f.render_widget(summary, upper_area[0]);
+        f.render_widget(list, upper_area[1]);
 
         // Lower section
 
@@ -392,21 +733,36 @@ impl Component for DlgReport {
             .border_set(MIDDLE_BORDER_SET)
             .style(style);
 
+        let btn_retry_width = match self.num_buttons() {
+            5 => self.btn_retry.width(),
+            _ => 0,
+        };
+
+        let btn_widths = [
+            self.btn_close.width(),
+            self.btn_save[0].width(),
+            self.btn_save[1].width(),
+            self.btn_save[2].width(),
+            btn_retry_width,
+        ];
+
         let lower_area = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(self.btn_close.width() as u16),
-                Constraint::Length(1),
-                Constraint::Length(self.btn_save.width() as u16),
-            ])
+            .constraints(
+                btn_widths
+                    .iter()
+                    .flat_map(|width| [Constraint::Length(*width as u16), Constraint::Length(1)])
+                    .take(btn_widths.len() * 2 - 1),
+            )
             .split(centered_rect(
-                (self.btn_close.width() + 1 + self.btn_save.width()) as u16,
+                (btn_widths.iter().sum::<usize>() + btn_widths.len() - 1) as u16,
                 1,
                 &lower_block.inner(sections[1]),
             ));
 
         self.btn_close_rect = lower_area[0];
-        self.btn_save_rect = lower_area[2];
+        self.btn_save_rect = [lower_area[2], lower_area[4], lower_area[6]];
+        self.btn_retry_rect = lower_area[8];
 
         f.render_widget(lower_block, sections[1]);
         self.btn_close.render(
@@ -420,16 +776,33 @@ impl Component for DlgReport {
                 _ => Focus::Normal,
             },
         );
-        self.btn_save.render(
-            f,
-            &self.btn_save_rect,
-            match self.focus_position {
-                1 => match focus {
-                    Focus::Focused => Focus::Focused,
-                    _ => Focus::Active,
+
+        for (i, btn_save) in self.btn_save.iter_mut().enumerate() {
+            btn_save.render(
+                f,
+                &self.btn_save_rect[i],
+                match self.focus_position {
+                    pos if pos == i + 1 => match focus {
+                        Focus::Focused => Focus::Focused,
+                        _ => Focus::Active,
+                    },
+                    _ => Focus::Normal,
                 },
-                _ => Focus::Normal,
-            },
-        );
+            );
+        }
+
+        if !self.retry_files.is_empty() {
+            self.btn_retry.render(
+                f,
+                &self.btn_retry_rect,
+                match self.focus_position {
+                    4 => match focus {
+                        Focus::Focused => Focus::Focused,
+                        _ => Focus::Active,
+                    },
+                    _ => Focus::Normal,
+                },
+            );
+        }
     }
 }
@@ -20,6 +20,7 @@ use crate::{
         archive_mounter::ArchiveEntry,
         cp_mv_rm::{
             database::{DBFileEntry, DBJobEntry, DBJobStatus, DataBase},
+            fs_ops::RealFs,
             rm::{rm, RmEvent, RmInfo},
         },
     },
@@ -119,7 +120,16 @@ impl DlgRmProgress {
         let pubsub_tx = self.pubsub_tx.clone();
 
         thread::spawn(move || {
-            let result = rm(&entries, ev_rx, info_tx, pubsub_tx.clone(), &archive_dirs);
+            let result = rm(
+                &entries,
+                ev_rx,
+                &mut |info| {
+                    let _ = info_tx.send(info);
+                    let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+                },
+                &archive_dirs,
+                &RealFs,
+            );
 
             let _ = result_tx.send(result);
             let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
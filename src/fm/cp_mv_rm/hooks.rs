@@ -0,0 +1,113 @@
+use std::{fmt, path::Path, process::Command};
+
+use crossbeam_channel::Receiver;
+
+use crate::{
+    fm::{
+        archive_mounter::ArchiveEntry,
+        child::output_cancelable,
+        cp_mv_rm::database::{DBFileEntry, DBJobEntry},
+    },
+    template,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Hook {
+    PreJob,
+    PostJob,
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            Hook::PreJob => write!(f, "pre-job hook"),
+            Hook::PostJob => write!(f, "post-job hook"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HookError {
+    pub hook: Hook,
+    pub message: String,
+}
+
+/// What to do with a job once its pre-job hook has succeeded
+#[derive(Debug, Clone)]
+pub enum PreJobNext {
+    Dirscan(Vec<ArchiveEntry>),
+    Retry(Vec<DBFileEntry>),
+}
+
+/// Runs the hook `command` through `sh` in `cwd`, after expanding its macros:
+///
+/// * `%o`: the operation (Copy, Move or Delete)
+/// * `%d`: the working directory of the job (as shown in the panels)
+/// * `%D`: the destination directory of the job (empty for Delete)
+/// * `%i`: the job id
+/// * `%s`: the job status
+/// * `%r`: the report file (only for the post-job hook)
+///
+/// Every macro is expanded shell quoted. An empty `command` does nothing.
+/// The hook is killed as soon as something is sent on `cancel_rx`.
+pub fn run_hook(
+    hook: Hook,
+    command: &str,
+    job: &DBJobEntry,
+    cwd: &Path,
+    report: Option<&Path>,
+    cancel_rx: &Receiver<()>,
+) -> Result<(), HookError> {
+    if command.is_empty() {
+        return Ok(());
+    }
+
+    let quote = |s: &str| shlex::try_quote(s).map(String::from).unwrap_or_default();
+
+    let mapping = [
+        ("o", quote(&job.operation.to_string())),
+        ("d", quote(&job.cwd.to_string_lossy())),
+        (
+            "D",
+            quote(
+                &job.dest
+                    .as_ref()
+                    .map(|dest| dest.to_string_lossy())
+                    .unwrap_or_default(),
+            ),
+        ),
+        ("i", job.id.to_string()),
+        ("s", quote(&job.status.to_string())),
+        (
+            "r",
+            quote(
+                &report
+                    .map(|report| report.to_string_lossy())
+                    .unwrap_or_default(),
+            ),
+        ),
+    ];
+
+    let hook_error = |message: String| HookError { hook, message };
+
+    let output = output_cancelable(
+        Command::new("sh")
+            .args(["-c", &template::substitute(command, mapping, '%')])
+            .current_dir(cwd),
+        cancel_rx,
+    )
+    .map_err(|e| hook_error(e.to_string()))?
+    .ok_or_else(|| hook_error(String::from("canceled")))?;
+
+    match output.status.success() {
+        true => Ok(()),
+        false => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            Err(hook_error(match stderr.trim().lines().last() {
+                Some(line) => format!("{} -- {}", output.status, line),
+                None => output.status.to_string(),
+            }))
+        }
+    }
+}
@@ -1,11 +1,12 @@
-pub mod cp_mv;
-pub mod database;
-pub mod dirscan;
+pub use fcd::engine::{cp_mv, database, dirscan, fs_ops, rm};
+
 pub mod dlg_cp_mv;
 pub mod dlg_cp_mv_progress;
 pub mod dlg_dirscan;
+pub mod dlg_hook;
 pub mod dlg_pending_job;
 pub mod dlg_question;
 pub mod dlg_report;
 pub mod dlg_rm_progress;
-pub mod rm;
+pub mod hooks;
+pub mod notify;
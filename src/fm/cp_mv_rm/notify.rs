@@ -0,0 +1,95 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::{
+    config::{DesktopNotification, Notifications},
+    fm::cp_mv_rm::database::{DBFileEntry, DBFileStatus, DBJobEntry},
+};
+
+/// Notifies the user that a job has completed, as configured in the `[notifications]`
+/// section of the config file.
+///
+/// The command runs in `cwd`, the real directory of the job, that differs from `job.cwd` when
+/// the job is inside an archive.
+pub fn notify_job_completed(
+    config: &Notifications,
+    job: &DBJobEntry,
+    cwd: &Path,
+    files: &[DBFileEntry],
+) {
+    let title = format!("fcd: {} {}", job.operation, job.status);
+    let summary = summary(files);
+
+    let mut output = io::stdout();
+
+    if config.bell {
+        let _ = write!(output, "\x07");
+    }
+
+    match config.desktop {
+        DesktopNotification::None => {}
+        DesktopNotification::Osc9 => {
+            let _ = write!(
+                output,
+                "\x1b]9;{}\x07",
+                sanitize(&format!("{}: {}", title, summary))
+            );
+        }
+        DesktopNotification::Osc777 => {
+            let _ = write!(
+                output,
+                "\x1b]777;notify;{};{}\x07",
+                sanitize(&title).replace(';', ","),
+                sanitize(&summary)
+            );
+        }
+    }
+
+    let _ = output.flush();
+
+    if !config.command.is_empty() {
+        let child = Command::new("sh")
+            .args(["-c", &config.command])
+            .current_dir(cwd)
+            .env("FCD_JOB_ID", job.id.to_string())
+            .env("FCD_JOB_OPERATION", job.operation.to_string())
+            .env("FCD_JOB_STATUS", job.status.to_string())
+            .env("FCD_JOB_SUMMARY", &summary)
+            .env("FCD_JOB_CWD", &job.cwd)
+            .env(
+                "FCD_JOB_DEST",
+                job.dest.as_deref().unwrap_or(job.cwd.as_path()),
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        // The hook runs in the background, but it still needs to be reaped
+        if let Ok(mut child) = child {
+            thread::spawn(move || child.wait());
+        }
+    }
+}
+
+fn summary(files: &[DBFileEntry]) -> String {
+    let count = |f: fn(DBFileStatus) -> bool| files.iter().filter(|entry| f(entry.status)).count();
+
+    format!(
+        "{} files (DONE {}, SKIPPED {}, ERROR {}, ABORTED {})",
+        files.len(),
+        count(|status| matches!(status, DBFileStatus::Done)),
+        count(|status| matches!(status, DBFileStatus::Skipped)),
+        count(|status| matches!(status, DBFileStatus::Error)),
+        count(|status| matches!(status, DBFileStatus::ToDo | DBFileStatus::InProgress)),
+    )
+}
+
+// Control characters would terminate the escape sequence early
+fn sanitize(s: &str) -> String {
+    s.chars().filter(|c| !c.is_control()).collect()
+}
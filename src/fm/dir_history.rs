@@ -0,0 +1,95 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+
+use atomicwrites::{AllowOverwrite, AtomicFile};
+use crossbeam_channel::{Receiver, Sender};
+
+const MAX_HISTORY: usize = 1000;
+
+/// The directories visited by all the panels, shared across sessions, the most recent last.
+#[derive(Debug)]
+pub struct DirHistory {
+    data: Vec<PathBuf>,
+    // The file is written by a thread, not to block the UI at every directory change
+    writer: Option<(Sender<Vec<PathBuf>>, JoinHandle<()>)>,
+}
+
+fn writer_thread(file: PathBuf, data_rx: Receiver<Vec<PathBuf>>) {
+    while let Ok(mut data) = data_rx.recv() {
+        // Only the most recent history needs to be written
+        while let Ok(newer_data) = data_rx.try_recv() {
+            data = newer_data;
+        }
+
+        let _ = AtomicFile::new(&file, AllowOverwrite).write(|f| {
+            for dir in &data {
+                writeln!(f, "{}", dir.to_string_lossy())?;
+            }
+
+            Ok::<(), io::Error>(())
+        });
+    }
+}
+
+impl DirHistory {
+    pub fn new(file: Option<&Path>) -> DirHistory {
+        let data = match file.map(File::open) {
+            Some(Ok(f)) => BufReader::new(f)
+                .lines()
+                .map_while(Result::ok)
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let writer = file.map(|file| {
+            let (data_tx, data_rx) = crossbeam_channel::unbounded();
+            let file = PathBuf::from(file);
+
+            (data_tx, thread::spawn(move || writer_thread(file, data_rx)))
+        });
+
+        DirHistory { data, writer }
+    }
+
+    /// The visited directories, the most recent first.
+    pub fn get(&self) -> Vec<PathBuf> {
+        self.data.iter().rev().cloned().collect()
+    }
+
+    pub fn add(&mut self, dir: &Path) {
+        if self.data.last().is_some_and(|last| last == dir) {
+            return;
+        }
+
+        self.data.retain(|d| d != dir);
+        self.data.push(PathBuf::from(dir));
+
+        if self.data.len() > MAX_HISTORY {
+            self.data.drain(..(self.data.len() - MAX_HISTORY));
+        }
+
+        self.update_file();
+    }
+
+    fn update_file(&mut self) {
+        if let Some((data_tx, _)) = &self.writer {
+            let _ = data_tx.send(self.data.clone());
+        }
+    }
+}
+
+impl Drop for DirHistory {
+    fn drop(&mut self) {
+        // Closing the channel lets the thread write the pending history before exiting
+        if let Some((data_tx, handle)) = self.writer.take() {
+            drop(data_tx);
+            let _ = handle.join();
+        }
+    }
+}
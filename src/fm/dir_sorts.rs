@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use atomicwrites::{AllowOverwrite, AtomicFile};
+use serde::{Deserialize, Serialize};
+
+use crate::fm::entry::{SortBy, SortDirs, SortOrder};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SortSettings {
+    pub sort_method: SortBy,
+    pub sort_order: SortOrder,
+    #[serde(default)]
+    pub sort_dirs: SortDirs,
+}
+
+/// The sort settings of every directory whose sorting has been changed, remembered across
+/// launches when the `sort_per_directory` option is enabled.
+#[derive(Debug)]
+pub struct DirSorts {
+    file: Option<PathBuf>,
+    data: HashMap<PathBuf, SortSettings>,
+}
+
+impl DirSorts {
+    pub fn new(file: Option<&Path>) -> DirSorts {
+        let data = match file.map(File::open) {
+            Some(Ok(f)) => serde_json::from_reader(BufReader::new(f)).unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+
+        DirSorts {
+            file: file.map(PathBuf::from),
+            data,
+        }
+    }
+
+    pub fn get(&self, dir: &Path) -> Option<SortSettings> {
+        self.data.get(dir).copied()
+    }
+
+    pub fn insert(&mut self, dir: &Path, settings: SortSettings) {
+        self.data.insert(PathBuf::from(dir), settings);
+        self.update_file();
+    }
+
+    fn update_file(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = AtomicFile::new(file, AllowOverwrite).write(|f| {
+                let mut writer = BufWriter::new(f);
+
+                serde_json::to_writer(&mut writer, &self.data)?;
+
+                writer.flush()
+            });
+        }
+    }
+}
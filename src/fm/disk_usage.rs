@@ -0,0 +1,823 @@
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    rc::Rc,
+    thread,
+    time::Instant,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use thousands::Separable;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::{
+        app::{human_readable_size, LABELS},
+        entry::{get_file_list, sort_by_name, Entry, SortOrder},
+        panel::{Panel, PanelComponent},
+    },
+    palette::Palette,
+    tilde_layout::tilde_layout,
+};
+
+const BAR_WIDTH: usize = 10;
+
+#[derive(Debug, Clone)]
+struct ScanInfo {
+    current: PathBuf,
+    num_files: usize,
+    total_size: u64,
+}
+
+#[derive(Debug, Clone)]
+enum ComponentPubSub {
+    ScanInfo(ScanInfo),
+    ScanDone(HashMap<PathBuf, u64>),
+}
+
+#[derive(Debug)]
+pub struct DiskUsage {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    component_pubsub_tx: Sender<ComponentPubSub>,
+    component_pubsub_rx: Receiver<ComponentPubSub>,
+    abort_tx: Option<Sender<()>>,
+    root: PathBuf,
+    cwd: PathBuf,
+    dir_sizes: HashMap<PathBuf, u64>,
+    scan_info: Option<ScanInfo>,
+    file_list: Vec<Entry>,
+    tagged_files: Vec<Entry>,
+    cursor_position: usize,
+    first_line: usize,
+    focus: Focus,
+    rect: Rect,
+}
+
+impl DiskUsage {
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        root: &Path,
+        focus: Focus,
+    ) -> DiskUsage {
+        let (component_pubsub_tx, component_pubsub_rx) = crossbeam_channel::unbounded();
+
+        let mut disk_usage = DiskUsage {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            component_pubsub_tx,
+            component_pubsub_rx,
+            abort_tx: None,
+            root: PathBuf::from(root),
+            cwd: PathBuf::from(root),
+            dir_sizes: HashMap::new(),
+            scan_info: None,
+            file_list: Vec::new(),
+            tagged_files: Vec::new(),
+            cursor_position: 0,
+            first_line: 0,
+            focus,
+            rect: Rect::default(),
+        };
+
+        disk_usage.scan_thread();
+
+        disk_usage
+    }
+
+    fn handle_component_pubsub(&mut self) {
+        if let Ok(event) = self.component_pubsub_rx.try_recv() {
+            match event {
+                ComponentPubSub::ScanInfo(info) => {
+                    if self.scan_info.is_some() {
+                        self.scan_info = Some(info);
+                    }
+                }
+                ComponentPubSub::ScanDone(dir_sizes) => {
+                    self.scan_info = None;
+                    self.abort_tx = None;
+                    self.dir_sizes = dir_sizes;
+
+                    // The current directory may have been deleted while rescanning
+                    if let Some(cwd) = self
+                        .cwd
+                        .ancestors()
+                        .find(|d| self.dir_sizes.contains_key(*d))
+                    {
+                        self.cwd = PathBuf::from(cwd);
+                    }
+
+                    self.load_file_list(self.get_selected_file().as_deref());
+                }
+            }
+        }
+    }
+
+    fn scan_thread(&mut self) {
+        self.stop_scan_thread();
+
+        let (abort_tx, abort_rx) = crossbeam_channel::unbounded();
+        self.abort_tx = Some(abort_tx);
+
+        self.scan_info = Some(ScanInfo {
+            current: self.root.clone(),
+            num_files: 0,
+            total_size: 0,
+        });
+
+        let root = self.root.clone();
+        let component_pubsub_tx = self.component_pubsub_tx.clone();
+        let pubsub_tx = self.pubsub_tx.clone();
+
+        thread::spawn(move || {
+            let metadata = match fs::symlink_metadata(&root) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    // Complete the scan anyway, not to leave the panel scanning forever
+                    let _ = component_pubsub_tx.send(ComponentPubSub::ScanDone(HashMap::new()));
+                    let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+                    let _ = pubsub_tx.send(PubSub::Error(e.to_string(), None));
+                    return;
+                }
+            };
+
+            let mut info = ScanInfo {
+                current: root.clone(),
+                num_files: 0,
+                total_size: 0,
+            };
+
+            let mut dir_sizes = HashMap::new();
+            let mut last_write = Instant::now();
+
+            if scan_dir(
+                &root,
+                &metadata,
+                metadata.dev(),
+                &abort_rx,
+                &mut info,
+                &mut last_write,
+                &mut |info| {
+                    let _ = component_pubsub_tx.send(ComponentPubSub::ScanInfo(info.clone()));
+                    let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+                },
+                &mut dir_sizes,
+            )
+            .is_some()
+            {
+                let _ = component_pubsub_tx.send(ComponentPubSub::ScanDone(dir_sizes));
+                let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            }
+        });
+    }
+
+    fn stop_scan_thread(&mut self) {
+        if let Some(abort_tx) = self.abort_tx.take() {
+            let _ = abort_tx.send(());
+        }
+    }
+
+    fn entry_size(&self, entry: &Entry) -> u64 {
+        match entry.lstat.is_dir() {
+            true => self
+                .dir_sizes
+                .get(&entry.file)
+                .copied()
+                .unwrap_or_else(|| disk_size(&entry.lstat)),
+            false => disk_size(&entry.lstat),
+        }
+    }
+
+    fn load_file_list(&mut self, selected_file: Option<&Path>) {
+        let mut file_list = get_file_list(&self.cwd, &self.palette, None).unwrap_or_default();
+
+        for entry in file_list.iter_mut() {
+            let size = self.entry_size(entry);
+
+            entry.size = Some(size);
+            entry.shown_size = human_readable_size(size);
+        }
+
+        file_list.sort_unstable_by(|a, b| {
+            b.size
+                .cmp(&a.size)
+                .then_with(|| sort_by_name(a, b, SortOrder::Normal))
+        });
+
+        self.file_list = file_list;
+
+        self.tagged_files
+            .retain(|entry| self.file_list.contains(entry));
+
+        self.cursor_position = self.clamp_cursor(match selected_file {
+            Some(file) => self
+                .file_list
+                .iter()
+                .position(|entry| entry.file == file)
+                .unwrap_or(self.cursor_position),
+            None => 0,
+        });
+
+        self.clamp_first_line();
+
+        if let Focus::Focused = self.focus {
+            self.pubsub_tx
+                .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                .unwrap();
+        }
+    }
+
+    fn update_cwd_size(&mut self, old_file_list: &[Entry]) {
+        let old_size = self.dir_sizes.get(&self.cwd).copied().unwrap_or(0);
+
+        // Forget about the directories that are no longer there
+        let removed_dirs: Vec<&Entry> = old_file_list
+            .iter()
+            .filter(|entry| entry.lstat.is_dir() && !self.file_list.contains(entry))
+            .collect();
+
+        if !removed_dirs.is_empty() {
+            self.dir_sizes.retain(|dir, _| {
+                !removed_dirs
+                    .iter()
+                    .any(|removed_dir| dir.starts_with(&removed_dir.file))
+            });
+        }
+
+        let new_size = fs::symlink_metadata(&self.cwd)
+            .map(|metadata| disk_size(&metadata))
+            .unwrap_or(0)
+            + self
+                .file_list
+                .iter()
+                .filter_map(|entry| entry.size)
+                .sum::<u64>();
+
+        let delta = (new_size as i64) - (old_size as i64);
+
+        for dir in self.cwd.ancestors() {
+            if !dir.starts_with(&self.root) {
+                break;
+            }
+
+            if let Some(size) = self.dir_sizes.get_mut(dir) {
+                *size = size.saturating_add_signed(delta);
+            }
+        }
+    }
+
+    fn chdir(&mut self, cwd: &Path, selected_file: Option<&Path>) {
+        if cwd.starts_with(&self.root) && cwd != self.cwd {
+            self.cwd = PathBuf::from(cwd);
+            self.tagged_files.clear();
+            self.cursor_position = 0;
+            self.first_line = 0;
+
+            self.load_file_list(selected_file);
+        }
+    }
+
+    fn get_selected_file(&self) -> Option<PathBuf> {
+        match self.file_list.is_empty() {
+            true => None,
+            false => Some(self.file_list[self.cursor_position].file.clone()),
+        }
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.file_list.len().saturating_sub(1))
+    }
+
+    fn clamp_first_line(&mut self) {
+        if (self.first_line + (self.rect.height as usize)) > self.file_list.len() {
+            self.first_line = self
+                .file_list
+                .len()
+                .saturating_sub(self.rect.height as usize);
+        }
+    }
+
+    fn move_cursor(&mut self, new_cursor_pos: usize) {
+        let old_cursor_position = self.cursor_position;
+
+        self.cursor_position = self.clamp_cursor(new_cursor_pos);
+
+        if self.cursor_position != old_cursor_position {
+            if let Focus::Focused = self.focus {
+                self.pubsub_tx
+                    .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn tag_toggle(&mut self) {
+        if !self.file_list.is_empty() {
+            let entry = &self.file_list[self.cursor_position];
+
+            if let Some(i) = self.tagged_files.iter().position(|x| x == entry) {
+                self.tagged_files.swap_remove(i);
+            } else {
+                self.tagged_files.push(entry.clone());
+            }
+        }
+    }
+}
+
+impl Drop for DiskUsage {
+    fn drop(&mut self) {
+        self.stop_scan_thread();
+    }
+}
+
+impl Component for DiskUsage {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        // The tree can be browsed only once the scan completes
+        if self.scan_info.is_some() {
+            return false;
+        }
+
+        match key {
+            Key::Up | Key::Char('k') => self.move_cursor(self.cursor_position.saturating_sub(1)),
+            Key::Down | Key::Char('j') => self.move_cursor(self.cursor_position.saturating_add(1)),
+            Key::Home | Key::CtrlHome | Key::Char('g') => self.move_cursor(0),
+            Key::End | Key::CtrlEnd | Key::Char('G') => self.move_cursor(self.file_list.len()),
+            Key::PageUp | Key::Ctrl('b') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.first_line = self.first_line.saturating_sub(rect_height);
+                self.move_cursor(self.cursor_position.saturating_sub(rect_height));
+            }
+            Key::PageDown | Key::Ctrl('f') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.first_line = self.first_line.saturating_add(rect_height);
+                self.clamp_first_line();
+                self.move_cursor(self.cursor_position.saturating_add(rect_height));
+            }
+            Key::Right | Key::Char('\n') | Key::Char('l') => {
+                if !self.file_list.is_empty() {
+                    let entry = &self.file_list[self.cursor_position];
+
+                    if entry.lstat.is_dir() {
+                        self.chdir(&entry.file.clone(), None);
+                    }
+                }
+            }
+            Key::Left | Key::Char('h') | Key::Backspace => {
+                if let Some(parent) = self.cwd.parent() {
+                    let old_cwd = self.cwd.clone();
+
+                    self.chdir(&PathBuf::from(parent), Some(&old_cwd));
+                }
+            }
+            Key::Insert | Key::Char(' ') => {
+                self.tag_toggle();
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('t') => {
+                if !self.file_list.is_empty() {
+                    let entry = &self.file_list[self.cursor_position];
+
+                    if !self.tagged_files.contains(entry) {
+                        self.tagged_files.push(entry.clone());
+                    }
+                }
+
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('u') => {
+                if !self.file_list.is_empty() {
+                    let entry = &self.file_list[self.cursor_position];
+
+                    if let Some(i) = self.tagged_files.iter().position(|x| x == entry) {
+                        self.tagged_files.swap_remove(i);
+                    }
+                }
+
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('U') => self.tagged_files.clear(),
+            Key::Ctrl('r') => {
+                self.tagged_files.clear();
+                self.scan_thread();
+            }
+            Key::F(8) | Key::Char('8') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    let question = match selected_files.len() {
+                        1 => format!("Delete {}?", selected_files[0].file_name),
+                        n => format!("Delete {} files/directories?", n),
+                    };
+
+                    self.pubsub_tx
+                        .send(PubSub::Question(
+                            String::from("Delete"),
+                            question,
+                            Box::new(PubSub::Rm(self.cwd.clone(), selected_files)),
+                        ))
+                        .unwrap();
+                }
+            }
+            _ => key_handled = false,
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        match button {
+            MouseButton::Left | MouseButton::Right if self.rect.contains(mouse_position) => {
+                let new_cursor_position =
+                    self.first_line + ((mouse_position.y - self.rect.y) as usize);
+
+                if new_cursor_position < self.file_list.len() {
+                    self.move_cursor(new_cursor_position);
+
+                    if let MouseButton::Right = button {
+                        self.tag_toggle();
+                    }
+                }
+            }
+            MouseButton::WheelUp => {
+                self.first_line = self.first_line.saturating_sub(1);
+
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                if (self.cursor_position - self.first_line) > rect_height {
+                    self.move_cursor(self.cursor_position.saturating_sub(1));
+                }
+            }
+            MouseButton::WheelDown => {
+                self.first_line = self.first_line.saturating_add(1);
+                self.clamp_first_line();
+
+                if self.first_line > self.cursor_position {
+                    self.move_cursor(self.first_line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_pubsub(&mut self, event: &PubSub) {
+        match event {
+            PubSub::ComponentThreadEvent => self.handle_component_pubsub(),
+            PubSub::Reload => self.reload(self.get_selected_file().as_deref()),
+            _ => (),
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, focus: Focus) {
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(*chunk);
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(vec![
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                    Span::styled(
+                        tilde_layout(
+                            &format!(" (Disk usage) {} ", self.cwd.to_string_lossy()),
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        match focus {
+                            Focus::Focused => self.palette.panel_reverse,
+                            _ => self.palette.panel,
+                        },
+                    ),
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                ])
+                .left_aligned(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .style(self.palette.panel);
+
+        let upper_inner = upper_block.inner(sections[0]);
+        let upper_height = (upper_inner.height as usize).saturating_sub(1);
+
+        self.rect = upper_inner;
+        self.clamp_first_line();
+
+        if self.first_line > self.cursor_position {
+            self.first_line = self.cursor_position;
+        }
+
+        if (self.cursor_position - self.first_line) > upper_height {
+            self.first_line = self.cursor_position.saturating_sub(upper_height);
+        }
+
+        f.render_widget(upper_block, sections[0]);
+
+        let total_size = self.dir_sizes.get(&self.cwd).copied().unwrap_or(0);
+
+        match &self.scan_info {
+            Some(info) => {
+                let width = upper_inner.width.into();
+
+                f.render_widget(
+                    Paragraph::new(vec![
+                        Line::from(Span::raw("Scanning...")),
+                        Line::from(Span::raw(tilde_layout(
+                            &info.current.to_string_lossy(),
+                            width,
+                        ))),
+                        Line::from(Span::raw(tilde_layout(
+                            &format!(
+                                "Files: {}  Size: {}",
+                                info.num_files.separate_with_commas(),
+                                human_readable_size(info.total_size)
+                            ),
+                            width,
+                        ))),
+                    ])
+                    .style(self.palette.panel),
+                    upper_inner,
+                );
+            }
+            None => {
+                let items: Vec<ListItem> = self
+                    .file_list
+                    .iter()
+                    .skip(self.first_line)
+                    .take(upper_inner.height.into())
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let size = entry.size.unwrap_or(0);
+                        let ratio = match total_size {
+                            0 => 0.0,
+                            total_size => (size as f64) / (total_size as f64),
+                        };
+
+                        let bar_len =
+                            ((ratio * (BAR_WIDTH as f64)).round() as usize).min(BAR_WIDTH);
+
+                        let prefix = format!(
+                            "{:>7} {:>5.1}% [{}{}] ",
+                            entry.shown_size,
+                            ratio * 100.0,
+                            "#".repeat(bar_len),
+                            " ".repeat(BAR_WIDTH - bar_len),
+                        );
+
+                        let filename_max_width =
+                            (upper_inner.width as usize).saturating_sub(prefix.width());
+
+                        let is_selected = self.first_line + i == self.cursor_position;
+
+                        let filename = if is_selected && !matches!(focus, Focus::Focused) {
+                            tilde_layout(
+                                &std::iter::once('\u{2192}')
+                                    .chain(entry.label.chars().skip(1))
+                                    .collect::<String>(),
+                                filename_max_width,
+                            )
+                        } else {
+                            tilde_layout(&entry.label, filename_max_width)
+                        };
+
+                        let filename_width = filename.width();
+
+                        Span::styled(
+                            format!(
+                                "{}{}{:width$}",
+                                &prefix,
+                                &filename,
+                                "",
+                                width = filename_max_width.saturating_sub(filename_width)
+                            ),
+                            match (
+                                self.tagged_files.contains(entry),
+                                is_selected,
+                                matches!(focus, Focus::Focused),
+                            ) {
+                                (true, true, true) => self.palette.markselect,
+                                (true, true, false) => self.palette.marked,
+                                (true, false, _) => self.palette.marked,
+                                (false, true, true) => self.palette.selected,
+                                (false, _, _) => entry.style,
+                            },
+                        )
+                        .into()
+                    })
+                    .collect();
+
+                let items = List::new(items).highlight_style(match focus {
+                    Focus::Focused => self.palette.selected_bg,
+                    _ => Style::default(),
+                });
+
+                let mut state = ListState::default();
+                state.select(Some(self.cursor_position - self.first_line));
+
+                f.render_stateful_widget(items, upper_inner, &mut state);
+            }
+        }
+
+        let lower_block = Block::default()
+            .title_bottom(
+                Line::from(vec![
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                    Span::raw(tilde_layout(
+                        &format!(" Total: {} ", human_readable_size(total_size)),
+                        chunk.width.saturating_sub(4).into(),
+                    )),
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                ])
+                .right_aligned(),
+            )
+            .title_top(
+                Line::from(match self.tagged_files.is_empty() {
+                    true => Span::raw(symbols::line::NORMAL.horizontal),
+                    false => Span::styled(
+                        tilde_layout(
+                            &format!(
+                                " {} in {} file{} ",
+                                human_readable_size(
+                                    self.tagged_files
+                                        .iter()
+                                        .filter_map(|entry| entry.size)
+                                        .sum()
+                                ),
+                                self.tagged_files.len(),
+                                if self.tagged_files.len() == 1 {
+                                    ""
+                                } else {
+                                    "s"
+                                }
+                            ),
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        self.palette.marked,
+                    ),
+                })
+                .centered(),
+            )
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.panel);
+
+        let lower_inner = lower_block.inner(sections[1]);
+
+        f.render_widget(lower_block, sections[1]);
+
+        if self.scan_info.is_none() && !self.file_list.is_empty() {
+            f.render_widget(
+                Block::new()
+                    .title_top(
+                        Line::from(Span::raw(tilde_layout(
+                            &self.file_list[self.cursor_position].details,
+                            lower_inner.width.into(),
+                        )))
+                        .left_aligned(),
+                    )
+                    .style(self.palette.panel),
+                lower_inner,
+            );
+        }
+    }
+}
+
+impl Panel for DiskUsage {
+    fn change_focus(&mut self, focus: Focus) {
+        self.focus = focus;
+
+        if let Focus::Focused = focus {
+            self.pubsub_tx
+                .send(PubSub::ButtonLabels(
+                    LABELS.iter().map(|&label| String::from(label)).collect(),
+                ))
+                .unwrap();
+        }
+    }
+
+    fn get_selected_entry(&self) -> Option<Entry> {
+        match self.file_list.is_empty() {
+            true => None,
+            false => Some(self.file_list[self.cursor_position].clone()),
+        }
+    }
+
+    fn get_cwd(&self) -> Option<PathBuf> {
+        Some(self.cwd.clone())
+    }
+
+    fn get_tagged_files(&self) -> Vec<Entry> {
+        self.file_list
+            .iter()
+            .filter(|entry| self.tagged_files.contains(entry))
+            .cloned()
+            .collect()
+    }
+
+    fn get_selected_files(&self) -> Vec<Entry> {
+        match self.tagged_files.is_empty() {
+            true => self.get_selected_entry().into_iter().collect(),
+            false => self.get_tagged_files(),
+        }
+    }
+
+    fn reload(&mut self, selected_file: Option<&Path>) {
+        // Rescanning the whole tree would be too slow, so only the current directory gets
+        // reloaded, and its size difference gets propagated to its parents
+        if self.scan_info.is_none() {
+            match self.cwd.ancestors().find(|d| d.is_dir()) {
+                Some(cwd) if cwd.starts_with(&self.root) => {
+                    let old_file_list = match cwd == self.cwd {
+                        true => self.file_list.clone(),
+                        false => Vec::new(),
+                    };
+
+                    self.cwd = PathBuf::from(cwd);
+                    self.load_file_list(selected_file);
+                    self.update_cwd_size(&old_file_list);
+                }
+                _ => {
+                    self.cwd.clone_from(&self.root);
+                    self.scan_thread();
+                }
+            }
+        }
+    }
+}
+
+impl PanelComponent for DiskUsage {}
+
+fn disk_size(metadata: &Metadata) -> u64 {
+    metadata.blocks() * 512
+}
+
+/// Recursively compute the disk usage of `dir`, without following symlinks and without crossing
+/// filesystem boundaries.
+///
+/// Returns `None` if the scan gets aborted.
+#[allow(clippy::too_many_arguments)]
+fn scan_dir(
+    dir: &Path,
+    metadata: &Metadata,
+    dev: u64,
+    abort_rx: &Receiver<()>,
+    info: &mut ScanInfo,
+    last_write: &mut Instant,
+    on_info: &mut dyn FnMut(&ScanInfo),
+    dir_sizes: &mut HashMap<PathBuf, u64>,
+) -> Option<u64> {
+    let mut size = disk_size(metadata);
+
+    info.num_files += 1;
+    info.total_size += size;
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if !abort_rx.is_empty() {
+                return None;
+            }
+
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            if metadata.is_dir() && metadata.dev() == dev {
+                size += scan_dir(
+                    &entry.path(),
+                    &metadata,
+                    dev,
+                    abort_rx,
+                    info,
+                    last_write,
+                    on_info,
+                    dir_sizes,
+                )?;
+            } else {
+                let entry_size = disk_size(&metadata);
+
+                info.num_files += 1;
+                info.total_size += entry_size;
+                size += entry_size;
+            }
+
+            if last_write.elapsed().as_millis() >= 50 {
+                *last_write = Instant::now();
+                info.current = PathBuf::from(dir);
+                on_info(info);
+            }
+        }
+    }
+
+    dir_sizes.insert(PathBuf::from(dir), size);
+
+    Some(size)
+}
@@ -0,0 +1,404 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crossbeam_channel::Sender;
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use thousands::Separable;
+
+use crate::{
+    app::{centered_rect, render_shadow, PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::entry::{common_parent, get_path_list},
+    palette::Palette,
+    tilde_layout::tilde_layout,
+    widgets::button::Button,
+};
+
+const BUTTONS: &[&str] = &["Copy", "Move", "Delete", "Clear", "Close"];
+
+/// A dialog to view and edit the basket, the files collected from any directory, to copy,
+/// move or delete them all at once.
+#[derive(Debug)]
+pub struct DlgBasket {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    basket: Rc<RefCell<Vec<PathBuf>>>,
+    buttons: Vec<Button>,
+    section_focus_position: usize,
+    button_focus_position: usize,
+    cursor_position: usize,
+    first_line: usize,
+    rect: Rect,
+    button_rect: Rc<[Rect]>,
+}
+
+impl DlgBasket {
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        basket: &Rc<RefCell<Vec<PathBuf>>>,
+    ) -> DlgBasket {
+        DlgBasket {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            basket: Rc::clone(basket),
+            buttons: BUTTONS
+                .iter()
+                .map(|label| {
+                    Button::new(
+                        label,
+                        &palette.dialog,
+                        &palette.dialog_focus,
+                        &palette.dialog_title,
+                    )
+                })
+                .collect(),
+            section_focus_position: 0,
+            button_focus_position: 0,
+            cursor_position: 0,
+            first_line: 0,
+            rect: Rect::default(),
+            button_rect: Rc::new([]),
+        }
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.basket.borrow().len().saturating_sub(1))
+    }
+
+    fn clamp_first_line(&mut self) {
+        let len = self.basket.borrow().len();
+
+        if (self.first_line + (self.rect.height as usize)) > len {
+            self.first_line = len.saturating_sub(self.rect.height as usize);
+        }
+    }
+
+    fn remove_selected(&mut self) {
+        if self.cursor_position < self.basket.borrow().len() {
+            self.basket.borrow_mut().remove(self.cursor_position);
+        }
+
+        self.cursor_position = self.clamp_cursor(self.cursor_position);
+    }
+
+    fn on_button(&mut self, button: usize) {
+        match button {
+            0..=2 => {
+                let entries = get_path_list(Path::new("/"), &self.basket.borrow(), &self.palette);
+
+                if entries.is_empty() {
+                    return;
+                }
+
+                let cwd = common_parent(&entries).unwrap_or_else(|| PathBuf::from("/"));
+
+                self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+
+                match button {
+                    0 => self.pubsub_tx.send(PubSub::CpFlat(cwd, entries)).unwrap(),
+                    1 => self.pubsub_tx.send(PubSub::MvFlat(cwd, entries)).unwrap(),
+                    2 => {
+                        let question = match entries.len() {
+                            1 => format!("Delete {}?", entries[0].file_name),
+                            n => format!("Delete {} files/directories?", n),
+                        };
+
+                        self.pubsub_tx
+                            .send(PubSub::Question(
+                                String::from("Delete"),
+                                question,
+                                Box::new(PubSub::Rm(cwd, entries)),
+                            ))
+                            .unwrap();
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            3 => {
+                self.basket.borrow_mut().clear();
+                self.cursor_position = 0;
+                self.first_line = 0;
+            }
+            4 => self.pubsub_tx.send(PubSub::CloseDialog).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Component for DlgBasket {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        match key {
+            Key::Esc | Key::Char('q') | Key::Char('Q') | Key::F(10) | Key::Char('0') => {
+                self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+            }
+            Key::Char('\t') | Key::BackTab => {
+                self.section_focus_position ^= 1;
+            }
+            Key::Char('\n') | Key::Char(' ') => match self.section_focus_position {
+                0 => {
+                    let selected_file = self.basket.borrow().get(self.cursor_position).cloned();
+
+                    if let Some(selected_file) = selected_file {
+                        self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+                        self.pubsub_tx
+                            .send(PubSub::SelectFile(selected_file))
+                            .unwrap();
+                    }
+                }
+                _ => self.on_button(self.button_focus_position),
+            },
+            Key::Left | Key::Char('h') if self.section_focus_position == 1 => {
+                self.button_focus_position = self.button_focus_position.saturating_sub(1);
+            }
+            Key::Right | Key::Char('l')
+                if (self.section_focus_position == 1)
+                    && ((self.button_focus_position + 1) < self.buttons.len()) =>
+            {
+                self.button_focus_position += 1;
+            }
+            Key::Up | Key::Char('k') => match self.section_focus_position {
+                0 => {
+                    self.cursor_position =
+                        self.clamp_cursor(self.cursor_position.saturating_sub(1));
+                }
+                _ => self.section_focus_position = 0,
+            },
+            Key::Down | Key::Char('j') if self.section_focus_position == 0 => {
+                if (self.cursor_position + 1) < self.basket.borrow().len() {
+                    self.cursor_position += 1;
+                } else {
+                    self.section_focus_position = 1;
+                }
+            }
+            Key::Home | Key::CtrlHome | Key::Char('g') => self.cursor_position = 0,
+            Key::End | Key::CtrlEnd | Key::Char('G') => {
+                self.cursor_position = self.clamp_cursor(self.basket.borrow().len());
+            }
+            Key::PageUp | Key::Ctrl('b') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.cursor_position =
+                    self.clamp_cursor(self.cursor_position.saturating_sub(rect_height));
+
+                self.first_line = self.first_line.saturating_sub(rect_height);
+            }
+            Key::PageDown | Key::Ctrl('f') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.cursor_position =
+                    self.clamp_cursor(self.cursor_position.saturating_add(rect_height));
+
+                self.first_line = self.first_line.saturating_add(rect_height);
+                self.clamp_first_line();
+            }
+            Key::Delete | Key::Char('d') | Key::Char('-') if self.section_focus_position == 0 => {
+                self.remove_selected();
+            }
+            Key::Ctrl('c') => key_handled = false,
+            Key::Ctrl('l') => key_handled = false,
+            Key::Ctrl('z') => key_handled = false,
+            Key::Ctrl('o') => key_handled = false,
+            _ => (),
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        match button {
+            MouseButton::Left | MouseButton::Right => {
+                if self.rect.contains(mouse_position) {
+                    let new_cursor_position =
+                        self.first_line + ((mouse_position.y - self.rect.y) as usize);
+
+                    if new_cursor_position < self.basket.borrow().len() {
+                        self.section_focus_position = 0;
+                        self.cursor_position = new_cursor_position;
+                    }
+                }
+
+                let clicked_button = self
+                    .button_rect
+                    .iter()
+                    .position(|rect| rect.contains(mouse_position));
+
+                if let Some(i) = clicked_button {
+                    self.section_focus_position = 1;
+                    self.button_focus_position = i;
+
+                    if let MouseButton::Left = button {
+                        self.on_button(i);
+                    }
+                }
+            }
+            MouseButton::WheelUp => {
+                self.first_line = self.first_line.saturating_sub(1);
+
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                if self.cursor_position.saturating_sub(self.first_line) > rect_height {
+                    self.cursor_position = self.cursor_position.saturating_sub(1);
+                }
+            }
+            MouseButton::WheelDown => {
+                self.first_line = self.first_line.saturating_add(1);
+                self.clamp_first_line();
+
+                if self.first_line > self.cursor_position {
+                    self.cursor_position = self.first_line;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, _focus: Focus) {
+        let area = centered_rect(
+            (((chunk.width as usize) * 3) / 4) as u16,
+            (((chunk.height as usize) * 3) / 4) as u16,
+            chunk,
+        );
+
+        f.render_widget(Clear, area);
+        f.render_widget(Block::default().style(self.palette.dialog), area);
+        if let Some(shadow) = self.palette.shadow {
+            render_shadow(f, &area, &shadow);
+        }
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(centered_rect(
+                area.width.saturating_sub(2),
+                area.height.saturating_sub(2),
+                &area,
+            ));
+
+        // Upper section
+
+        let basket = self.basket.borrow();
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(Span::styled(
+                    tilde_layout(
+                        &format!(" Basket ({}) ", basket.len().separate_with_commas()),
+                        sections[0].width as usize,
+                    ),
+                    self.palette.dialog_title,
+                ))
+                .centered(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let upper_area = upper_block.inner(sections[0]);
+
+        let upper_height = (upper_area.height as usize).saturating_sub(1);
+
+        self.rect = upper_area;
+
+        if (self.first_line + (self.rect.height as usize)) > basket.len() {
+            self.first_line = basket.len().saturating_sub(self.rect.height as usize);
+        }
+
+        if self.first_line > self.cursor_position {
+            self.first_line = self.cursor_position;
+        }
+
+        if (self.cursor_position - self.first_line) > upper_height {
+            self.first_line = self.cursor_position.saturating_sub(upper_height);
+        }
+
+        f.render_widget(upper_block, sections[0]);
+
+        let items: Vec<ListItem> = basket
+            .iter()
+            .skip(self.first_line)
+            .take(upper_area.height.into())
+            .map(|file| {
+                ListItem::new(tilde_layout(
+                    &file.to_string_lossy(),
+                    upper_area.width as usize,
+                ))
+            })
+            .collect();
+
+        let list = List::new(items).highlight_style(match self.section_focus_position {
+            0 => self.palette.dialog_focus,
+            _ => self.palette.dialog,
+        });
+
+        let mut state = ListState::default();
+        state.select(Some(self.cursor_position - self.first_line));
+
+        f.render_stateful_widget(list, upper_area, &mut state);
+
+        drop(basket);
+
+        // Lower section
+
+        let lower_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.dialog);
+
+        let buttons_width: usize = self
+            .buttons
+            .iter()
+            .map(|button| button.width())
+            .sum::<usize>()
+            + self.buttons.len()
+            - 1;
+
+        let constraints: Vec<Constraint> = self
+            .buttons
+            .iter()
+            .enumerate()
+            .flat_map(|(i, button)| {
+                let spacing = match i {
+                    0 => None,
+                    _ => Some(Constraint::Length(1)),
+                };
+
+                spacing
+                    .into_iter()
+                    .chain([Constraint::Length(button.width() as u16)])
+            })
+            .collect();
+
+        let lower_area = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(centered_rect(
+                buttons_width as u16,
+                1,
+                &lower_block.inner(sections[1]),
+            ));
+
+        self.button_rect = lower_area.iter().step_by(2).copied().collect();
+
+        f.render_widget(lower_block, sections[1]);
+
+        for (i, button) in self.buttons.iter_mut().enumerate() {
+            button.render(
+                f,
+                &self.button_rect[i],
+                match (self.section_focus_position, self.button_focus_position) {
+                    (1, position) if position == i => Focus::Focused,
+                    (_, position) if position == i => Focus::Active,
+                    _ => Focus::Normal,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::{path::PathBuf, rc::Rc};
+
+use crossbeam_channel::Sender;
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use nucleo_matcher::{
+    pattern::{CaseMatching, Normalization, Pattern},
+    Config, Matcher, Utf32String,
+};
+use thousands::Separable;
+
+use crate::{
+    app::{centered_rect, render_shadow, PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    palette::Palette,
+    tilde_layout::tilde_layout,
+    widgets::input::Input,
+};
+
+/// A dialog to choose a directory to go to from a list, filtered with the same matcher as the
+/// find file dialog.
+///
+/// Unlike the find file dialog, the matching directories keep the order of the list (the most
+/// relevant first), instead of being sorted by their score.
+#[derive(Debug)]
+pub struct DlgDirList {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    title: String,
+    dirs: Vec<(PathBuf, Utf32String)>,
+    shown_dirs: Vec<PathBuf>,
+    matcher: Matcher,
+    input: Input,
+    cursor_position: usize,
+    first_line: usize,
+    rect: Rect,
+    input_rect: Rect,
+}
+
+impl DlgDirList {
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        title: &str,
+        dirs: &[PathBuf],
+    ) -> DlgDirList {
+        let mut dlg = DlgDirList {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            title: String::from(title),
+            dirs: dirs
+                .iter()
+                .map(|dir| (dir.clone(), Utf32String::from(dir.to_string_lossy())))
+                .collect(),
+            shown_dirs: Vec::new(),
+            matcher: Matcher::new(Config::DEFAULT.match_paths()),
+            input: Input::new(&palette.dialog_input, "", 0),
+            cursor_position: 0,
+            first_line: 0,
+            rect: Rect::default(),
+            input_rect: Rect::default(),
+        };
+
+        dlg.filter_dirs();
+
+        dlg
+    }
+
+    fn filter_dirs(&mut self) {
+        let filter = self.input.value();
+        let pattern = Pattern::parse(&filter, CaseMatching::Ignore, Normalization::Smart);
+
+        self.shown_dirs = self
+            .dirs
+            .iter()
+            .filter(|(_dir, key)| {
+                filter.is_empty() || pattern.score(key.slice(..), &mut self.matcher).is_some()
+            })
+            .map(|(dir, _key)| dir.clone())
+            .collect();
+
+        self.cursor_position = 0;
+        self.first_line = 0;
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.shown_dirs.len().saturating_sub(1))
+    }
+
+    fn clamp_first_line(&mut self) {
+        if (self.first_line + (self.rect.height as usize)) > self.shown_dirs.len() {
+            self.first_line = self
+                .shown_dirs
+                .len()
+                .saturating_sub(self.rect.height as usize);
+        }
+    }
+}
+
+impl Component for DlgDirList {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        let old_filter = self.input.value();
+
+        let input_handled = self.input.handle_key(key);
+
+        if self.input.value() != old_filter {
+            self.filter_dirs();
+        }
+
+        if !input_handled {
+            match key {
+                Key::Esc | Key::Char('q') | Key::Char('Q') | Key::F(10) | Key::Char('0') => {
+                    self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+                }
+                Key::Char('\n') | Key::Char(' ') => {
+                    self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+
+                    if !self.shown_dirs.is_empty() {
+                        self.pubsub_tx
+                            .send(PubSub::GotoDirectory(
+                                self.shown_dirs[self.cursor_position].clone(),
+                            ))
+                            .unwrap();
+                    }
+                }
+                Key::Up | Key::Char('k') => {
+                    self.cursor_position =
+                        self.clamp_cursor(self.cursor_position.saturating_add(1));
+                }
+                Key::Down | Key::Char('j') => {
+                    self.cursor_position =
+                        self.clamp_cursor(self.cursor_position.saturating_sub(1));
+                }
+                Key::Home | Key::CtrlHome | Key::Char('g') => {
+                    self.cursor_position = self.clamp_cursor(self.shown_dirs.len());
+                }
+                Key::End | Key::CtrlEnd | Key::Char('G') => {
+                    self.cursor_position = 0;
+                }
+                Key::PageUp | Key::Ctrl('b') => {
+                    let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                    self.cursor_position =
+                        self.clamp_cursor(self.cursor_position.saturating_add(rect_height));
+
+                    self.first_line = self.first_line.saturating_add(rect_height);
+                    self.clamp_first_line();
+                }
+                Key::PageDown | Key::Ctrl('f') => {
+                    let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                    self.cursor_position =
+                        self.clamp_cursor(self.cursor_position.saturating_sub(rect_height));
+
+                    self.first_line = self.first_line.saturating_sub(rect_height);
+                    self.clamp_first_line();
+                }
+                Key::Ctrl('c') => key_handled = false,
+                Key::Ctrl('l') => key_handled = false,
+                Key::Ctrl('z') => key_handled = false,
+                Key::Ctrl('o') => key_handled = false,
+                _ => (),
+            }
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        match button {
+            MouseButton::Left | MouseButton::Right => {
+                if self.rect.contains(mouse_position) {
+                    let new_cursor_position = self.first_line
+                        + ((self.rect.height - 1 - (mouse_position.y - self.rect.y)) as usize);
+
+                    if new_cursor_position < self.shown_dirs.len() {
+                        self.cursor_position = new_cursor_position;
+                    }
+                }
+
+                if self.input_rect.contains(mouse_position) {
+                    self.input.handle_mouse(button, mouse_position);
+                }
+            }
+            MouseButton::WheelUp => {
+                self.first_line = self.first_line.saturating_add(1);
+                self.clamp_first_line();
+
+                if self.first_line > self.cursor_position {
+                    self.cursor_position = self.first_line;
+                }
+            }
+            MouseButton::WheelDown => {
+                self.first_line = self.first_line.saturating_sub(1);
+
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                if (self.cursor_position - self.first_line) > rect_height {
+                    self.cursor_position = self.cursor_position.saturating_sub(1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, focus: Focus) {
+        let area = centered_rect(
+            (((chunk.width as usize) * 3) / 4) as u16,
+            (((chunk.height as usize) * 3) / 4) as u16,
+            chunk,
+        );
+
+        f.render_widget(Clear, area);
+        f.render_widget(Block::default().style(self.palette.dialog), area);
+        if let Some(shadow) = self.palette.shadow {
+            render_shadow(f, &area, &shadow);
+        }
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(centered_rect(
+                area.width.saturating_sub(2),
+                area.height.saturating_sub(2),
+                &area,
+            ));
+
+        // Upper section
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(Span::styled(
+                    tilde_layout(&format!(" {} ", self.title), sections[0].width as usize),
+                    self.palette.dialog_title,
+                ))
+                .centered(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let upper_area = upper_block.inner(sections[0]);
+
+        let upper_height = (upper_area.height as usize).saturating_sub(1);
+
+        self.rect = upper_area;
+        self.clamp_first_line();
+
+        if self.first_line > self.cursor_position {
+            self.first_line = self.cursor_position;
+        }
+
+        if (self.cursor_position - self.first_line) > upper_height {
+            self.first_line = self.cursor_position.saturating_sub(upper_height);
+        }
+
+        f.render_widget(upper_block, sections[0]);
+
+        let items: Vec<ListItem> = self
+            .shown_dirs
+            .iter()
+            .skip(self.first_line)
+            .take(upper_area.height.into())
+            .map(|dir| {
+                ListItem::new(tilde_layout(
+                    &dir.to_string_lossy(),
+                    upper_area.width as usize,
+                ))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .direction(ListDirection::BottomToTop)
+            .highlight_style(self.palette.dialog_focus);
+
+        let mut state = ListState::default();
+        state.select(Some(self.cursor_position - self.first_line));
+
+        f.render_stateful_widget(list, upper_area, &mut state);
+
+        // Lower section
+
+        let lower_block = Block::default()
+            .title_top(
+                Line::from(Span::raw(tilde_layout(
+                    &format!(
+                        " {}/{} ",
+                        self.shown_dirs.len().separate_with_commas(),
+                        self.dirs.len().separate_with_commas()
+                    ),
+                    sections[0].width as usize,
+                )))
+                .centered(),
+            )
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let lower_area = lower_block.inner(sections[1]);
+
+        self.input_rect = lower_area;
+
+        f.render_widget(lower_block, sections[1]);
+        self.input.render(f, &self.input_rect, focus);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    ffi::OsStr,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    thread,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{centered_rect, render_shadow, PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::child::output_cancelable,
+    palette::Palette,
+    tilde_layout::tilde_layout,
+    widgets::button::Button,
+};
+
+#[derive(Debug)]
+pub struct DlgPanelize {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    command: String,
+    result_rx: Receiver<Vec<PubSub>>,
+    cancel_tx: Sender<()>,
+    btn_cancel: Button,
+    btn_cancel_rect: Rect,
+}
+
+impl DlgPanelize {
+    /// Runs `command` through `shell` in a thread, and panelizes the files it prints.
+    ///
+    /// If the command fails, its exit status and the last line of its stderr are shown in an
+    /// error dialog, after panelizing the files it printed anyway.
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        cwd: &Path,
+        command: &str,
+        shell: &Path,
+    ) -> DlgPanelize {
+        let (result_tx, result_rx) = crossbeam_channel::unbounded();
+        let (cancel_tx, cancel_rx) = crossbeam_channel::unbounded();
+
+        let ps_tx = pubsub_tx.clone();
+        let cwd = PathBuf::from(cwd);
+        let cmd = String::from(command);
+        let shell = PathBuf::from(shell);
+
+        thread::spawn(move || {
+            let output = output_cancelable(
+                Command::new(&shell).args(["-c", &cmd]).current_dir(&cwd),
+                &cancel_rx,
+            );
+
+            let result = match output {
+                Ok(Some(output)) => {
+                    let files: Vec<PathBuf> = output
+                        .stdout
+                        .split(|&c| c == b'\n')
+                        .filter(|line| !line.is_empty())
+                        .map(|line| PathBuf::from(OsStr::from_bytes(line)))
+                        .collect();
+
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+
+                    let error = (!output.status.success()).then(|| {
+                        let message = match stderr.trim().lines().last() {
+                            Some(line) => format!("{} -- {}", output.status, line),
+                            None => output.status.to_string(),
+                        };
+
+                        PubSub::Error(message, None)
+                    });
+
+                    match (files.is_empty(), error) {
+                        (true, Some(error)) => vec![error],
+                        (_, error) => [PubSub::Panelized(cwd, cmd, files)]
+                            .into_iter()
+                            .chain(error)
+                            .collect(),
+                    }
+                }
+                Ok(None) => Vec::new(),
+                Err(e) => vec![PubSub::Error(e.to_string(), None)],
+            };
+
+            let _ = result_tx.send(result);
+            let _ = ps_tx.send(PubSub::ComponentThreadEvent);
+        });
+
+        DlgPanelize {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            command: String::from(command),
+            result_rx,
+            cancel_tx,
+            btn_cancel: Button::new(
+                "Cancel",
+                &palette.dialog,
+                &palette.dialog_focus,
+                &palette.dialog_title,
+            ),
+            btn_cancel_rect: Rect::default(),
+        }
+    }
+
+    // The thread kills the command, and closes the dialog
+    fn on_cancel(&mut self) {
+        let _ = self.cancel_tx.send(());
+    }
+}
+
+impl Component for DlgPanelize {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        match key {
+            Key::Esc | Key::Char('q') | Key::Char('Q') | Key::F(10) | Key::Char('0') => {
+                self.on_cancel();
+            }
+            Key::Char('\n') | Key::Char(' ') => self.on_cancel(),
+            Key::Ctrl('c') => key_handled = false,
+            Key::Ctrl('l') => key_handled = false,
+            Key::Ctrl('z') => key_handled = false,
+            Key::Ctrl('o') => key_handled = false,
+            _ => (),
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        if self.btn_cancel_rect.contains(mouse_position) {
+            if let MouseButton::Left = button {
+                self.on_cancel();
+            }
+        }
+    }
+
+    fn handle_pubsub(&mut self, event: &PubSub) {
+        if let PubSub::ComponentThreadEvent = event {
+            if let Ok(result) = self.result_rx.try_recv() {
+                self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+
+                for pubsub in result {
+                    self.pubsub_tx.send(pubsub).unwrap();
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, _focus: Focus) {
+        let message = format!("Running {}", self.command);
+
+        let area = centered_rect((message.width() + 6) as u16, 7, chunk);
+
+        f.render_widget(Clear, area);
+        f.render_widget(Block::default().style(self.palette.dialog), area);
+        if let Some(shadow) = self.palette.shadow {
+            render_shadow(f, &area, &shadow);
+        }
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Length(3)])
+            .split(centered_rect(
+                area.width.saturating_sub(2),
+                area.height.saturating_sub(2),
+                &area,
+            ));
+
+        // Upper section
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(Span::styled(
+                    tilde_layout(" Panelize ", sections[0].width as usize),
+                    self.palette.dialog_title,
+                ))
+                .centered(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let upper_area = upper_block.inner(sections[0]);
+
+        let message = Paragraph::new(Span::raw(tilde_layout(&message, upper_area.width as usize)));
+
+        f.render_widget(upper_block, sections[0]);
+        f.render_widget(message, upper_area);
+
+        // Lower section
+
+        let lower_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.dialog);
+
+        let lower_area = centered_rect(
+            self.btn_cancel.width() as u16,
+            1,
+            &lower_block.inner(sections[1]),
+        );
+
+        self.btn_cancel_rect = lower_area;
+
+        f.render_widget(lower_block, sections[1]);
+        self.btn_cancel
+            .render(f, &self.btn_cancel_rect, Focus::Focused);
+    }
+}
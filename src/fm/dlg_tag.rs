@@ -0,0 +1,524 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crossbeam_channel::Sender;
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use crate::{
+    app::{centered_rect, render_shadow, PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::{
+        tag_criteria::{FileType, TagCriteria},
+        tag_sets::TagSets,
+    },
+    palette::Palette,
+    tilde_layout::tilde_layout,
+    widgets::{button::Button, check_box::CheckBox, input::Input, radio_box::RadioBox},
+};
+
+const LABELS: &[&str] = &[
+    "Name regex:",
+    "Size:",
+    "Modified:",
+    "Owner:",
+    "Permissions:",
+    "Tag set:",
+];
+
+const TAG_SET_INPUT: usize = 5;
+
+const BUTTONS: &[&str] = &["Tag", "Untag", "Load", "Save", "Delete", "Cancel"];
+
+/// A dialog to tag (or untag) the files matching some criteria, that can be saved by name as
+/// tag sets, to be loaded again later.
+#[derive(Debug)]
+pub struct DlgTag {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    tag_sets: Rc<RefCell<TagSets>>,
+    inputs: Vec<Input>,
+    radio: RadioBox,
+    check_boxes: Vec<CheckBox>,
+    buttons: Vec<Button>,
+    section_focus_position: usize,
+    input_focus_position: usize,
+    middle_focus_position: usize,
+    check_focus_position: usize,
+    button_focus_position: usize,
+    input_rect: Rc<[Rect]>,
+    radio_rect: Rect,
+    check_box_rect: Rc<[Rect]>,
+    button_rect: Rc<[Rect]>,
+}
+
+impl DlgTag {
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        tag_sets: &Rc<RefCell<TagSets>>,
+        criteria: &TagCriteria,
+    ) -> DlgTag {
+        let mut dlg = DlgTag {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            tag_sets: Rc::clone(tag_sets),
+            inputs: LABELS
+                .iter()
+                .map(|_label| Input::new(&palette.dialog_input, "", 0))
+                .collect(),
+            radio: RadioBox::new(
+                ["Any type", "Files", "Directories", "Symlinks"],
+                &palette.dialog,
+                &palette.dialog_focus,
+                0,
+            ),
+            check_boxes: Vec::new(),
+            buttons: BUTTONS
+                .iter()
+                .map(|label| {
+                    Button::new(
+                        label,
+                        &palette.dialog,
+                        &palette.dialog_focus,
+                        &palette.dialog_title,
+                    )
+                })
+                .collect(),
+            section_focus_position: 0,
+            input_focus_position: 0,
+            middle_focus_position: 0,
+            check_focus_position: 0,
+            button_focus_position: 0,
+            input_rect: Rc::new([]),
+            radio_rect: Rect::default(),
+            check_box_rect: Rc::new([]),
+            button_rect: Rc::new([]),
+        };
+
+        dlg.set_criteria(criteria);
+
+        dlg
+    }
+
+    fn set_criteria(&mut self, criteria: &TagCriteria) {
+        let values = [
+            &criteria.name,
+            &criteria.size,
+            &criteria.mtime,
+            &criteria.owner,
+            &criteria.permissions,
+        ];
+
+        for (input, value) in self.inputs.iter_mut().zip(values) {
+            *input = Input::new(&self.palette.dialog_input, value, value.chars().count());
+        }
+
+        self.radio = RadioBox::new(
+            ["Any type", "Files", "Directories", "Symlinks"],
+            &self.palette.dialog,
+            &self.palette.dialog_focus,
+            match criteria.file_type {
+                FileType::Any => 0,
+                FileType::File => 1,
+                FileType::Directory => 2,
+                FileType::Symlink => 3,
+            },
+        );
+
+        self.check_boxes = vec![
+            CheckBox::new(
+                "Newer than in the other panel",
+                &self.palette.dialog,
+                &self.palette.dialog_focus,
+                criteria.newer,
+            ),
+            CheckBox::new(
+                "Invert selection",
+                &self.palette.dialog,
+                &self.palette.dialog_focus,
+                criteria.invert,
+            ),
+        ];
+    }
+
+    fn get_criteria(&mut self) -> TagCriteria {
+        TagCriteria {
+            name: self.inputs[0].value(),
+            size: self.inputs[1].value(),
+            mtime: self.inputs[2].value(),
+            owner: self.inputs[3].value(),
+            permissions: self.inputs[4].value(),
+            file_type: match self.radio.value() {
+                0 => FileType::Any,
+                1 => FileType::File,
+                2 => FileType::Directory,
+                3 => FileType::Symlink,
+                _ => unreachable!(),
+            },
+            newer: self.check_boxes[0].value(),
+            invert: self.check_boxes[1].value(),
+        }
+    }
+
+    fn on_button(&mut self, button: usize) {
+        let tag_set = self.inputs[TAG_SET_INPUT].value();
+
+        match button {
+            0 => {
+                let criteria = self.get_criteria();
+
+                self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+                self.pubsub_tx.send(PubSub::TagMatching(criteria)).unwrap();
+            }
+            1 => {
+                let criteria = self.get_criteria();
+
+                self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+                self.pubsub_tx
+                    .send(PubSub::UntagMatching(criteria))
+                    .unwrap();
+            }
+            2 => {
+                let criteria = self.tag_sets.borrow().get(&tag_set);
+
+                if let Some(criteria) = criteria {
+                    self.set_criteria(&criteria);
+                }
+            }
+            3 => {
+                if !tag_set.is_empty() {
+                    let criteria = self.get_criteria();
+
+                    self.tag_sets.borrow_mut().insert(&tag_set, &criteria);
+                }
+            }
+            4 => {
+                self.tag_sets.borrow_mut().remove(&tag_set);
+            }
+            5 => self.pubsub_tx.send(PubSub::CloseDialog).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Component for DlgTag {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        let input_handled = match self.section_focus_position {
+            0 => self.inputs[self.input_focus_position].handle_key(key),
+            1 => match self.middle_focus_position {
+                0 => self.radio.handle_key(key),
+                1 => self.check_boxes[self.check_focus_position].handle_key(key),
+                _ => unreachable!(),
+            },
+            2 => false,
+            _ => unreachable!(),
+        };
+
+        if !input_handled {
+            match key {
+                Key::Esc | Key::Char('q') | Key::Char('Q') | Key::F(10) | Key::Char('0') => {
+                    self.pubsub_tx.send(PubSub::CloseDialog).unwrap();
+                }
+                Key::Char('\n') | Key::Char(' ') => match self.section_focus_position {
+                    2 => self.on_button(self.button_focus_position),
+                    _ => self.on_button(0),
+                },
+                Key::BackTab => {
+                    self.section_focus_position =
+                        ((self.section_focus_position as isize) - 1).rem_euclid(3) as usize;
+                }
+                Key::Char('\t') => {
+                    self.section_focus_position = (self.section_focus_position + 1) % 3;
+                }
+                Key::Up | Key::Char('k') => match self.section_focus_position {
+                    0 => {
+                        self.input_focus_position = self.input_focus_position.saturating_sub(1);
+                    }
+                    1 => match self.middle_focus_position {
+                        1 if self.check_focus_position > 0 => self.check_focus_position -= 1,
+                        _ => {
+                            self.section_focus_position = 0;
+                            self.input_focus_position = self.inputs.len() - 1;
+                        }
+                    },
+                    _ => self.section_focus_position -= 1,
+                },
+                Key::Down | Key::Char('j') => match self.section_focus_position {
+                    0 => {
+                        if (self.input_focus_position + 1) < self.inputs.len() {
+                            self.input_focus_position += 1;
+                        } else {
+                            self.section_focus_position = 1;
+                        }
+                    }
+                    1 => match self.middle_focus_position {
+                        1 if (self.check_focus_position + 1) < self.check_boxes.len() => {
+                            self.check_focus_position += 1;
+                        }
+                        _ => self.section_focus_position = 2,
+                    },
+                    _ => (),
+                },
+                Key::Left | Key::Char('h') => match self.section_focus_position {
+                    0 => (),
+                    1 => self.middle_focus_position = 0,
+                    2 => {
+                        self.button_focus_position = self.button_focus_position.saturating_sub(1);
+                    }
+                    _ => unreachable!(),
+                },
+                Key::Right | Key::Char('l') => match self.section_focus_position {
+                    0 => (),
+                    1 => self.middle_focus_position = 1,
+                    2 => {
+                        if (self.button_focus_position + 1) < self.buttons.len() {
+                            self.button_focus_position += 1;
+                        }
+                    }
+                    _ => unreachable!(),
+                },
+                Key::Ctrl('c') => key_handled = false,
+                Key::Ctrl('l') => key_handled = false,
+                Key::Ctrl('z') => key_handled = false,
+                Key::Ctrl('o') => key_handled = false,
+                _ => (),
+            }
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        if matches!(button, MouseButton::Left | MouseButton::Right) {
+            for (i, rect) in self.input_rect.iter().enumerate() {
+                if rect.contains(mouse_position) {
+                    self.section_focus_position = 0;
+                    self.input_focus_position = i;
+
+                    self.inputs[i].handle_mouse(button, mouse_position);
+                }
+            }
+
+            if self.radio_rect.contains(mouse_position) {
+                self.section_focus_position = 1;
+                self.middle_focus_position = 0;
+
+                self.radio.handle_mouse(button, mouse_position);
+            }
+
+            for (i, rect) in self.check_box_rect.iter().enumerate() {
+                if rect.contains(mouse_position) {
+                    self.section_focus_position = 1;
+                    self.middle_focus_position = 1;
+                    self.check_focus_position = i;
+
+                    self.check_boxes[i].handle_mouse(button, mouse_position);
+                }
+            }
+
+            let clicked_button = self
+                .button_rect
+                .iter()
+                .position(|rect| rect.contains(mouse_position));
+
+            if let Some(i) = clicked_button {
+                self.section_focus_position = 2;
+                self.button_focus_position = i;
+
+                if let MouseButton::Left = button {
+                    self.on_button(i);
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, _focus: Focus) {
+        let area = centered_rect(64, 18, chunk);
+
+        f.render_widget(Clear, area);
+        f.render_widget(Block::default().style(self.palette.dialog), area);
+        if let Some(shadow) = self.palette.shadow {
+            render_shadow(f, &area, &shadow);
+        }
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(8),
+                Constraint::Length(5),
+                Constraint::Length(3),
+            ])
+            .split(centered_rect(
+                area.width.saturating_sub(2),
+                area.height.saturating_sub(2),
+                &area,
+            ));
+
+        // Upper section
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(Span::styled(" Tag files ", self.palette.dialog_title)).centered(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let upper_area = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); self.inputs.len() + 1])
+            .split(upper_block.inner(sections[0]));
+
+        let rows: Vec<Rc<[Rect]>> = upper_area
+            .iter()
+            .map(|row| {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(13), Constraint::Min(1)])
+                    .split(*row)
+            })
+            .collect();
+
+        self.input_rect = rows
+            .iter()
+            .take(self.inputs.len())
+            .map(|row| row[1])
+            .collect();
+
+        f.render_widget(upper_block, sections[0]);
+
+        for (i, label) in LABELS.iter().enumerate() {
+            f.render_widget(Paragraph::new(Span::raw(*label)), rows[i][0]);
+
+            self.inputs[i].render(
+                f,
+                &self.input_rect[i],
+                match (self.section_focus_position, self.input_focus_position) {
+                    (0, position) if position == i => Focus::Focused,
+                    _ => Focus::Normal,
+                },
+            );
+        }
+
+        let tag_sets = self.tag_sets.borrow().names().join(", ");
+        let saved_area = rows[self.inputs.len()][1];
+
+        f.render_widget(
+            Paragraph::new(Span::raw(tilde_layout(
+                &match tag_sets.is_empty() {
+                    true => String::from("No saved tag sets"),
+                    false => format!("Saved: {}", tag_sets),
+                },
+                saved_area.width as usize,
+            ))),
+            saved_area,
+        );
+
+        // Middle section
+
+        let middle_block = Block::default()
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .border_set(MIDDLE_BORDER_SET)
+            .padding(Padding::horizontal(1))
+            .style(self.palette.dialog);
+
+        let middle_sections = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(self.radio.width() as u16 + 2),
+                Constraint::Min(1),
+            ])
+            .split(middle_block.inner(sections[1]));
+
+        self.radio_rect = middle_sections[0];
+
+        self.check_box_rect = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); self.check_boxes.len()])
+            .split(middle_sections[1]);
+
+        f.render_widget(middle_block, sections[1]);
+
+        self.radio.render(
+            f,
+            &self.radio_rect,
+            match (self.section_focus_position, self.middle_focus_position) {
+                (1, 0) => Focus::Focused,
+                _ => Focus::Normal,
+            },
+        );
+
+        for (i, check_box) in self.check_boxes.iter_mut().enumerate() {
+            check_box.render(
+                f,
+                &self.check_box_rect[i],
+                if (self.section_focus_position == 1)
+                    && (self.middle_focus_position == 1)
+                    && (self.check_focus_position == i)
+                {
+                    Focus::Focused
+                } else {
+                    Focus::Normal
+                },
+            );
+        }
+
+        // Lower section
+
+        let lower_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.dialog);
+
+        let buttons_width: usize = self
+            .buttons
+            .iter()
+            .map(|button| button.width())
+            .sum::<usize>()
+            + self.buttons.len()
+            - 1;
+
+        let constraints: Vec<Constraint> = self
+            .buttons
+            .iter()
+            .enumerate()
+            .flat_map(|(i, button)| {
+                let spacing = match i {
+                    0 => None,
+                    _ => Some(Constraint::Length(1)),
+                };
+
+                spacing
+                    .into_iter()
+                    .chain([Constraint::Length(button.width() as u16)])
+            })
+            .collect();
+
+        let lower_area = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(centered_rect(
+                buttons_width as u16,
+                1,
+                &lower_block.inner(sections[2]),
+            ));
+
+        self.button_rect = lower_area.iter().step_by(2).copied().collect();
+
+        f.render_widget(lower_block, sections[2]);
+
+        for (i, button) in self.buttons.iter_mut().enumerate() {
+            button.render(
+                f,
+                &self.button_rect[i],
+                match (self.section_focus_position, self.button_focus_position) {
+                    (2, position) if position == i => Focus::Focused,
+                    (_, position) if position == i => Focus::Active,
+                    _ => Focus::Normal,
+                },
+            );
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use std::{
     cmp::Ordering,
+    collections::HashSet,
     fs::{self, read_dir, Metadata},
+    io,
     os::unix::fs::{FileTypeExt, MetadataExt},
     path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
@@ -15,11 +18,16 @@ use nucleo_matcher::{
     Config, Matcher, Utf32String,
 };
 use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
 use thousands::Separable;
 use uzers::{Groups, Users, UsersCache};
 
 use crate::{
-    fm::app::{format_date, human_readable_size, natsort_key, tar_suffix},
+    config::Column,
+    fm::{
+        app::{format_date, human_readable_size, natsort_key, tar_suffix},
+        git_status::GitStatus,
+    },
     palette::Palette,
     stat::filemode,
 };
@@ -71,8 +79,11 @@ pub struct Entry {
     pub shown_mtime: String,
     pub size: Option<u64>,
     pub shown_size: String,
+    pub owner: String,
+    pub group: String,
     pub details: String,
     pub link_target: Option<PathBuf>,
+    pub git_status: Option<GitStatus>,
 }
 
 impl PartialEq for Entry {
@@ -81,10 +92,173 @@ impl PartialEq for Entry {
     }
 }
 
+/// A request to list the files of a directory in the background.
+///
+/// With `flat_depth` set, the files get listed recursively, up to that many levels of
+/// subdirectories (0 means no limit).
+#[derive(Debug, Clone)]
+pub struct FileListRequest {
+    pub cwd: PathBuf,
+    pub flat_depth: Option<usize>,
+}
+
+fn make_entry(
+    shown_file: PathBuf,
+    metadata: Metadata,
+    palette: &Palette,
+    users_cache: &UsersCache,
+) -> Entry {
+    let file_name = shown_file
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_else(|| shown_file.to_string_lossy().to_string());
+    let extension: String = natsort_key(&tar_suffix(&file_name));
+
+    let (stat, label, style) = if metadata.is_symlink() {
+        match fs::metadata(&shown_file) {
+            Ok(stat) => {
+                if stat.is_dir() {
+                    (stat, format!("~{}", file_name), palette.dir_symlink)
+                } else {
+                    (
+                        stat,
+                        format!("@{}", file_name),
+                        match ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
+                            true => palette.archive,
+                            false => palette.symlink,
+                        },
+                    )
+                }
+            }
+            Err(_) => (
+                metadata.clone(),
+                format!("!{}", file_name),
+                palette.stalelink,
+            ),
+        }
+    } else if metadata.is_dir() {
+        (
+            metadata.clone(),
+            format!("/{}", file_name),
+            palette.directory,
+        )
+    } else if metadata.file_type().is_char_device() {
+        (metadata.clone(), format!("-{}", file_name), palette.device)
+    } else if metadata.file_type().is_block_device() {
+        (metadata.clone(), format!("+{}", file_name), palette.device)
+    } else if metadata.file_type().is_fifo() {
+        (metadata.clone(), format!("|{}", file_name), palette.special)
+    } else if metadata.file_type().is_socket() {
+        (metadata.clone(), format!("={}", file_name), palette.special)
+    } else if (metadata.mode() & (S_IXUSR | S_IXGRP | S_IXOTH)) != 0 {
+        (
+            metadata.clone(),
+            format!("*{}", file_name),
+            match ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
+                true => palette.archive,
+                false => palette.executable,
+            },
+        )
+    } else {
+        (
+            metadata.clone(),
+            format!(" {}", file_name),
+            match ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
+                true => palette.archive,
+                false => palette.panel,
+            },
+        )
+    };
+
+    let (size, shown_size) = if stat.is_dir() {
+        (None, String::from("DIR"))
+    } else if metadata.file_type().is_char_device() || metadata.file_type().is_block_device() {
+        let rdev = metadata.rdev();
+        let major = rustix::fs::major(rdev);
+        let minor = rustix::fs::minor(rdev);
+
+        (
+            // This works as long as size is u64 and major and minor are u32
+            Some(((major as u64) << 32) | (minor as u64)),
+            format!("{},{}", major, minor),
+        )
+    } else {
+        let size = metadata.len();
+
+        (Some(size), human_readable_size(size))
+    };
+
+    let uid = match users_cache.get_user_by_uid(metadata.uid()) {
+        Some(uid) => uid.name().to_string_lossy().to_string(),
+        None => metadata.uid().to_string(),
+    };
+
+    let gid = match users_cache.get_group_by_gid(metadata.gid()) {
+        Some(gid) => gid.name().to_string_lossy().to_string(),
+        None => metadata.gid().to_string(),
+    };
+
+    let details = format!(
+        "{} {} {} {}",
+        filemode(metadata.mode()),
+        metadata.nlink(),
+        uid,
+        gid
+    );
+
+    let (details, link_target) = if metadata.is_symlink() {
+        match fs::read_link(&shown_file) {
+            Ok(link_target) => (
+                format!("{} -> {}", details, link_target.to_string_lossy()),
+                Some(match link_target.is_absolute() {
+                    true => link_target.clean(),
+                    false => [
+                        shown_file.parent().unwrap_or(Path::new("/")),
+                        link_target.as_path(),
+                    ]
+                    .iter()
+                    .collect::<PathBuf>()
+                    .clean(),
+                }),
+            ),
+            Err(_) => (format!("{} -> ?", details), Some(shown_file.clone())),
+        }
+    } else {
+        (format!("{} {}", details, file_name), None)
+    };
+
+    let shown_mtime = match metadata.modified() {
+        Ok(modified) => format_date(modified),
+        Err(_) => String::from("???????"),
+    };
+
+    let key = natsort_key(&file_name);
+
+    Entry {
+        file: shown_file,
+        filter_key: Utf32String::from(key.as_ref()),
+        sort_key: key,
+        file_name,
+        extension,
+        label,
+        style,
+        lstat: metadata,
+        stat,
+        shown_mtime,
+        size,
+        shown_size,
+        owner: uid,
+        group: gid,
+        details,
+        link_target,
+        git_status: None,
+    }
+}
+
 pub fn get_file_list(
     cwd: &Path,
     palette: &Palette,
-    file_list_rx: Option<Receiver<PathBuf>>,
+    file_list_rx: Option<Receiver<FileListRequest>>,
 ) -> Result<Vec<Entry>> {
     let users_cache = UsersCache::new();
 
@@ -104,155 +278,122 @@ pub fn get_file_list(
                 }
             }
 
-            let shown_file = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
-            let extension: String = natsort_key(&tar_suffix(&file_name));
-
-            let (stat, label, style) = if metadata.is_symlink() {
-                match fs::metadata(entry.path()) {
-                    Ok(stat) => {
-                        if stat.is_dir() {
-                            (stat, format!("~{}", file_name), palette.dir_symlink)
-                        } else {
-                            (
-                                stat,
-                                format!("@{}", file_name),
-                                match ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
-                                    true => palette.archive,
-                                    false => palette.symlink,
-                                },
-                            )
-                        }
-                    }
-                    Err(_) => (
-                        metadata.clone(),
-                        format!("!{}", file_name),
-                        palette.stalelink,
-                    ),
-                }
-            } else if metadata.is_dir() {
-                (
-                    metadata.clone(),
-                    format!("/{}", file_name),
-                    palette.directory,
-                )
-            } else if metadata.file_type().is_char_device() {
-                (metadata.clone(), format!("-{}", file_name), palette.device)
-            } else if metadata.file_type().is_block_device() {
-                (metadata.clone(), format!("+{}", file_name), palette.device)
-            } else if metadata.file_type().is_fifo() {
-                (metadata.clone(), format!("|{}", file_name), palette.special)
-            } else if metadata.file_type().is_socket() {
-                (metadata.clone(), format!("={}", file_name), palette.special)
-            } else if (metadata.mode() & (S_IXUSR | S_IXGRP | S_IXOTH)) != 0 {
-                (
-                    metadata.clone(),
-                    format!("*{}", file_name),
-                    match ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
-                        true => palette.archive,
-                        false => palette.executable,
-                    },
-                )
-            } else {
-                (
-                    metadata.clone(),
-                    format!(" {}", file_name),
-                    match ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
-                        true => palette.archive,
-                        false => palette.panel,
-                    },
-                )
-            };
-
-            let (size, shown_size) = if stat.is_dir() {
-                (None, String::from("DIR"))
-            } else if metadata.file_type().is_char_device()
-                || metadata.file_type().is_block_device()
-            {
-                let rdev = metadata.rdev();
-                let major = rustix::fs::major(rdev);
-                let minor = rustix::fs::minor(rdev);
-
-                (
-                    // This works as long as size is u64 and major and minor are u32
-                    Some(((major as u64) << 32) | (minor as u64)),
-                    format!("{},{}", major, minor),
-                )
-            } else {
-                let size = metadata.len();
-
-                (Some(size), human_readable_size(size))
-            };
-
-            let uid = match users_cache.get_user_by_uid(metadata.uid()) {
-                Some(uid) => uid.name().to_string_lossy().to_string(),
-                None => metadata.uid().to_string(),
-            };
-
-            let gid = match users_cache.get_group_by_gid(metadata.gid()) {
-                Some(gid) => gid.name().to_string_lossy().to_string(),
-                None => metadata.gid().to_string(),
-            };
-
-            let details = format!(
-                "{} {} {} {}",
-                filemode(metadata.mode()),
-                metadata.nlink(),
-                uid,
-                gid
-            );
-
-            let (details, link_target) = if metadata.is_symlink() {
-                match fs::read_link(entry.path()) {
-                    Ok(link_target) => (
-                        format!("{} -> {}", details, link_target.to_string_lossy()),
-                        Some(match link_target.is_absolute() {
-                            true => link_target.clean(),
-                            false => [
-                                shown_file.parent().unwrap_or(Path::new("/")),
-                                link_target.as_path(),
-                            ]
-                            .iter()
-                            .collect::<PathBuf>()
-                            .clean(),
-                        }),
-                    ),
-                    Err(_) => (format!("{} -> ?", details), Some(shown_file.clone())),
-                }
-            } else {
-                (format!("{} {}", details, file_name), None)
-            };
+            Some(make_entry(entry.path(), metadata, palette, &users_cache))
+        })
+        .collect::<Vec<Entry>>())
+}
+
+pub fn get_flat_file_list(
+    cwd: &Path,
+    palette: &Palette,
+    flat_depth: usize,
+    file_list_rx: Option<Receiver<FileListRequest>>,
+) -> Result<Vec<Entry>> {
+    let mut file_list = get_file_list(cwd, palette, file_list_rx.clone())?;
+    let mut dirs: Vec<(PathBuf, usize)> = file_list
+        .iter()
+        .filter(|entry| entry.lstat.is_dir())
+        .map(|entry| (entry.file.clone(), 1))
+        .collect();
+
+    // Symlinks to directories are not followed, so that there are no loops
+    while let Some((dir, depth)) = dirs.pop() {
+        if flat_depth != 0 && depth > flat_depth {
+            continue;
+        }
+
+        if let Some(rx) = &file_list_rx {
+            // Stop processing the current file list if a new file listing request arrived in the
+            // meantime
+            if !rx.is_empty() {
+                break;
+            }
+        }
+
+        for entry in get_file_list(&dir, palette, file_list_rx.clone()).unwrap_or_default() {
+            if entry.lstat.is_dir() {
+                dirs.push((entry.file.clone(), depth + 1));
+            }
+
+            // The entries in the subdirectories are shown, filtered and sorted by their path
+            // relative to cwd
+            let rel_file = entry
+                .file
+                .strip_prefix(cwd)
+                .unwrap_or(&entry.file)
+                .to_string_lossy()
+                .to_string();
+
+            let key = natsort_key(&rel_file);
+
+            file_list.push(Entry {
+                label: format!("{}{}", entry.label.chars().next().unwrap_or(' '), rel_file),
+                filter_key: Utf32String::from(key.as_ref()),
+                sort_key: key,
+                ..entry
+            });
+        }
+    }
+
+    Ok(file_list)
+}
 
-            let shown_mtime = match metadata.modified() {
-                Ok(modified) => format_date(modified),
-                Err(_) => String::from("???????"),
-            };
+/// The entries of a list of files, that may be anywhere on the file system, like the output of
+/// `find`. The relative paths are relative to `cwd`, and the missing files are skipped.
+pub fn get_path_list(cwd: &Path, files: &[PathBuf], palette: &Palette) -> Vec<Entry> {
+    let users_cache = UsersCache::new();
+    let mut seen: HashSet<PathBuf> = HashSet::new();
 
-            let key = natsort_key(&file_name);
+    files
+        .iter()
+        .map(|file| cwd.join(file).clean())
+        .filter(|file| seen.insert(file.clone()))
+        .filter_map(|file| {
+            let metadata = fs::symlink_metadata(&file).ok()?;
+            let entry = make_entry(file, metadata, palette, &users_cache);
+
+            // Like in the flat listing, the entries are shown by their path relative to cwd
+            let shown_file = entry
+                .file
+                .strip_prefix(cwd)
+                .unwrap_or(&entry.file)
+                .to_string_lossy()
+                .to_string();
+
+            let key = natsort_key(&shown_file);
 
             Some(Entry {
-                file: shown_file,
+                label: format!(
+                    "{}{}",
+                    entry.label.chars().next().unwrap_or(' '),
+                    shown_file
+                ),
                 filter_key: Utf32String::from(key.as_ref()),
                 sort_key: key,
-                file_name,
-                extension,
-                label,
-                style,
-                lstat: metadata,
-                stat,
-                shown_mtime,
-                size,
-                shown_size,
-                details,
-                link_target,
+                ..entry
             })
         })
-        .collect::<Vec<Entry>>())
+        .collect()
+}
+
+/// The deepest directory that contains all the given files, so that the files in different
+/// directories keep their relative paths when copied or moved
+pub fn common_parent(files: &[Entry]) -> Option<PathBuf> {
+    files.iter().filter_map(|entry| entry.file.parent()).fold(
+        None,
+        |common: Option<PathBuf>, parent| match common {
+            Some(common) => common
+                .ancestors()
+                .find(|d| parent.starts_with(d))
+                .map(PathBuf::from),
+            None => Some(PathBuf::from(parent)),
+        },
+    )
 }
 
 pub fn count_directories(
     file_list: &[Entry],
-    file_list_rx: Option<Receiver<PathBuf>>,
+    file_list_rx: Option<Receiver<FileListRequest>>,
 ) -> Vec<Entry> {
     file_list
         .iter()
@@ -287,7 +428,40 @@ pub fn count_directories(
         .collect()
 }
 
-#[derive(Debug, Clone, Copy)]
+pub fn column_text(entry: &Entry, column: Column) -> String {
+    match column {
+        Column::Name => entry.label.clone(),
+        Column::Size => entry.shown_size.clone(),
+        Column::Mtime => entry.shown_mtime.clone(),
+        Column::Atime => match entry.lstat.accessed() {
+            Ok(accessed) => format_date(accessed),
+            Err(_) => String::from("???????"),
+        },
+        Column::Ctime => match u64::try_from(entry.lstat.ctime()) {
+            Ok(ctime) => {
+                format_date(UNIX_EPOCH + Duration::new(ctime, entry.lstat.ctime_nsec() as u32))
+            }
+            Err(_) => String::from("???????"),
+        },
+        Column::Permissions => filemode(entry.lstat.mode()),
+        Column::Octal => format!("{:04o}", entry.lstat.mode() & 0o7777),
+        Column::Git => entry
+            .git_status
+            .map(|git_status| String::from(git_status.marker()))
+            .unwrap_or_default(),
+        Column::Owner => entry.owner.clone(),
+        Column::Group => entry.group.clone(),
+        Column::Inode => entry.lstat.ino().to_string(),
+        Column::Links => entry.lstat.nlink().to_string(),
+        Column::LinkTarget => entry
+            .link_target
+            .as_ref()
+            .map(|link_target| link_target.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum HiddenFiles {
     Show,
     Hide,
@@ -321,92 +495,190 @@ pub fn filter_file_list(
         .collect()
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum SortOrder {
     Normal,
     Reverse,
 }
 
-pub fn sort_by_name(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
-    match (a.stat.is_dir(), b.stat.is_dir()) {
-        (true, false) => Ordering::Less,
-        (false, true) => Ordering::Greater,
-        _ => {
-            let o = natord::compare(&a.sort_key, &b.sort_key)
-                .then_with(|| natord::compare(&a.file_name, &b.file_name));
-
-            match sort_order {
-                SortOrder::Normal => o,
-                SortOrder::Reverse => o.reverse(),
-            }
-        }
+fn sort_with_name(a: &Entry, b: &Entry, o: Ordering, sort_order: SortOrder) -> Ordering {
+    let o = o.then_with(|| sort_by_name(a, b, SortOrder::Normal));
+
+    match sort_order {
+        SortOrder::Normal => o,
+        SortOrder::Reverse => o.reverse(),
     }
 }
 
-pub fn sort_by_extension(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
-    match (a.stat.is_dir(), b.stat.is_dir()) {
-        (true, false) => Ordering::Less,
-        (false, true) => Ordering::Greater,
-        _ => {
-            let o = natord::compare(&a.extension, &b.extension)
-                .then_with(|| sort_by_name(a, b, SortOrder::Normal));
-
-            match sort_order {
-                SortOrder::Normal => o,
-                SortOrder::Reverse => o.reverse(),
-            }
-        }
+fn cmp_times(a: io::Result<SystemTime>, b: io::Result<SystemTime>) -> Ordering {
+    match (a, b) {
+        (Ok(a_time), Ok(b_time)) => a_time.cmp(&b_time),
+        (Err(_), Ok(_)) => Ordering::Less,
+        (Ok(_), Err(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
     }
 }
 
-pub fn sort_by_date(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
-    match (a.stat.is_dir(), b.stat.is_dir()) {
-        (true, false) => Ordering::Less,
-        (false, true) => Ordering::Greater,
-        _ => {
-            let o = match (a.lstat.modified(), b.lstat.modified()) {
-                (Ok(a_modified), Ok(b_modified)) => a_modified.cmp(&b_modified),
-                (Err(_), Ok(_)) => Ordering::Less,
-                (Ok(_), Err(_)) => Ordering::Greater,
-                _ => Ordering::Equal,
-            }
-            .then_with(|| sort_by_name(a, b, SortOrder::Normal));
+pub fn sort_by_name(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    let o = natord::compare(&a.sort_key, &b.sort_key)
+        .then_with(|| natord::compare(&a.file_name, &b.file_name));
 
-            match sort_order {
-                SortOrder::Normal => o,
-                SortOrder::Reverse => o.reverse(),
-            }
-        }
+    match sort_order {
+        SortOrder::Normal => o,
+        SortOrder::Reverse => o.reverse(),
     }
 }
 
+pub fn sort_by_extension(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(
+        a,
+        b,
+        natord::compare(&a.extension, &b.extension),
+        sort_order,
+    )
+}
+
+pub fn sort_by_date(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(
+        a,
+        b,
+        cmp_times(a.lstat.modified(), b.lstat.modified()),
+        sort_order,
+    )
+}
+
 pub fn sort_by_size(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
-    match (a.stat.is_dir(), b.stat.is_dir()) {
-        (true, false) => Ordering::Less,
-        (false, true) => Ordering::Greater,
-        _ => {
-            let o = match (a.size, b.size) {
-                (Some(a_size), Some(b_size)) => a_size.cmp(&b_size),
-                (None, Some(_)) => Ordering::Less,
-                (Some(_), None) => Ordering::Greater,
-                _ => Ordering::Equal,
+    let o = match (a.size, b.size) {
+        (Some(a_size), Some(b_size)) => a_size.cmp(&b_size),
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        _ => Ordering::Equal,
+    };
+
+    sort_with_name(a, b, o, sort_order)
+}
+
+pub fn sort_by_ctime(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    let o = (a.lstat.ctime(), a.lstat.ctime_nsec()).cmp(&(b.lstat.ctime(), b.lstat.ctime_nsec()));
+
+    sort_with_name(a, b, o, sort_order)
+}
+
+pub fn sort_by_atime(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(
+        a,
+        b,
+        cmp_times(a.lstat.accessed(), b.lstat.accessed()),
+        sort_order,
+    )
+}
+
+pub fn sort_by_owner(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(a, b, natord::compare(&a.owner, &b.owner), sort_order)
+}
+
+pub fn sort_by_group(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(a, b, natord::compare(&a.group, &b.group), sort_order)
+}
+
+pub fn sort_by_permissions(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(
+        a,
+        b,
+        (a.lstat.mode() & 0o7777).cmp(&(b.lstat.mode() & 0o7777)),
+        sort_order,
+    )
+}
+
+pub fn sort_by_link_target(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(a, b, a.link_target.cmp(&b.link_target), sort_order)
+}
+
+/// Compare two file names like `ls -v` does: runs of digits are compared by their numeric value,
+/// and everything else byte by byte, so that case matters, unlike with natural sorting.
+pub fn version_compare(a: &str, b: &str) -> Ordering {
+    let mut a = a.as_bytes();
+    let mut b = b.as_bytes();
+
+    loop {
+        match (a.first(), b.first()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+                let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+
+                let a_digits = &a[a.iter().take_while(|&&c| c == b'0').count().min(a_len)..a_len];
+                let b_digits = &b[b.iter().take_while(|&&c| c == b'0').count().min(b_len)..b_len];
+
+                let o = a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits));
+
+                if o != Ordering::Equal {
+                    return o;
+                }
+
+                a = &a[a_len..];
+                b = &b[b_len..];
             }
-            .then_with(|| sort_by_name(a, b, SortOrder::Normal));
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(y);
+                }
 
-            match sort_order {
-                SortOrder::Normal => o,
-                SortOrder::Reverse => o.reverse(),
+                a = &a[1..];
+                b = &b[1..];
             }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+pub fn sort_by_version(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    sort_with_name(
+        a,
+        b,
+        version_compare(&a.file_name, &b.file_name),
+        sort_order,
+    )
+}
+
+pub fn sort_by_type(a: &Entry, b: &Entry, sort_order: SortOrder) -> Ordering {
+    // The type of the entries is the one shown by the first character of their label
+    let file_type = |entry: &Entry| {
+        entry
+            .label
+            .chars()
+            .next()
+            .and_then(|c| "/~ *@!-+|=".find(c))
+    };
+
+    sort_with_name(a, b, file_type(a).cmp(&file_type(b)), sort_order)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum SortBy {
     Name,
     Extension,
     Date,
     Size,
+    Ctime,
+    Atime,
+    Owner,
+    Group,
+    Permissions,
+    LinkTarget,
+    Version,
+    Type,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SortDirs {
+    #[default]
+    First,
+    Mixed,
 }
 
 pub fn sort_by_function(sort_by: SortBy) -> fn(&Entry, &Entry, SortOrder) -> Ordering {
@@ -415,5 +687,71 @@ pub fn sort_by_function(sort_by: SortBy) -> fn(&Entry, &Entry, SortOrder) -> Ord
         SortBy::Extension => sort_by_extension,
         SortBy::Date => sort_by_date,
         SortBy::Size => sort_by_size,
+        SortBy::Ctime => sort_by_ctime,
+        SortBy::Atime => sort_by_atime,
+        SortBy::Owner => sort_by_owner,
+        SortBy::Group => sort_by_group,
+        SortBy::Permissions => sort_by_permissions,
+        SortBy::LinkTarget => sort_by_link_target,
+        SortBy::Version => sort_by_version,
+        SortBy::Type => sort_by_type,
+    }
+}
+
+pub fn sort_entries(
+    a: &Entry,
+    b: &Entry,
+    sort_by: SortBy,
+    sort_order: SortOrder,
+    sort_dirs: SortDirs,
+) -> Ordering {
+    let o = match (sort_dirs, a.stat.is_dir(), b.stat.is_dir()) {
+        (SortDirs::First, true, false) => Ordering::Less,
+        (SortDirs::First, false, true) => Ordering::Greater,
+        _ => Ordering::Equal,
+    };
+
+    o.then_with(|| sort_by_function(sort_by)(a, b, sort_order))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    use crate::palette::get_monochrome_palette;
+
+    #[test]
+    fn test_flat_file_list_depth() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cwd = temp_dir.path();
+
+        fs::create_dir_all(cwd.join("a/b/c")).unwrap();
+        fs::write(cwd.join("a/b/c/file"), b"").unwrap();
+
+        let palette = get_monochrome_palette();
+        let flat_files = |flat_depth| -> Vec<PathBuf> {
+            get_flat_file_list(cwd, &palette, flat_depth, None)
+                .unwrap()
+                .into_iter()
+                .map(|entry| PathBuf::from(entry.file.strip_prefix(cwd).unwrap()))
+                .sorted()
+                .collect()
+        };
+
+        assert_eq!(flat_files(1), [Path::new("a"), Path::new("a/b")]);
+        assert_eq!(
+            flat_files(2),
+            [Path::new("a"), Path::new("a/b"), Path::new("a/b/c")]
+        );
+        assert_eq!(
+            flat_files(0),
+            [
+                Path::new("a"),
+                Path::new("a/b"),
+                Path::new("a/b/c"),
+                Path::new("a/b/c/file")
+            ]
+        );
     }
 }
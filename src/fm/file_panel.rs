@@ -1,11 +1,13 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     fs::{self, read_dir},
     io,
+    os::unix::fs::MetadataExt,
     path::{Path, PathBuf},
     process::Command,
     rc::Rc,
-    thread,
+    slice, thread,
 };
 
 use anyhow::{anyhow, bail};
@@ -15,14 +17,17 @@ use termion::{event::*, raw::RawTerminal};
 
 use nucleo_matcher::{
     pattern::{CaseMatching, Normalization, Pattern},
-    Config, Matcher,
+    Matcher,
 };
 use regex::RegexBuilder;
 use unicode_width::UnicodeWidthStr;
 
+use fcd::shutil::disk_usage;
+
 use crate::{
     app::{start_inputs, Events, Inputs, PubSub, MIDDLE_BORDER_SET},
     component::{Component, Focus},
+    config::{Column, Config, ListingMode},
     fm::{
         app::{
             human_readable_size, raw_output_activate, raw_output_suspend, tar_stem, tar_suffix,
@@ -30,18 +35,34 @@ use crate::{
         },
         archive_mounter::{self, ArchiveMounterCommand},
         bookmarks::{Bookmarks, BOOKMARK_KEYS},
+        compare_dirs::CompareBy,
+        cp_mv_rm::{
+            database::DBEntriesEntry,
+            dirscan::{dirscan, DirScanEvent, ReadMetadata},
+            fs_ops::RealFs,
+        },
+        dir_sorts::{DirSorts, SortSettings},
+        disk_usage::DiskUsage,
         entry::{
-            count_directories, filter_file_list, get_file_list, sort_by_function, Entry,
-            HiddenFiles, SortBy, SortOrder, ARCHIVE_EXTENSIONS,
+            column_text, count_directories, filter_file_list, get_file_list, get_flat_file_list,
+            sort_entries, Entry, FileListRequest, HiddenFiles, SortBy, SortDirs, SortOrder,
+            ARCHIVE_EXTENSIONS,
         },
+        git_status::{get_git_statuses, GitStatus, GitStatuses},
         panel::{Panel, PanelComponent},
+        panelize::Panelize,
+        tabs::TabState,
+        tree_view::TreeView,
+        watcher,
     },
     fnmatch,
     palette::Palette,
-    shutil::disk_usage,
     tilde_layout::tilde_layout,
 };
 
+// The number of directories kept in the back/forward history of each panel
+const MAX_HISTORY: usize = 100;
+
 #[derive(Debug, Clone, Copy)]
 enum CursorPosition {
     Keep,
@@ -51,6 +72,16 @@ enum CursorPosition {
 #[derive(Debug, Clone)]
 enum ComponentPubSub {
     FileList(Vec<Entry>),
+    DirSize(PathBuf, PathBuf, u64),
+    DirChanged(PathBuf),
+    GitStatuses(PathBuf, Option<GitStatuses>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GitFilter {
+    All,
+    Changed,
+    HideIgnored,
 }
 
 #[derive(Debug, Clone)]
@@ -64,19 +95,27 @@ pub struct FilePanel {
     palette: Rc<Palette>,
     events_tx: Sender<Events>,
     bookmarks: Rc<RefCell<Bookmarks>>,
+    dir_sorts: Rc<RefCell<DirSorts>>,
     raw_output: Rc<RawTerminal<io::Stdout>>,
     stop_inputs_tx: Sender<Inputs>,
     stop_inputs_rx: Receiver<Inputs>,
     pubsub_tx: Sender<PubSub>,
-    opener: String,
+    config: Rc<Config>,
     rect: Rect,
     component_pubsub_tx: Sender<ComponentPubSub>,
     component_pubsub_rx: Receiver<ComponentPubSub>,
-    file_list_tx: Sender<PathBuf>,
-    file_list_rx: Receiver<PathBuf>,
+    file_list_tx: Sender<FileListRequest>,
+    file_list_rx: Receiver<FileListRequest>,
+    watcher_tx: Option<Sender<Option<PathBuf>>>,
+    git_status_tx: Sender<PathBuf>,
+    git_status_rx: Receiver<PathBuf>,
+    git_statuses: Option<GitStatuses>,
+    git_filter: GitFilter,
     cwd: PathBuf,
     shown_cwd: PathBuf,
     old_cwd: PathBuf,
+    history: Vec<PathBuf>,
+    history_position: usize,
     leader: Option<char>,
     free: u64,
     is_loading: bool,
@@ -89,10 +128,19 @@ pub struct FilePanel {
     file_filter: String,
     sort_method: SortBy,
     sort_order: SortOrder,
+    sort_dirs: SortDirs,
+    // With sort_per_directory, the sort of the directories that have none saved
+    panel_sort: SortSettings,
     selected_file: Option<PathBuf>,
     focus: Focus,
     archive_mounter_command_tx: Option<Sender<ArchiveMounterCommand>>,
     archive_mount_request: ArchiveMountRequest,
+    dir_sizes: HashMap<PathBuf, u64>,
+    dir_size_ev_tx: Option<Sender<DirScanEvent>>,
+    panel_mode: Option<Box<dyn PanelComponent>>,
+    pending_tagged_files: Vec<PathBuf>,
+    flat: bool,
+    listing_mode: ListingMode,
 }
 
 impl FilePanel {
@@ -101,35 +149,55 @@ impl FilePanel {
         palette: &Rc<Palette>,
         events_tx: &Sender<Events>,
         bookmarks: &Rc<RefCell<Bookmarks>>,
+        dir_sorts: &Rc<RefCell<DirSorts>>,
         raw_output: &Rc<RawTerminal<io::Stdout>>,
         stop_inputs_tx: &Sender<Inputs>,
         stop_inputs_rx: &Receiver<Inputs>,
         pubsub_tx: Sender<PubSub>,
-        opener: &str,
+        config: &Rc<Config>,
         initial_path: &Path,
         archive_mounter_command_tx: Option<Sender<ArchiveMounterCommand>>,
         focus: Focus,
     ) -> FilePanel {
         let (component_pubsub_tx, component_pubsub_rx) = crossbeam_channel::unbounded();
         let (file_list_tx, file_list_rx) = crossbeam_channel::unbounded();
+        let (git_status_tx, git_status_rx) = crossbeam_channel::unbounded();
+
+        let watcher_tx = {
+            let component_pubsub_tx = component_pubsub_tx.clone();
+            let pubsub_tx = pubsub_tx.clone();
+
+            watcher::start(move |cwd| {
+                let _ = component_pubsub_tx.send(ComponentPubSub::DirChanged(PathBuf::from(cwd)));
+                let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            })
+        };
 
         let mut panel = FilePanel {
             palette: Rc::clone(palette),
             events_tx: events_tx.clone(),
             bookmarks: Rc::clone(bookmarks),
+            dir_sorts: Rc::clone(dir_sorts),
             raw_output: Rc::clone(raw_output),
             stop_inputs_tx: stop_inputs_tx.clone(),
             stop_inputs_rx: stop_inputs_rx.clone(),
             pubsub_tx,
-            opener: String::from(opener),
+            config: Rc::clone(config),
             rect: Rect::default(),
             component_pubsub_tx,
             component_pubsub_rx,
             file_list_tx,
             file_list_rx,
+            watcher_tx,
+            git_status_tx,
+            git_status_rx,
+            git_statuses: None,
+            git_filter: GitFilter::All,
             cwd: PathBuf::new(),
             shown_cwd: PathBuf::new(),
             old_cwd: PathBuf::new(),
+            history: Vec::new(),
+            history_position: 0,
             leader: None,
             free: 0,
             is_loading: false,
@@ -142,13 +210,26 @@ impl FilePanel {
             file_filter: String::from(""),
             sort_method: SortBy::Name,
             sort_order: SortOrder::Normal,
+            sort_dirs: SortDirs::First,
+            panel_sort: SortSettings {
+                sort_method: SortBy::Name,
+                sort_order: SortOrder::Normal,
+                sort_dirs: SortDirs::First,
+            },
             selected_file: None,
             focus,
             archive_mounter_command_tx,
             archive_mount_request: ArchiveMountRequest::None,
+            dir_sizes: HashMap::new(),
+            dir_size_ev_tx: None,
+            panel_mode: None,
+            pending_tagged_files: Vec::new(),
+            flat: false,
+            listing_mode: config.columns.listing_mode,
         };
 
         panel.file_list_thread();
+        panel.git_status_thread();
         panel.chdir(initial_path, None);
         panel.old_cwd.clone_from(&panel.shown_cwd);
 
@@ -163,6 +244,17 @@ impl FilePanel {
 
                     self.file_list = file_list;
 
+                    // Keep the directory sizes computed so far, as the file list thread only counts
+                    // the directory entries
+                    for entry in self.file_list.iter_mut() {
+                        if let Some(size) = self.dir_sizes.get(&entry.file) {
+                            entry.size = Some(*size);
+                            entry.shown_size = human_readable_size(*size);
+                        }
+                    }
+
+                    self.apply_git_statuses();
+
                     self.filter_and_sort_file_list(
                         self.selected_file
                             .as_ref()
@@ -174,13 +266,119 @@ impl FilePanel {
                     self.tagged_files
                         .retain(|entry| self.file_list.contains(entry));
 
+                    // Tag the files of a restored tab, once they are listed
+                    if !self.pending_tagged_files.is_empty() {
+                        let pending_tagged_files = std::mem::take(&mut self.pending_tagged_files);
+
+                        self.tagged_files.extend(
+                            self.file_list
+                                .iter()
+                                .filter(|entry| pending_tagged_files.contains(&entry.file))
+                                .cloned(),
+                        );
+                    }
+
                     if let Focus::Focused = self.focus {
                         self.pubsub_tx
                             .send(PubSub::SelectedEntry(self.get_selected_entry()))
                             .unwrap();
                     }
                 }
+                ComponentPubSub::DirSize(cwd, dir, size) => {
+                    // Discard the results of a directory that we left in the meantime
+                    if cwd == self.cwd {
+                        self.dir_sizes.insert(dir.clone(), size);
+
+                        if let Some(entry) = self.file_list.iter_mut().find(|e| e.file == dir) {
+                            entry.size = Some(size);
+                            entry.shown_size = human_readable_size(size);
+                        }
+
+                        self.filter_and_sort_file_list(
+                            self.get_selected_file()
+                                .as_ref()
+                                .map(|selected_file| self.archive_path(selected_file))
+                                .as_deref(),
+                            CursorPosition::Keep,
+                        );
+
+                        if let Focus::Focused = self.focus {
+                            self.pubsub_tx
+                                .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                                .unwrap();
+                        }
+                    }
+                }
+                ComponentPubSub::GitStatuses(cwd, git_statuses) => {
+                    // Discard the results of a directory that we left in the meantime
+                    if cwd == self.cwd {
+                        self.git_statuses = git_statuses;
+                        self.apply_git_statuses();
+
+                        self.filter_and_sort_file_list(
+                            self.get_selected_file()
+                                .as_ref()
+                                .map(|selected_file| self.archive_path(selected_file))
+                                .as_deref(),
+                            CursorPosition::Keep,
+                        );
+
+                        if let Focus::Focused = self.focus {
+                            self.pubsub_tx
+                                .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                                .unwrap();
+                        }
+                    }
+                }
+                ComponentPubSub::DirChanged(cwd) => {
+                    // The disk usage and tree view modes are not refreshed automatically
+                    if cwd == self.cwd && self.panel_mode.is_none() && !self.is_loading {
+                        let selected_file = self
+                            .get_selected_file()
+                            .map(|selected_file| self.archive_path(&selected_file));
+
+                        // If the current directory has been deleted, reload changes to its
+                        // nearest existing parent
+                        match read_dir(&self.cwd) {
+                            Ok(_) => self.request_file_list(selected_file.as_deref()),
+                            Err(_) => self.reload(selected_file.as_deref()),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn git_status_thread(&mut self) {
+        let git_status_rx = self.git_status_rx.clone();
+        let component_pubsub_tx = self.component_pubsub_tx.clone();
+        let pubsub_tx = self.pubsub_tx.clone();
+
+        thread::spawn(move || loop {
+            let cwd = match git_status_rx.recv() {
+                // We're only interested in the latest message in the queue
+                Ok(cwd) => git_status_rx.try_iter().last().unwrap_or(cwd),
+
+                // When the main thread exits, the channel returns an error
+                Err(_) => return,
+            };
+
+            let git_statuses = get_git_statuses(&cwd);
+
+            // Send the current result only if there are no newer requests in the queue
+            if git_status_rx.is_empty() {
+                let _ = component_pubsub_tx.send(ComponentPubSub::GitStatuses(cwd, git_statuses));
+                let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
             }
+        });
+    }
+
+    fn apply_git_statuses(&mut self) {
+        for entry in self.file_list.iter_mut() {
+            entry.git_status = self
+                .git_statuses
+                .as_ref()
+                .and_then(|git_statuses| git_statuses.get(&entry.file));
         }
     }
 
@@ -192,10 +390,10 @@ impl FilePanel {
 
         thread::spawn(move || {
             loop {
-                let cwd = match file_list_rx.is_empty() {
+                let request = match file_list_rx.is_empty() {
                     // Block this thread until we recevie something
                     true => match file_list_rx.recv() {
-                        Ok(cwd) => cwd,
+                        Ok(request) => request,
 
                         // When the main thread exits, the channel returns an error
                         Err(_) => return,
@@ -206,8 +404,16 @@ impl FilePanel {
                 };
 
                 // Step 1: Get the current file list without counting the directories
-                let file_list =
-                    get_file_list(&cwd, &palette, Some(file_list_rx.clone())).unwrap_or_default();
+                let file_list = match request.flat_depth {
+                    Some(flat_depth) => get_flat_file_list(
+                        &request.cwd,
+                        &palette,
+                        flat_depth,
+                        Some(file_list_rx.clone()),
+                    ),
+                    None => get_file_list(&request.cwd, &palette, Some(file_list_rx.clone())),
+                }
+                .unwrap_or_default();
 
                 // Send the current result only if there are no newer file list requests in the queue,
                 // otherwise discard the current result
@@ -235,12 +441,258 @@ impl FilePanel {
         });
     }
 
+    fn dir_size_thread(&mut self) {
+        // Compute the size of the tagged directories, or of all the directories if none is tagged
+        let tagged_dirs: Vec<Entry> = self
+            .get_tagged_files()
+            .into_iter()
+            .filter(|entry| entry.lstat.is_dir())
+            .collect();
+
+        let entries: Vec<DBEntriesEntry> = match tagged_dirs.is_empty() {
+            true => self
+                .shown_file_list
+                .iter()
+                .filter(|entry| entry.lstat.is_dir())
+                .cloned()
+                .collect(),
+            false => tagged_dirs,
+        }
+        .iter()
+        .map(|entry| DBEntriesEntry {
+            id: 0,
+            job_id: 0,
+            file: entry.file.clone(),
+            is_file: false,
+            is_dir: true,
+            is_symlink: false,
+            size: entry.lstat.len(),
+            uid: entry.lstat.uid(),
+            gid: entry.lstat.gid(),
+        })
+        .collect();
+
+        self.stop_dir_size_thread();
+
+        if entries.is_empty() {
+            return;
+        }
+
+        let (ev_tx, ev_rx) = crossbeam_channel::unbounded();
+        self.dir_size_ev_tx = Some(ev_tx);
+
+        let cwd = self.cwd.clone();
+        let component_pubsub_tx = self.component_pubsub_tx.clone();
+        let pubsub_tx = self.pubsub_tx.clone();
+
+        thread::spawn(move || {
+            for entry in entries {
+                let files = match dirscan(
+                    &cwd,
+                    slice::from_ref(&entry),
+                    &[],
+                    ReadMetadata::Yes,
+                    ev_rx.clone(),
+                    &mut |_info| {},
+                    &RealFs,
+                ) {
+                    Some(files) => files,
+
+                    // The calculation has been aborted
+                    None => return,
+                };
+
+                let size = files.iter().map(|file| file.size).sum();
+
+                // First send the component event
+                let _ = component_pubsub_tx.send(ComponentPubSub::DirSize(
+                    cwd.clone(),
+                    entry.file,
+                    size,
+                ));
+
+                // Then notify the app that there is an component event
+                let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            }
+        });
+    }
+
+    fn stop_dir_size_thread(&mut self) {
+        if let Some(ev_tx) = self.dir_size_ev_tx.take() {
+            let _ = ev_tx.send(DirScanEvent::Abort);
+        }
+    }
+
+    pub fn get_tab_state(&self) -> TabState {
+        TabState {
+            cwd: self.shown_cwd.clone(),
+            selected_file: self
+                .get_selected_file()
+                .map(|selected_file| self.archive_path(&selected_file)),
+            hidden_files: self.hidden_files,
+            file_filter: self.file_filter.clone(),
+            sort_method: self.panel_sort.sort_method,
+            sort_order: self.panel_sort.sort_order,
+            sort_dirs: self.panel_sort.sort_dirs,
+            tagged_files: self
+                .tagged_files
+                .iter()
+                .map(|entry| self.archive_path(&entry.file))
+                .collect(),
+            listing_mode: self.listing_mode,
+        }
+    }
+
+    pub fn set_tab_state(&mut self, state: &TabState) {
+        // The file list is still loading, so the selected file gets applied when it arrives
+        self.selected_file = state
+            .selected_file
+            .as_ref()
+            .map(|selected_file| self.unarchive_path(selected_file));
+        self.hidden_files = state.hidden_files;
+        self.file_filter.clone_from(&state.file_filter);
+        self.panel_sort = SortSettings {
+            sort_method: state.sort_method,
+            sort_order: state.sort_order,
+            sort_dirs: state.sort_dirs,
+        };
+        self.apply_dir_sort();
+        self.pending_tagged_files.clone_from(&state.tagged_files);
+        self.listing_mode = state.listing_mode;
+    }
+
+    // The sort saved for the directory, if any, or the panel sort
+    fn apply_dir_sort(&mut self) {
+        let dir_sort = match self.config.options.sort_per_directory {
+            true => self.dir_sorts.borrow().get(&self.shown_cwd),
+            false => None,
+        };
+
+        let settings = dir_sort.unwrap_or(self.panel_sort);
+
+        self.sort_method = settings.sort_method;
+        self.sort_order = settings.sort_order;
+        self.sort_dirs = settings.sort_dirs;
+    }
+
+    fn sort_changed(&mut self) {
+        let settings = SortSettings {
+            sort_method: self.sort_method,
+            sort_order: self.sort_order,
+            sort_dirs: self.sort_dirs,
+        };
+
+        match self.config.options.sort_per_directory {
+            true => self
+                .dir_sorts
+                .borrow_mut()
+                .insert(&self.shown_cwd, settings),
+            false => self.panel_sort = settings,
+        }
+
+        self.filter_and_sort_file_list(
+            self.get_selected_file()
+                .as_ref()
+                .map(|selected_file| self.archive_path(selected_file))
+                .as_deref(),
+            CursorPosition::Reset,
+        );
+
+        self.pubsub_tx
+            .send(PubSub::SelectedEntry(self.get_selected_entry()))
+            .unwrap();
+    }
+
+    fn title_labels(&self) -> Vec<&str> {
+        let mut labels = Vec::new();
+
+        if self.flat {
+            labels.push("Flat");
+        }
+
+        if self.git_statuses.is_some() {
+            match self.git_filter {
+                GitFilter::All => (),
+                GitFilter::Changed => labels.push("Git changed"),
+                GitFilter::HideIgnored => labels.push("Git not ignored"),
+            }
+        }
+
+        labels
+    }
+
+    fn columns(&self) -> &[Column] {
+        match self.listing_mode {
+            ListingMode::Brief => slice::from_ref(&Column::Name),
+            ListingMode::Full => &self.config.columns.full,
+            ListingMode::Long => &self.config.columns.long,
+        }
+    }
+
+    fn leave_panel_mode(&mut self) {
+        if let Some(panel_mode) = self.panel_mode.take() {
+            let selected_file = panel_mode.get_selected_entry().map(|entry| entry.file);
+
+            // Files may have been deleted in the disk usage or tree view mode, so reload the file
+            // list anyway
+            match panel_mode.get_cwd() {
+                Some(cwd) if cwd != self.cwd => self.chdir(&cwd, selected_file.as_deref()),
+                _ => self.reload(selected_file.as_deref()),
+            }
+
+            if let Focus::Focused = self.focus {
+                self.pubsub_tx
+                    .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                    .unwrap();
+            }
+        }
+    }
+
+    /// The directory of the entry relative to cwd, as in the flat listing the entries can be in
+    /// subdirectories, and renaming them must not move them to cwd
+    fn rename_prefix(&self, entry: &Entry) -> String {
+        entry
+            .file
+            .parent()
+            .and_then(|parent| parent.strip_prefix(&self.cwd).ok())
+            .filter(|rel_dir| !rel_dir.as_os_str().is_empty())
+            .map(|rel_dir| format!("{}/", rel_dir.to_string_lossy().replace('%', "%%")))
+            .unwrap_or_default()
+    }
+
     fn chdir_old_cwd(&mut self) {
         let old_cwd = self.unarchive_path(&self.old_cwd);
 
         self.chdir(&old_cwd, None)
     }
 
+    fn chdir_history(&mut self, position: usize) {
+        if let Some(dir) = self.history.get(position) {
+            let dir = self.unarchive_path(dir);
+
+            // Set the position before changing directory, so that chdir doesn't add the
+            // directory to the history again
+            self.history_position = position;
+            self.chdir(&dir, None);
+        }
+    }
+
+    fn add_to_history(&mut self) {
+        if self.history.get(self.history_position) == Some(&self.shown_cwd) {
+            return;
+        }
+
+        // Going to a new directory discards the directories that could be reached going forward
+        self.history.truncate(self.history_position + 1);
+        self.history.push(self.shown_cwd.clone());
+
+        if self.history.len() > MAX_HISTORY {
+            self.history.drain(..(self.history.len() - MAX_HISTORY));
+        }
+
+        self.history_position = self.history.len() - 1;
+    }
+
     fn get_selected_file(&self) -> Option<PathBuf> {
         match self.shown_file_list.is_empty() {
             true => None,
@@ -249,14 +701,26 @@ impl FilePanel {
     }
 
     fn load_file_list(&mut self, selected_file: Option<&Path>) {
+        self.is_loading = true;
+        self.request_file_list(selected_file);
+    }
+
+    /// Like `load_file_list`, but keep showing the current file list until the new one arrives.
+    fn request_file_list(&mut self, selected_file: Option<&Path>) {
         self.selected_file = selected_file.map(PathBuf::from);
         self.free = match disk_usage(&self.cwd) {
             Ok(usage) => usage.free,
             Err(_) => 0,
         };
 
-        self.is_loading = true;
-        self.file_list_tx.send(self.cwd.clone()).unwrap();
+        self.file_list_tx
+            .send(FileListRequest {
+                cwd: self.cwd.clone(),
+                flat_depth: self.flat.then_some(self.config.options.flat_depth),
+            })
+            .unwrap();
+
+        self.git_status_tx.send(self.cwd.clone()).unwrap();
     }
 
     fn filter_and_sort_file_list(&mut self, selected_file: Option<&Path>, cursor: CursorPosition) {
@@ -265,8 +729,22 @@ impl FilePanel {
         self.shown_file_list =
             filter_file_list(&self.file_list, self.hidden_files, &self.file_filter);
 
-        self.shown_file_list
-            .sort_unstable_by(|a, b| sort_by_function(self.sort_method)(a, b, self.sort_order));
+        // The git filters apply only inside git repositories
+        if self.git_statuses.is_some() {
+            match self.git_filter {
+                GitFilter::All => (),
+                GitFilter::Changed => self.shown_file_list.retain(|entry| {
+                    matches!(entry.git_status, Some(git_status) if git_status != GitStatus::Ignored)
+                }),
+                GitFilter::HideIgnored => self
+                    .shown_file_list
+                    .retain(|entry| entry.git_status != Some(GitStatus::Ignored)),
+            }
+        }
+
+        self.shown_file_list.sort_unstable_by(|a, b| {
+            sort_entries(a, b, self.sort_method, self.sort_order, self.sort_dirs)
+        });
 
         self.cursor_position = self.clamp_cursor(match selected_file {
             Some(file) => match self
@@ -378,7 +856,7 @@ impl FilePanel {
         self.stop_inputs_tx.send(Inputs::Stop).unwrap();
         raw_output_suspend(&self.raw_output);
 
-        let _ = Command::new(&self.opener)
+        let _ = Command::new(&self.config.options.opener)
             .arg(file)
             .current_dir(&self.cwd)
             .status();
@@ -396,7 +874,12 @@ impl Component for FilePanel {
     fn handle_key(&mut self, key: &Key) -> bool {
         let mut key_handled = true;
 
-        if let Some(c) = self.leader {
+        if let Some(panel_mode) = &mut self.panel_mode {
+            match key {
+                Key::Alt('d') | Key::Alt('e') | Key::Alt('p') => self.leave_panel_mode(),
+                _ => key_handled = panel_mode.handle_key(key),
+            }
+        } else if let Some(c) = self.leader {
             // When pressing a key after a leader, the leader is automatically reset
             self.leader = None;
             self.pubsub_tx.send(PubSub::Leader(self.leader)).unwrap();
@@ -422,6 +905,26 @@ impl Component for FilePanel {
                 ('m', Key::Char(c)) if BOOKMARK_KEYS.contains(*c) => {
                     self.bookmarks.borrow_mut().insert(*c, &self.cwd)
                 }
+                ('=', Key::Char('n')) => {
+                    self.pubsub_tx
+                        .send(PubSub::CompareDirs(CompareBy::Name))
+                        .unwrap();
+                }
+                ('=', Key::Char('s')) => {
+                    self.pubsub_tx
+                        .send(PubSub::CompareDirs(CompareBy::Size))
+                        .unwrap();
+                }
+                ('=', Key::Char('d')) => {
+                    self.pubsub_tx
+                        .send(PubSub::CompareDirs(CompareBy::Mtime))
+                        .unwrap();
+                }
+                ('=', Key::Char('c')) => {
+                    self.pubsub_tx
+                        .send(PubSub::CompareDirs(CompareBy::Content))
+                        .unwrap();
+                }
                 ('s', Key::Char('n')) => {
                     self.pubsub_tx
                         .send(PubSub::SortFiles(SortBy::Name, SortOrder::Normal))
@@ -462,20 +965,109 @@ impl Component for FilePanel {
                         .send(PubSub::SortFiles(SortBy::Size, SortOrder::Reverse))
                         .unwrap();
                 }
+                ('s', Key::Char('c')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Ctime, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('C')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Ctime, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('a')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Atime, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('A')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Atime, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('o')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Owner, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('O')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Owner, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('g')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Group, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('G')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Group, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('p')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Permissions, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('P')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Permissions, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('l')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::LinkTarget, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('L')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::LinkTarget, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('v')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Version, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('V')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Version, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('t')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Type, SortOrder::Normal))
+                        .unwrap();
+                }
+                ('s', Key::Char('T')) => {
+                    self.pubsub_tx
+                        .send(PubSub::SortFiles(SortBy::Type, SortOrder::Reverse))
+                        .unwrap();
+                }
+                ('s', Key::Char('f')) => self.pubsub_tx.send(PubSub::ToggleSortDirs).unwrap(),
                 ('c', Key::Char('c')) | ('c', Key::Char('w')) => {
                     if !self.shown_file_list.is_empty() {
+                        let entry = &self.shown_file_list[self.cursor_position];
+                        let rel_dir = self.rename_prefix(entry);
+                        let cursor_position = rel_dir.chars().count();
+
                         self.pubsub_tx
-                            .send(PubSub::PromptRename(String::from(""), 0))
+                            .send(PubSub::PromptRename(rel_dir, cursor_position))
                             .unwrap();
                     }
                 }
                 ('c', Key::Char('e')) => {
                     if !self.shown_file_list.is_empty() {
                         let entry = &self.shown_file_list[self.cursor_position];
+                        let rel_dir = self.rename_prefix(entry);
                         let file_name = tar_suffix(&entry.file_name.replace('%', "%%"));
 
                         self.pubsub_tx
-                            .send(PubSub::PromptRename(file_name, 0))
+                            .send(PubSub::PromptRename(
+                                format!("{}{}", rel_dir, file_name),
+                                rel_dir.chars().count(),
+                            ))
                             .unwrap();
                     }
                 }
@@ -508,6 +1100,10 @@ impl Component for FilePanel {
                     self.leader = Some('c');
                     self.pubsub_tx.send(PubSub::Leader(self.leader)).unwrap();
                 }
+                Key::Char('=') => {
+                    self.leader = Some('=');
+                    self.pubsub_tx.send(PubSub::Leader(self.leader)).unwrap();
+                }
                 Key::Left | Key::Char('h') => {
                     if let Some(new_cwd) = self.shown_cwd.parent() {
                         self.chdir(&self.unarchive_path(new_cwd), None);
@@ -795,7 +1391,35 @@ impl Component for FilePanel {
                 Key::Char('-') | Key::Char('\\') => {
                     self.pubsub_tx.send(PubSub::PromptUntagGlob).unwrap();
                 }
+                Key::Alt('+') => self.pubsub_tx.send(PubSub::DlgTag).unwrap(),
                 Key::Ctrl('r') => self.pubsub_tx.send(PubSub::Reload).unwrap(),
+                Key::Null => self.dir_size_thread(),
+                Key::Alt('d') => {
+                    self.panel_mode = Some(Box::new(DiskUsage::new(
+                        &self.palette,
+                        self.pubsub_tx.clone(),
+                        &self.cwd,
+                        self.focus,
+                    )));
+                }
+                Key::Alt('e') => {
+                    self.panel_mode = Some(Box::new(TreeView::new(
+                        &self.palette,
+                        self.pubsub_tx.clone(),
+                        &self.cwd,
+                        self.get_selected_file().as_deref(),
+                        self.hidden_files,
+                        self.sort_method,
+                        self.sort_order,
+                        self.sort_dirs,
+                        self.focus,
+                    )));
+                }
+                Key::Alt('p') => {
+                    self.pubsub_tx
+                        .send(PubSub::PromptPanelize(self.cwd.clone()))
+                        .unwrap();
+                }
                 Key::Backspace => self.pubsub_tx.send(PubSub::ToggleHidden).unwrap(),
                 Key::Char('f') | Key::Char('/') => {
                     self.pubsub_tx
@@ -805,30 +1429,39 @@ impl Component for FilePanel {
                 Key::F(7) | Key::Char('7') => self.pubsub_tx.send(PubSub::PromptMkdir).unwrap(),
                 Key::Char('r') => {
                     if !self.shown_file_list.is_empty() {
+                        let entry = &self.shown_file_list[self.cursor_position];
+                        let rel_dir = self.rename_prefix(entry);
+                        let cursor_position = rel_dir.chars().count();
+
                         self.pubsub_tx
-                            .send(PubSub::PromptRename(String::from(""), 0))
+                            .send(PubSub::PromptRename(rel_dir, cursor_position))
                             .unwrap();
                     }
                 }
                 Key::Char('i') | Key::Char('I') => {
                     if !self.shown_file_list.is_empty() {
                         let entry = &self.shown_file_list[self.cursor_position];
+                        let rel_dir = self.rename_prefix(entry);
                         let file_name = entry.file_name.replace('%', "%%");
 
                         self.pubsub_tx
-                            .send(PubSub::PromptRename(file_name, 0))
+                            .send(PubSub::PromptRename(
+                                format!("{}{}", rel_dir, file_name),
+                                rel_dir.chars().count(),
+                            ))
                             .unwrap();
                     }
                 }
                 Key::Char('a') => {
                     if !self.shown_file_list.is_empty() {
                         let entry = &self.shown_file_list[self.cursor_position];
+                        let rel_dir = self.rename_prefix(entry);
                         let file_name = entry.file_name.replace('%', "%%");
 
                         self.pubsub_tx
                             .send(PubSub::PromptRename(
-                                file_name.clone(),
-                                tar_stem(&file_name).chars().count(),
+                                format!("{}{}", rel_dir, file_name),
+                                rel_dir.chars().count() + tar_stem(&file_name).chars().count(),
                             ))
                             .unwrap();
                     }
@@ -836,13 +1469,58 @@ impl Component for FilePanel {
                 Key::Char('A') => {
                     if !self.shown_file_list.is_empty() {
                         let entry = &self.shown_file_list[self.cursor_position];
-                        let file_name = entry.file_name.replace('%', "%%");
+                        let file_name = format!(
+                            "{}{}",
+                            self.rename_prefix(entry),
+                            entry.file_name.replace('%', "%%")
+                        );
 
                         self.pubsub_tx
                             .send(PubSub::PromptRename(file_name.clone(), file_name.len()))
                             .unwrap();
                     }
                 }
+                Key::Alt('f') => {
+                    self.flat = !self.flat;
+                    self.tagged_files.clear();
+
+                    self.load_file_list(
+                        self.get_selected_file()
+                            .map(|selected_file| self.archive_path(&selected_file))
+                            .as_deref(),
+                    );
+                }
+                Key::Alt('g') => {
+                    self.git_filter = match self.git_filter {
+                        GitFilter::All => GitFilter::Changed,
+                        GitFilter::Changed => GitFilter::HideIgnored,
+                        GitFilter::HideIgnored => GitFilter::All,
+                    };
+
+                    self.filter_and_sort_file_list(
+                        self.get_selected_file()
+                            .map(|selected_file| self.archive_path(&selected_file))
+                            .as_deref(),
+                        CursorPosition::Keep,
+                    );
+
+                    self.pubsub_tx
+                        .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                        .unwrap();
+                }
+                Key::AltLeft => {
+                    if let Some(position) = self.history_position.checked_sub(1) {
+                        self.chdir_history(position);
+                    }
+                }
+                Key::AltRight => self.chdir_history(self.history_position + 1),
+                Key::Alt('l') => {
+                    self.listing_mode = match self.listing_mode {
+                        ListingMode::Brief => ListingMode::Full,
+                        ListingMode::Full => ListingMode::Long,
+                        ListingMode::Long => ListingMode::Brief,
+                    };
+                }
                 Key::Char(':') | Key::Char('!') => {
                     self.pubsub_tx
                         .send(PubSub::PromptShell(self.cwd.clone()))
@@ -900,13 +1578,16 @@ impl Component for FilePanel {
     }
 
     fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        if let Some(panel_mode) = &mut self.panel_mode {
+            panel_mode.handle_mouse(button, mouse_position);
+            return;
+        }
+
         match button {
             MouseButton::Left => self.handle_click(mouse_position),
-            MouseButton::Right => {
-                if self.rect.contains(mouse_position) {
-                    self.handle_click(mouse_position);
-                    self.tag_toggle();
-                }
+            MouseButton::Right if self.rect.contains(mouse_position) => {
+                self.handle_click(mouse_position);
+                self.tag_toggle();
             }
             MouseButton::WheelUp => {
                 self.first_line = self.first_line.saturating_sub(1);
@@ -942,6 +1623,15 @@ impl Component for FilePanel {
     }
 
     fn handle_pubsub(&mut self, event: &PubSub) {
+        if let Some(panel_mode) = &mut self.panel_mode {
+            panel_mode.handle_pubsub(event);
+
+            // The file list thread keeps on running in the disk usage and tree view modes
+            if !matches!(event, PubSub::ComponentThreadEvent) {
+                return;
+            }
+        }
+
         match event {
             PubSub::ComponentThreadEvent => self.handle_component_pubsub(),
             PubSub::Esc => {
@@ -966,21 +1656,22 @@ impl Component for FilePanel {
                 }
             }
             PubSub::SortFiles(sort_method, sort_order) => {
-                self.sort_method = *sort_method;
-                self.sort_order = *sort_order;
-
-                self.filter_and_sort_file_list(
-                    self.get_selected_file()
-                        .as_ref()
-                        .map(|selected_file| self.archive_path(selected_file))
-                        .as_deref(),
-                    CursorPosition::Reset,
-                );
+                // Every panel (and every tab) has its own sort method
+                if let Focus::Focused = self.focus {
+                    self.sort_method = *sort_method;
+                    self.sort_order = *sort_order;
 
+                    self.sort_changed();
+                }
+            }
+            PubSub::ToggleSortDirs => {
                 if let Focus::Focused = self.focus {
-                    self.pubsub_tx
-                        .send(PubSub::SelectedEntry(self.get_selected_entry()))
-                        .unwrap();
+                    self.sort_dirs = match self.sort_dirs {
+                        SortDirs::First => SortDirs::Mixed,
+                        SortDirs::Mixed => SortDirs::First,
+                    };
+
+                    self.sort_changed();
                 }
             }
             PubSub::FileFilter(filter) => {
@@ -997,7 +1688,8 @@ impl Component for FilePanel {
                         );
 
                         if !self.shown_file_list.is_empty() && !filter.is_empty() {
-                            let mut matcher = Matcher::new(Config::DEFAULT.match_paths());
+                            let mut matcher =
+                                Matcher::new(nucleo_matcher::Config::DEFAULT.match_paths());
 
                             let pattern =
                                 Pattern::parse(filter, CaseMatching::Ignore, Normalization::Smart);
@@ -1138,6 +1830,23 @@ impl Component for FilePanel {
                 },
                 _ => self.reload(self.get_selected_file().as_deref()),
             },
+            PubSub::Panelized(cwd, command, files) => {
+                if let Focus::Focused = self.focus {
+                    self.panel_mode = Some(Box::new(Panelize::new(
+                        &self.palette,
+                        self.pubsub_tx.clone(),
+                        cwd,
+                        command,
+                        files,
+                        self.focus,
+                    )));
+                }
+            }
+            PubSub::GotoDirectory(dir) => {
+                if let Focus::Focused = self.focus {
+                    self.chdir(dir, None);
+                }
+            }
             PubSub::SelectFile(selected_file) => {
                 if let Focus::Focused = self.focus {
                     match selected_file.parent() {
@@ -1163,6 +1872,11 @@ impl Component for FilePanel {
     }
 
     fn render(&mut self, f: &mut Frame, chunk: &Rect, focus: Focus) {
+        if let Some(panel_mode) = &mut self.panel_mode {
+            panel_mode.render(f, chunk, focus);
+            return;
+        }
+
         let sections = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(1), Constraint::Length(3)])
@@ -1174,7 +1888,14 @@ impl Component for FilePanel {
                     Span::raw(symbols::line::NORMAL.horizontal),
                     Span::styled(
                         tilde_layout(
-                            &format!(" {} ", self.shown_cwd.to_string_lossy()),
+                            &match self.title_labels().as_slice() {
+                                [] => format!(" {} ", self.shown_cwd.to_string_lossy()),
+                                labels => format!(
+                                    " ({}) {} ",
+                                    labels.join(", "),
+                                    self.shown_cwd.to_string_lossy()
+                                ),
+                            },
                             chunk.width.saturating_sub(4).into(),
                         ),
                         match focus {
@@ -1215,45 +1936,111 @@ impl Component for FilePanel {
                 );
             }
             false => {
-                let items: Vec<ListItem> = self
+                let columns = self.columns();
+
+                let visible_entries: Vec<&Entry> = self
                     .shown_file_list
                     .iter()
                     .skip(self.first_line)
                     .take(upper_inner.height.into())
-                    .enumerate()
-                    .map(|(i, entry)| {
-                        let filename_max_width = (upper_inner.width as usize)
-                            .saturating_sub(entry.shown_size.width())
-                            .saturating_sub(9);
+                    .collect();
 
-                        let is_selected = self.first_line + i == self.cursor_position;
+                let max_column_width = |column: Column| {
+                    visible_entries
+                        .iter()
+                        .map(|entry| column_text(entry, column).width())
+                        .max()
+                        .unwrap_or(0)
+                };
 
-                        let filename = if is_selected && !matches!(focus, Focus::Focused) {
-                            tilde_layout(
-                                &std::iter::once('\u{2192}')
-                                    .chain(entry.label.chars().skip(1))
-                                    .collect::<String>(),
-                                filename_max_width,
-                            )
-                        } else {
-                            tilde_layout(&entry.label, filename_max_width)
-                        };
+                // The columns that are empty for all the visible entries (like the git status
+                // outside of git repositories) are not shown
+                let (columns, mut column_widths): (Vec<Column>, Vec<usize>) = columns
+                    .iter()
+                    .map(|&column| match column {
+                        Column::Name | Column::LinkTarget => (column, 0),
+                        _ => (column, max_column_width(column)),
+                    })
+                    .filter(|&(column, width)| {
+                        width > 0 || matches!(column, Column::Name | Column::LinkTarget)
+                    })
+                    .unzip();
+
+                // The name and link target columns share the width left by the other columns
+                let free_width = (upper_inner.width as usize)
+                    .saturating_sub(column_widths.iter().sum())
+                    .saturating_sub(columns.len().saturating_sub(1));
+
+                let link_target_width = match (
+                    columns.contains(&Column::Name),
+                    columns.contains(&Column::LinkTarget),
+                ) {
+                    (true, true) => max_column_width(Column::LinkTarget).min(free_width / 2),
+                    (false, true) => free_width,
+                    _ => 0,
+                };
 
-                        let filename_width = filename.width();
+                for (column, width) in columns.iter().zip(column_widths.iter_mut()) {
+                    match column {
+                        Column::Name => *width = free_width - link_target_width,
+                        Column::LinkTarget => *width = link_target_width,
+                        _ => {}
+                    }
+                }
+
+                let items: Vec<ListItem> = visible_entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &entry)| {
+                        let is_selected = self.first_line + i == self.cursor_position;
+
+                        let line = columns
+                            .iter()
+                            .zip(&column_widths)
+                            .map(|(&column, &width)| {
+                                let text = match column {
+                                    Column::Name
+                                        if is_selected && !matches!(focus, Focus::Focused) =>
+                                    {
+                                        tilde_layout(
+                                            &std::iter::once('\u{2192}')
+                                                .chain(entry.label.chars().skip(1))
+                                                .collect::<String>(),
+                                            width,
+                                        )
+                                    }
+                                    Column::Name | Column::LinkTarget => {
+                                        tilde_layout(&column_text(entry, column), width)
+                                    }
+                                    _ => column_text(entry, column),
+                                };
+
+                                let text_width = text.width();
+
+                                // The reason why I add {:width$} whitespaces next to the
+                                // text instead of putting the text directly
+                                // inside {:width$} is because the {:width$} formatting
+                                // has a bug with some 0-width Unicode characters
+                                match column {
+                                    Column::Size | Column::Inode | Column::Links => format!(
+                                        "{:width$}{}",
+                                        "",
+                                        &text,
+                                        width = width.saturating_sub(text_width)
+                                    ),
+                                    _ => format!(
+                                        "{}{:width$}",
+                                        &text,
+                                        "",
+                                        width = width.saturating_sub(text_width)
+                                    ),
+                                }
+                            })
+                            .collect::<Vec<String>>()
+                            .join(" ");
 
-                        // The reason why I add {:width$} whitespaces after the
-                        // filename instead of putting the filename directly
-                        // inside {:width$} is because the {:width$} formatting
-                        // has a bug with some 0-width Unicode characters
                         Span::styled(
-                            format!(
-                                "{}{:width$} {} {}",
-                                &filename,
-                                "",
-                                &entry.shown_size,
-                                &entry.shown_mtime,
-                                width = filename_max_width.saturating_sub(filename_width)
-                            ),
+                            line,
                             match (
                                 self.tagged_files.contains(entry),
                                 is_selected,
@@ -1263,7 +2050,10 @@ impl Component for FilePanel {
                                 (true, true, false) => self.palette.marked,
                                 (true, false, _) => self.palette.marked,
                                 (false, true, true) => self.palette.selected,
-                                (false, _, _) => entry.style,
+                                (false, _, _) => match entry.git_status {
+                                    Some(git_status) => git_status.style(&self.palette),
+                                    None => entry.style,
+                                },
                             },
                         )
                         .into()
@@ -1355,7 +2145,9 @@ impl Panel for FilePanel {
     fn change_focus(&mut self, focus: Focus) {
         self.focus = focus;
 
-        if let Focus::Focused = focus {
+        if let Some(panel_mode) = &mut self.panel_mode {
+            panel_mode.change_focus(focus);
+        } else if let Focus::Focused = focus {
             self.pubsub_tx
                 .send(PubSub::ButtonLabels(
                     LABELS.iter().map(|&label| String::from(label)).collect(),
@@ -1365,6 +2157,10 @@ impl Panel for FilePanel {
     }
 
     fn get_selected_entry(&self) -> Option<Entry> {
+        if let Some(panel_mode) = &self.panel_mode {
+            return panel_mode.get_selected_entry();
+        }
+
         match self.shown_file_list.is_empty() {
             true => None,
             false => Some(self.shown_file_list[self.cursor_position].clone()),
@@ -1372,11 +2168,17 @@ impl Panel for FilePanel {
     }
 
     fn get_cwd(&self) -> Option<PathBuf> {
-        Some(self.cwd.clone())
+        match &self.panel_mode {
+            Some(panel_mode) => panel_mode.get_cwd(),
+            None => Some(self.cwd.clone()),
+        }
     }
 
     fn get_shown_cwd(&self) -> Option<PathBuf> {
-        Some(self.shown_cwd.clone())
+        match &self.panel_mode {
+            Some(panel_mode) => panel_mode.get_cwd().map(|cwd| self.archive_path(&cwd)),
+            None => Some(self.shown_cwd.clone()),
+        }
     }
 
     fn get_old_cwd(&self) -> Option<PathBuf> {
@@ -1384,15 +2186,24 @@ impl Panel for FilePanel {
     }
 
     fn get_tagged_files(&self) -> Vec<Entry> {
+        if let Some(panel_mode) = &self.panel_mode {
+            return panel_mode.get_tagged_files();
+        }
+
         let mut tagged_files = self.tagged_files.clone();
 
-        tagged_files
-            .sort_unstable_by(|a, b| sort_by_function(self.sort_method)(a, b, self.sort_order));
+        tagged_files.sort_unstable_by(|a, b| {
+            sort_entries(a, b, self.sort_method, self.sort_order, self.sort_dirs)
+        });
 
         tagged_files
     }
 
     fn get_selected_files(&self) -> Vec<Entry> {
+        if let Some(panel_mode) = &self.panel_mode {
+            return panel_mode.get_selected_files();
+        }
+
         match self.tagged_files.is_empty() {
             true => {
                 let mut selected_files = Vec::new();
@@ -1407,7 +2218,16 @@ impl Panel for FilePanel {
         }
     }
 
+    fn get_file_list(&self) -> Option<Vec<Entry>> {
+        match &self.panel_mode {
+            Some(_) => None,
+            None => Some(self.shown_file_list.clone()),
+        }
+    }
+
     fn chdir(&mut self, cwd: &Path, selected_file: Option<&Path>) {
+        self.panel_mode = None;
+
         let new_cwd = self.unarchive_path(
             self.archive_path(cwd)
                 .ancestors()
@@ -1421,8 +2241,20 @@ impl Panel for FilePanel {
             self.shown_cwd = self.archive_path(&new_cwd);
             self.cwd = new_cwd;
 
+            self.add_to_history();
+
+            if let Some(watcher_tx) = &self.watcher_tx {
+                let _ = watcher_tx.send(Some(self.cwd.clone()));
+            }
+
+            self.apply_dir_sort();
+
+            self.flat = false;
             self.file_filter.clear();
             self.tagged_files.clear();
+            self.stop_dir_size_thread();
+            self.dir_sizes.clear();
+            self.git_statuses = None;
             self.cursor_position = 0;
             self.first_line = 0;
 
@@ -1440,6 +2272,11 @@ impl Panel for FilePanel {
     }
 
     fn reload(&mut self, selected_file: Option<&Path>) {
+        if let Some(panel_mode) = &mut self.panel_mode {
+            panel_mode.reload(selected_file);
+            return;
+        }
+
         let new_cwd = self.unarchive_path(
             self.shown_cwd
                 .ancestors()
@@ -1458,6 +2295,19 @@ impl Panel for FilePanel {
             );
         }
     }
+
+    fn set_tagged_files(&mut self, files: &[PathBuf]) {
+        if self.panel_mode.is_some() {
+            return;
+        }
+
+        self.tagged_files = self
+            .shown_file_list
+            .iter()
+            .filter(|entry| files.contains(&entry.file))
+            .cloned()
+            .collect();
+    }
 }
 
 impl PanelComponent for FilePanel {}
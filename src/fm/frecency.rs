@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use atomicwrites::{AllowOverwrite, AtomicFile};
+use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+
+// When the sum of the ranks of the visited directories exceeds this value, all the ranks are
+// decreased, and the directories that have not been visited in a long time are forgotten
+const MAX_RANK: f64 = 10000.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Visits {
+    rank: f64,
+    // In seconds since the epoch
+    last_access: u64,
+}
+
+type FrecencyData = HashMap<PathBuf, Visits>;
+
+/// The directories visited by all the panels, shared across sessions, ranked by frecency
+/// (frequency and recency of visits) like zoxide does.
+#[derive(Debug)]
+pub struct Frecency {
+    data: FrecencyData,
+    // The file is written by a thread, not to block the UI at every directory change
+    writer: Option<(Sender<FrecencyData>, JoinHandle<()>)>,
+}
+
+fn writer_thread(file: PathBuf, data_rx: Receiver<FrecencyData>) {
+    while let Ok(mut data) = data_rx.recv() {
+        // Only the most recent ranks need to be written
+        while let Ok(newer_data) = data_rx.try_recv() {
+            data = newer_data;
+        }
+
+        let _ = AtomicFile::new(&file, AllowOverwrite).write(|f| {
+            let mut writer = BufWriter::new(f);
+
+            serde_json::to_writer(&mut writer, &data)?;
+
+            writer.flush()
+        });
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+impl Frecency {
+    pub fn new(file: Option<&Path>) -> Frecency {
+        let data = match file.map(File::open) {
+            Some(Ok(f)) => serde_json::from_reader(BufReader::new(f)).unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+
+        let writer = file.map(|file| {
+            let (data_tx, data_rx) = crossbeam_channel::unbounded();
+            let file = PathBuf::from(file);
+
+            (data_tx, thread::spawn(move || writer_thread(file, data_rx)))
+        });
+
+        Frecency { data, writer }
+    }
+
+    /// The visited directories, the highest frecency first.
+    pub fn get(&self) -> Vec<PathBuf> {
+        let now = now_secs();
+
+        let mut dirs: Vec<(&PathBuf, f64)> = self
+            .data
+            .iter()
+            .map(|(dir, visits)| {
+                // The same weights used by zoxide
+                let score = match now.saturating_sub(visits.last_access) {
+                    age if age < 60 * 60 => visits.rank * 4.0,
+                    age if age < 24 * 60 * 60 => visits.rank * 2.0,
+                    age if age < 7 * 24 * 60 * 60 => visits.rank / 2.0,
+                    _ => visits.rank / 4.0,
+                };
+
+                (dir, score)
+            })
+            .collect();
+
+        dirs.sort_by(|(dir_a, a), (dir_b, b)| b.total_cmp(a).then_with(|| dir_a.cmp(dir_b)));
+
+        dirs.into_iter().map(|(dir, _)| dir.clone()).collect()
+    }
+
+    pub fn add(&mut self, dir: &Path) {
+        let now = now_secs();
+
+        self.data
+            .entry(PathBuf::from(dir))
+            .and_modify(|visits| {
+                visits.rank += 1.0;
+                visits.last_access = now;
+            })
+            .or_insert(Visits {
+                rank: 1.0,
+                last_access: now,
+            });
+
+        let total_rank: f64 = self.data.values().map(|visits| visits.rank).sum();
+
+        if total_rank > MAX_RANK {
+            let factor = (0.9 * MAX_RANK) / total_rank;
+
+            for visits in self.data.values_mut() {
+                visits.rank *= factor;
+            }
+
+            self.data.retain(|_dir, visits| visits.rank >= 1.0);
+        }
+
+        self.update_file();
+    }
+
+    /// Forget the directories, like the ones that don't exist anymore.
+    pub fn remove(&mut self, dirs: &[PathBuf]) {
+        if dirs.is_empty() {
+            return;
+        }
+
+        for dir in dirs {
+            self.data.remove(dir);
+        }
+
+        self.update_file();
+    }
+
+    fn update_file(&mut self) {
+        if let Some((data_tx, _)) = &self.writer {
+            let _ = data_tx.send(self.data.clone());
+        }
+    }
+}
+
+impl Drop for Frecency {
+    fn drop(&mut self) {
+        // Closing the channel lets the thread write the pending ranks before exiting
+        if let Some((data_tx, handle)) = self.writer.take() {
+            drop(data_tx);
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frecent_dirs_are_ranked_by_visits() {
+        let mut frecency = Frecency::new(None);
+
+        frecency.add(Path::new("/a"));
+        frecency.add(Path::new("/b"));
+        frecency.add(Path::new("/b"));
+        frecency.add(Path::new("/c"));
+        frecency.remove(&[PathBuf::from("/c")]);
+
+        assert_eq!(
+            frecency.get(),
+            vec![PathBuf::from("/b"), PathBuf::from("/a")]
+        );
+    }
+
+    #[test]
+    fn test_old_visits_rank_lower() {
+        let mut frecency = Frecency::new(None);
+
+        frecency.add(Path::new("/old"));
+        frecency.add(Path::new("/old"));
+        frecency.add(Path::new("/new"));
+
+        // 2 visits a week ago score less than 1 visit now
+        frecency
+            .data
+            .get_mut(Path::new("/old"))
+            .unwrap()
+            .last_access -= 7 * 24 * 60 * 60;
+
+        assert_eq!(
+            frecency.get(),
+            vec![PathBuf::from("/new"), PathBuf::from("/old")]
+        );
+    }
+
+    #[test]
+    fn test_ranks_are_aged() {
+        let mut frecency = Frecency::new(None);
+
+        for _ in 0..(MAX_RANK as usize) {
+            frecency.add(Path::new("/often"));
+        }
+
+        frecency.add(Path::new("/once"));
+
+        // The rarely visited directories are forgotten
+        assert_eq!(frecency.get(), vec![PathBuf::from("/often")]);
+    }
+}
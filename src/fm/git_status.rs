@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use ratatui::prelude::*;
+
+use crate::palette::Palette;
+
+// The variants are ordered by priority, as the status of a directory is the highest priority
+// status of the files inside it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GitStatus {
+    Ignored,
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+impl GitStatus {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            GitStatus::Ignored => "!",
+            GitStatus::Untracked => "?",
+            GitStatus::Staged => "S",
+            GitStatus::Modified => "M",
+            GitStatus::Conflicted => "U",
+        }
+    }
+
+    pub fn style(&self, palette: &Palette) -> Style {
+        match self {
+            GitStatus::Ignored => palette.git_ignored,
+            GitStatus::Untracked => palette.git_untracked,
+            GitStatus::Staged => palette.git_staged,
+            GitStatus::Modified => palette.git_modified,
+            GitStatus::Conflicted => palette.git_conflicted,
+        }
+    }
+}
+
+/// The git status of the files in a directory that is inside a git repository.
+#[derive(Debug, Clone, Default)]
+pub struct GitStatuses {
+    files: HashMap<PathBuf, GitStatus>,
+}
+
+impl GitStatuses {
+    pub fn get(&self, file: &Path) -> Option<GitStatus> {
+        // The files inside untracked and ignored directories are not listed by git
+        self.files.get(file).copied().or_else(|| {
+            file.ancestors()
+                .skip(1)
+                .find_map(|dir| match self.files.get(dir) {
+                    Some(status @ (GitStatus::Untracked | GitStatus::Ignored)) => Some(*status),
+                    _ => None,
+                })
+        })
+    }
+}
+
+fn parse_status(xy: &[u8]) -> Option<GitStatus> {
+    let (x, y) = (*xy.first()?, *xy.get(1)?);
+
+    match (x, y) {
+        (b'?', b'?') => Some(GitStatus::Untracked),
+        (b'!', b'!') => Some(GitStatus::Ignored),
+        (b'D', b'D') | (b'A', b'A') | (b'U', _) | (_, b'U') => Some(GitStatus::Conflicted),
+        (_, b' ') => Some(GitStatus::Staged),
+        _ => Some(GitStatus::Modified),
+    }
+}
+
+/// Get the git status of the files in `cwd` and in its subdirectories, by running `git status`.
+///
+/// Returns `None` if `cwd` is not inside a git repository, or if git is not available.
+pub fn get_git_statuses(cwd: &Path) -> Option<GitStatuses> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-prefix"])
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // git reports the paths relative to the root of the repository, so the root is found by
+    // going up from cwd, instead of using `git rev-parse --show-toplevel`, that resolves symlinks
+    let prefix = Path::new(OsStr::from_bytes(output.stdout.trim_ascii_end()));
+    let root = (0..prefix.components().count()).try_fold(cwd, |dir, _| dir.parent())?;
+
+    let output = Command::new("git")
+        .args([
+            "--no-optional-locks",
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--ignored",
+            "--",
+            ".",
+        ])
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // The paths are not necessarily valid UTF-8
+    let mut records = output.stdout.split(|b| *b == b'\0');
+    let mut files: HashMap<PathBuf, GitStatus> = HashMap::new();
+
+    while let Some(record) = records.next() {
+        let (Some(xy), Some(file)) = (record.get(..2), record.get(3..)) else {
+            continue;
+        };
+
+        // Renames and copies are followed by the original path, that we don't need
+        if xy.contains(&b'R') || xy.contains(&b'C') {
+            records.next();
+        }
+
+        let Some(status) = parse_status(xy) else {
+            continue;
+        };
+
+        let file = root.join(OsStr::from_bytes(file.strip_suffix(b"/").unwrap_or(file)));
+
+        // Directories get the status of the files inside them, but they are not ignored just
+        // because they contain ignored files
+        let dirs = file
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(root) && *dir != root);
+
+        if status != GitStatus::Ignored {
+            for dir in dirs {
+                let dir_status = files.entry(PathBuf::from(dir)).or_insert(status);
+
+                *dir_status = (*dir_status).max(status);
+            }
+        }
+
+        files.insert(file, status);
+    }
+
+    Some(GitStatuses { files })
+}
@@ -0,0 +1,209 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    thread,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    app::PubSub,
+    component::{Component, Focus},
+    fm::entry::{get_file_list, sort_entries, Entry, SortBy, SortDirs, SortOrder},
+    palette::Palette,
+    tilde_layout::tilde_layout,
+    viewer::{dir_viewer::DirViewer, file_viewer::FileViewer},
+};
+
+/// The side columns of the Miller columns layout: the parent directory of the focused panel on
+/// the left, and the preview of its selected entry on the right.
+#[derive(Debug)]
+pub struct Miller {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    tabsize: u8,
+    cwd: Option<PathBuf>,
+    parent: Option<DirViewer>,
+    parent_tx: Sender<PathBuf>,
+    parent_rx: Receiver<(PathBuf, Vec<Entry>)>,
+    preview_file: Option<PathBuf>,
+    preview: Option<FileViewer>,
+}
+
+impl Miller {
+    pub fn new(palette: &Rc<Palette>, pubsub_tx: Sender<PubSub>, tabsize: u8) -> Miller {
+        let (parent_tx, parent_rx) = Self::parent_thread(palette, &pubsub_tx);
+
+        Miller {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            tabsize,
+            cwd: None,
+            parent: None,
+            parent_tx,
+            parent_rx,
+            preview_file: None,
+            preview: None,
+        }
+    }
+
+    // Lists the parent directories of the working directories sent to it, like the file list
+    // thread of the panels, as they may be on slow mounts
+    fn parent_thread(
+        palette: &Rc<Palette>,
+        pubsub_tx: &Sender<PubSub>,
+    ) -> (Sender<PathBuf>, Receiver<(PathBuf, Vec<Entry>)>) {
+        let (cwd_tx, cwd_rx) = crossbeam_channel::unbounded::<PathBuf>();
+        let (parent_tx, parent_rx) = crossbeam_channel::unbounded();
+
+        let pubsub_tx = pubsub_tx.clone();
+        let palette = palette.as_ref().clone();
+
+        thread::spawn(move || loop {
+            let cwd = match cwd_rx.is_empty() {
+                // When Miller gets dropped, the channel returns an error
+                true => match cwd_rx.recv() {
+                    Ok(cwd) => cwd,
+                    Err(_) => return,
+                },
+
+                // We're only interested in the latest request in the queue
+                false => cwd_rx.try_iter().last().unwrap(),
+            };
+
+            let mut file_list = cwd
+                .parent()
+                .and_then(|parent| get_file_list(parent, &palette, None).ok())
+                .unwrap_or_default();
+
+            file_list.sort_unstable_by(|a, b| {
+                sort_entries(a, b, SortBy::Name, SortOrder::Normal, SortDirs::First)
+            });
+
+            // Discard the result if there are newer requests in the queue
+            if cwd_rx.is_empty() {
+                let _ = parent_tx.send((cwd, file_list));
+                let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            }
+        });
+
+        (cwd_tx, parent_rx)
+    }
+
+    pub fn update(&mut self, cwd: Option<&Path>, entry: Option<&Entry>) {
+        if self.cwd.as_deref() != cwd {
+            self.cwd = cwd.map(PathBuf::from);
+            self.parent = None;
+
+            if let Some(cwd) = cwd {
+                let _ = self.parent_tx.send(PathBuf::from(cwd));
+            }
+        }
+
+        let file = entry.map(|entry| entry.file.as_path());
+
+        if self.preview_file.as_deref() != file {
+            self.preview_file = file.map(PathBuf::from);
+
+            // Like the quick view, preview only regular files and directories
+            self.preview = entry
+                .filter(|entry| entry.stat.is_file() || entry.stat.is_dir())
+                .and_then(|entry| {
+                    FileViewer::new(
+                        &self.palette,
+                        self.pubsub_tx.clone(),
+                        &entry.file,
+                        self.tabsize,
+                    )
+                    .ok()
+                });
+        }
+    }
+
+    pub fn handle_pubsub(&mut self, event: &PubSub) {
+        match event {
+            PubSub::ComponentThreadEvent => {
+                if let Some((cwd, file_list)) = self.parent_rx.try_iter().last() {
+                    // Discard the listing of the parent of a directory that is not shown anymore
+                    if let (Some(parent), true) = (cwd.parent(), self.cwd.as_ref() == Some(&cwd)) {
+                        let mut viewer = DirViewer::new(
+                            &self.palette,
+                            self.pubsub_tx.clone(),
+                            parent,
+                            &parent.to_string_lossy(),
+                            file_list,
+                        );
+
+                        viewer.select_file(&cwd);
+
+                        self.parent = Some(viewer);
+                    }
+                }
+            }
+            PubSub::Reload => self.reload(),
+            _ => (),
+        }
+    }
+
+    fn reload(&mut self) {
+        self.cwd = None;
+        self.parent = None;
+        self.preview_file = None;
+        self.preview = None;
+    }
+
+    fn render_column(&self, f: &mut Frame, chunk: &Rect, title: &str, file: Option<&Path>) -> Rect {
+        let file_name = file
+            .map(|file| match file.file_name() {
+                Some(file_name) => file_name.to_string_lossy().to_string(),
+                None => file.to_string_lossy().to_string(),
+            })
+            .unwrap_or_default();
+
+        let block = Block::default()
+            .title_top(
+                Line::from(vec![
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                    Span::styled(
+                        tilde_layout(
+                            &match file_name.is_empty() {
+                                true => format!(" ({}) ", title),
+                                false => format!(" ({}) {} ", title, file_name),
+                            },
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        self.palette.panel,
+                    ),
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                ])
+                .left_aligned(),
+            )
+            .borders(Borders::ALL)
+            .style(self.palette.panel);
+
+        let inner = block.inner(*chunk);
+
+        f.render_widget(block, *chunk);
+
+        inner
+    }
+
+    pub fn render_parent(&mut self, f: &mut Frame, chunk: &Rect) {
+        let parent = self.cwd.as_deref().and_then(|cwd| cwd.parent());
+
+        let inner = self.render_column(f, chunk, "Parent", parent);
+
+        if let Some(viewer) = &mut self.parent {
+            viewer.render(f, &inner, Focus::Normal);
+        }
+    }
+
+    pub fn render_preview(&mut self, f: &mut Frame, chunk: &Rect) {
+        let inner = self.render_column(f, chunk, "Preview", self.preview_file.as_deref());
+
+        if let Some(viewer) = &mut self.preview {
+            viewer.render(f, &inner, Focus::Normal);
+        }
+    }
+}
@@ -1,11 +1,30 @@
 pub mod app;
 pub mod archive_mounter;
 pub mod bookmarks;
+pub mod child;
 pub mod command_bar;
+pub mod compare_dirs;
 pub mod cp_mv_rm;
+pub mod dir_history;
+pub mod dir_sorts;
+pub mod disk_usage;
+pub mod dlg_basket;
+pub mod dlg_dir_list;
 pub mod dlg_fzf;
 pub mod dlg_mount_archive;
+pub mod dlg_panelize;
+pub mod dlg_tag;
 pub mod entry;
 pub mod file_panel;
+pub mod frecency;
+pub mod git_status;
+pub mod miller;
 pub mod panel;
+pub mod panelize;
 pub mod quickview;
+pub mod session;
+pub mod tabs;
+pub mod tag_criteria;
+pub mod tag_sets;
+pub mod tree_view;
+pub mod watcher;
@@ -5,7 +5,7 @@ use std::{
 
 use crate::{
     component::{Component, Focus},
-    fm::entry::Entry,
+    fm::{entry::Entry, tabs::PanelTabsState},
 };
 
 pub trait Panel {
@@ -35,8 +35,21 @@ pub trait Panel {
         Vec::new()
     }
 
+    fn get_file_list(&self) -> Option<Vec<Entry>> {
+        None
+    }
+
+    fn get_background_cwds(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    fn get_tabs_state(&self) -> Option<PanelTabsState> {
+        None
+    }
+
     fn chdir(&mut self, _cwd: &Path, _selected_file: Option<&Path>) {}
     fn reload(&mut self, _selected_file: Option<&Path>) {}
+    fn set_tagged_files(&mut self, _files: &[PathBuf]) {}
 }
 
 impl fmt::Debug for dyn Panel + '_ {
@@ -0,0 +1,515 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crossbeam_channel::Sender;
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::{
+        app::{human_readable_size, LABELS},
+        entry::{common_parent, get_path_list, Entry},
+        panel::{Panel, PanelComponent},
+    },
+    palette::Palette,
+    tilde_layout::tilde_layout,
+};
+
+/// A panel listing the files produced by a shell command (like `find` or `git ls-files`), in
+/// the order given by the command, whatever directory they are in.
+#[derive(Debug)]
+pub struct Panelize {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    cwd: PathBuf,
+    command: String,
+    file_list: Vec<Entry>,
+    tagged_files: Vec<Entry>,
+    cursor_position: usize,
+    first_line: usize,
+    focus: Focus,
+    rect: Rect,
+}
+
+impl Panelize {
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        cwd: &Path,
+        command: &str,
+        files: &[PathBuf],
+        focus: Focus,
+    ) -> Panelize {
+        let mut panelize = Panelize {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            cwd: PathBuf::from(cwd),
+            command: String::from(command),
+            file_list: get_path_list(cwd, files, palette),
+            tagged_files: Vec::new(),
+            cursor_position: 0,
+            first_line: 0,
+            focus,
+            rect: Rect::default(),
+        };
+
+        panelize.select_file(None);
+
+        panelize
+    }
+
+    fn select_file(&mut self, selected_file: Option<&Path>) {
+        self.cursor_position = self.clamp_cursor(match selected_file {
+            Some(file) => self
+                .file_list
+                .iter()
+                .position(|entry| entry.file == file)
+                .unwrap_or(self.cursor_position),
+            None => 0,
+        });
+
+        self.clamp_first_line();
+
+        if let Focus::Focused = self.focus {
+            self.pubsub_tx
+                .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                .unwrap();
+        }
+    }
+
+    fn get_selected_file(&self) -> Option<PathBuf> {
+        self.file_list
+            .get(self.cursor_position)
+            .map(|entry| entry.file.clone())
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.file_list.len().saturating_sub(1))
+    }
+
+    fn clamp_first_line(&mut self) {
+        if (self.first_line + (self.rect.height as usize)) > self.file_list.len() {
+            self.first_line = self
+                .file_list
+                .len()
+                .saturating_sub(self.rect.height as usize);
+        }
+    }
+
+    fn move_cursor(&mut self, new_cursor_pos: usize) {
+        let old_cursor_position = self.cursor_position;
+
+        self.cursor_position = self.clamp_cursor(new_cursor_pos);
+
+        if self.cursor_position != old_cursor_position {
+            if let Focus::Focused = self.focus {
+                self.pubsub_tx
+                    .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn tag_toggle(&mut self) {
+        if let Some(entry) = self.file_list.get(self.cursor_position) {
+            if let Some(i) = self.tagged_files.iter().position(|x| x == entry) {
+                self.tagged_files.swap_remove(i);
+            } else {
+                self.tagged_files.push(entry.clone());
+            }
+        }
+    }
+}
+
+impl Component for Panelize {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        match key {
+            Key::Up | Key::Char('k') => self.move_cursor(self.cursor_position.saturating_sub(1)),
+            Key::Down | Key::Char('j') => self.move_cursor(self.cursor_position.saturating_add(1)),
+            Key::Home | Key::CtrlHome | Key::Char('g') => self.move_cursor(0),
+            Key::End | Key::CtrlEnd | Key::Char('G') => self.move_cursor(self.file_list.len()),
+            Key::PageUp | Key::Ctrl('b') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.first_line = self.first_line.saturating_sub(rect_height);
+                self.move_cursor(self.cursor_position.saturating_sub(rect_height));
+            }
+            Key::PageDown | Key::Ctrl('f') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.first_line = self.first_line.saturating_add(rect_height);
+                self.clamp_first_line();
+                self.move_cursor(self.cursor_position.saturating_add(rect_height));
+            }
+            Key::Char('v') | Key::F(3) | Key::Char('3') => {
+                if let Some(entry) = self.get_selected_entry() {
+                    if !entry.stat.is_dir() {
+                        self.pubsub_tx
+                            .send(PubSub::ViewFile(self.cwd.clone(), entry.file))
+                            .unwrap();
+                    }
+                }
+            }
+            Key::Char('e') | Key::F(4) | Key::Char('4') => {
+                if let Some(entry) = self.get_selected_entry() {
+                    if !entry.stat.is_dir() {
+                        self.pubsub_tx
+                            .send(PubSub::EditFile(self.cwd.clone(), entry.file))
+                            .unwrap();
+                    }
+                }
+            }
+            Key::Insert | Key::Char(' ') => {
+                self.tag_toggle();
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('*') => {
+                for entry in &self.file_list {
+                    if let Some(i) = self.tagged_files.iter().position(|x| x == entry) {
+                        self.tagged_files.swap_remove(i);
+                    } else {
+                        self.tagged_files.push(entry.clone());
+                    }
+                }
+            }
+            Key::Char('t') => {
+                if let Some(entry) = self.file_list.get(self.cursor_position) {
+                    if !self.tagged_files.contains(entry) {
+                        self.tagged_files.push(entry.clone());
+                    }
+                }
+
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('T') => self.tagged_files.clone_from(&self.file_list),
+            Key::Char('u') => {
+                if let Some(entry) = self.file_list.get(self.cursor_position) {
+                    if let Some(i) = self.tagged_files.iter().position(|x| x == entry) {
+                        self.tagged_files.swap_remove(i);
+                    }
+                }
+
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('U') => self.tagged_files.clear(),
+            Key::Ctrl('r') => self.pubsub_tx.send(PubSub::Reload).unwrap(),
+            Key::F(8) | Key::Char('8') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    let question = match selected_files.len() {
+                        1 => format!("Delete {}?", selected_files[0].file_name),
+                        n => format!("Delete {} files/directories?", n),
+                    };
+
+                    self.pubsub_tx
+                        .send(PubSub::Question(
+                            String::from("Delete"),
+                            question,
+                            Box::new(PubSub::Rm(
+                                common_parent(&selected_files).unwrap_or_else(|| self.cwd.clone()),
+                                selected_files,
+                            )),
+                        ))
+                        .unwrap();
+                }
+            }
+            Key::F(5) | Key::Char('5') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    self.pubsub_tx
+                        .send(PubSub::Cp(
+                            common_parent(&selected_files).unwrap_or_else(|| self.cwd.clone()),
+                            selected_files,
+                        ))
+                        .unwrap();
+                }
+            }
+            Key::F(6) | Key::Char('6') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    self.pubsub_tx
+                        .send(PubSub::Mv(
+                            common_parent(&selected_files).unwrap_or_else(|| self.cwd.clone()),
+                            selected_files,
+                        ))
+                        .unwrap();
+                }
+            }
+            _ => key_handled = false,
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        match button {
+            MouseButton::Left | MouseButton::Right if self.rect.contains(mouse_position) => {
+                let new_cursor_position =
+                    self.first_line + ((mouse_position.y - self.rect.y) as usize);
+
+                if new_cursor_position < self.file_list.len() {
+                    self.move_cursor(new_cursor_position);
+
+                    if let MouseButton::Right = button {
+                        self.tag_toggle();
+                    }
+                }
+            }
+            MouseButton::WheelUp => {
+                self.first_line = self.first_line.saturating_sub(1);
+
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                if (self.cursor_position - self.first_line) > rect_height {
+                    self.move_cursor(self.cursor_position.saturating_sub(1));
+                }
+            }
+            MouseButton::WheelDown => {
+                self.first_line = self.first_line.saturating_add(1);
+                self.clamp_first_line();
+
+                if self.first_line > self.cursor_position {
+                    self.move_cursor(self.first_line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_pubsub(&mut self, event: &PubSub) {
+        if let PubSub::Reload = event {
+            self.reload(self.get_selected_file().as_deref());
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, focus: Focus) {
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(*chunk);
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(vec![
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                    Span::styled(
+                        tilde_layout(
+                            &format!(" (Panelize) {} ", self.command),
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        match focus {
+                            Focus::Focused => self.palette.panel_reverse,
+                            _ => self.palette.panel,
+                        },
+                    ),
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                ])
+                .left_aligned(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .style(self.palette.panel);
+
+        let upper_inner = upper_block.inner(sections[0]);
+        let upper_height = (upper_inner.height as usize).saturating_sub(1);
+
+        self.rect = upper_inner;
+        self.clamp_first_line();
+
+        if self.first_line > self.cursor_position {
+            self.first_line = self.cursor_position;
+        }
+
+        if (self.cursor_position - self.first_line) > upper_height {
+            self.first_line = self.cursor_position.saturating_sub(upper_height);
+        }
+
+        f.render_widget(upper_block, sections[0]);
+
+        let items: Vec<ListItem> = self
+            .file_list
+            .iter()
+            .skip(self.first_line)
+            .take(upper_inner.height.into())
+            .enumerate()
+            .map(|(i, entry)| {
+                let filename_max_width = (upper_inner.width as usize)
+                    .saturating_sub(entry.shown_size.width())
+                    .saturating_sub(1);
+
+                let is_selected = self.first_line + i == self.cursor_position;
+
+                let filename = if is_selected && !matches!(focus, Focus::Focused) {
+                    tilde_layout(
+                        &std::iter::once('\u{2192}')
+                            .chain(entry.label.chars().skip(1))
+                            .collect::<String>(),
+                        filename_max_width,
+                    )
+                } else {
+                    tilde_layout(&entry.label, filename_max_width)
+                };
+
+                let filename_width = filename.width();
+
+                Span::styled(
+                    format!(
+                        "{}{:width$} {}",
+                        &filename,
+                        "",
+                        &entry.shown_size,
+                        width = filename_max_width.saturating_sub(filename_width)
+                    ),
+                    match (
+                        self.tagged_files.contains(entry),
+                        is_selected,
+                        matches!(focus, Focus::Focused),
+                    ) {
+                        (true, true, true) => self.palette.markselect,
+                        (true, true, false) => self.palette.marked,
+                        (true, false, _) => self.palette.marked,
+                        (false, true, true) => self.palette.selected,
+                        (false, _, _) => entry.style,
+                    },
+                )
+                .into()
+            })
+            .collect();
+
+        let items = List::new(items).highlight_style(match focus {
+            Focus::Focused => self.palette.selected_bg,
+            _ => Style::default(),
+        });
+
+        let mut state = ListState::default();
+        state.select(Some(self.cursor_position - self.first_line));
+
+        f.render_stateful_widget(items, upper_inner, &mut state);
+
+        let lower_block = Block::default()
+            .title_top(
+                Line::from(match self.tagged_files.is_empty() {
+                    true => Span::raw(symbols::line::NORMAL.horizontal),
+                    false => Span::styled(
+                        tilde_layout(
+                            &format!(
+                                " {} in {} file{} ",
+                                human_readable_size(
+                                    self.tagged_files
+                                        .iter()
+                                        .filter(|entry| !entry.stat.is_dir())
+                                        .filter_map(|entry| entry.size)
+                                        .sum()
+                                ),
+                                self.tagged_files.len(),
+                                if self.tagged_files.len() == 1 {
+                                    ""
+                                } else {
+                                    "s"
+                                }
+                            ),
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        self.palette.marked,
+                    ),
+                })
+                .centered(),
+            )
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.panel);
+
+        let lower_inner = lower_block.inner(sections[1]);
+
+        f.render_widget(lower_block, sections[1]);
+
+        if let Some(entry) = self.file_list.get(self.cursor_position) {
+            f.render_widget(
+                Block::new()
+                    .title_top(
+                        Line::from(Span::raw(tilde_layout(
+                            &entry.details,
+                            lower_inner.width.into(),
+                        )))
+                        .left_aligned(),
+                    )
+                    .style(self.palette.panel),
+                lower_inner,
+            );
+        }
+    }
+}
+
+impl Panel for Panelize {
+    fn change_focus(&mut self, focus: Focus) {
+        self.focus = focus;
+
+        if let Focus::Focused = focus {
+            self.pubsub_tx
+                .send(PubSub::ButtonLabels(
+                    LABELS.iter().map(|&label| String::from(label)).collect(),
+                ))
+                .unwrap();
+        }
+    }
+
+    fn get_selected_entry(&self) -> Option<Entry> {
+        self.file_list.get(self.cursor_position).cloned()
+    }
+
+    fn get_cwd(&self) -> Option<PathBuf> {
+        // The working directory is the one containing the selected entry
+        Some(
+            self.file_list
+                .get(self.cursor_position)
+                .and_then(|entry| entry.file.parent())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| self.cwd.clone()),
+        )
+    }
+
+    fn get_tagged_files(&self) -> Vec<Entry> {
+        let mut tagged_files = self.tagged_files.clone();
+
+        tagged_files.sort_unstable_by(|a, b| a.file.cmp(&b.file));
+
+        tagged_files
+    }
+
+    fn get_selected_files(&self) -> Vec<Entry> {
+        match self.tagged_files.is_empty() {
+            true => self.get_selected_entry().into_iter().collect(),
+            false => self.get_tagged_files(),
+        }
+    }
+
+    fn reload(&mut self, selected_file: Option<&Path>) {
+        // The command is not run again, but the files that have been deleted or moved away are
+        // removed from the list
+        let files: Vec<PathBuf> = self
+            .file_list
+            .iter()
+            .map(|entry| entry.file.clone())
+            .collect();
+
+        self.file_list = get_path_list(&self.cwd, &files, &self.palette);
+
+        self.tagged_files
+            .retain(|entry| fs::symlink_metadata(&entry.file).is_ok());
+
+        self.select_file(selected_file);
+    }
+}
+
+impl PanelComponent for Panelize {}
@@ -1,6 +1,6 @@
-use std::rc::Rc;
+use std::{path::PathBuf, rc::Rc};
 
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 use ratatui::{prelude::*, widgets::*};
 use termion::event::*;
 
@@ -10,6 +10,7 @@ use crate::{
     fm::{
         entry::Entry,
         panel::{Panel, PanelComponent},
+        watcher,
     },
     palette::Palette,
     tilde_layout::tilde_layout,
@@ -24,6 +25,9 @@ pub struct QuickView {
     filename: String,
     viewer: Option<FileViewer>,
     tabsize: u8,
+    file: Option<PathBuf>,
+    watcher_tx: Option<Sender<Option<PathBuf>>>,
+    file_changed_rx: Receiver<PathBuf>,
 }
 
 impl QuickView {
@@ -33,6 +37,17 @@ impl QuickView {
         tabsize: u8,
         _focus: Focus,
     ) -> QuickView {
+        let (file_changed_tx, file_changed_rx) = crossbeam_channel::unbounded();
+
+        let watcher_tx = {
+            let pubsub_tx = pubsub_tx.clone();
+
+            watcher::start(move |file| {
+                let _ = file_changed_tx.send(PathBuf::from(file));
+                let _ = pubsub_tx.send(PubSub::ComponentThreadEvent);
+            })
+        };
+
         QuickView {
             palette: Rc::clone(palette),
             pubsub_tx,
@@ -40,6 +55,23 @@ impl QuickView {
             filename: String::from(""),
             viewer: None,
             tabsize,
+            file: None,
+            watcher_tx,
+            file_changed_rx,
+        }
+    }
+
+    fn load_viewer(&mut self, file: Option<PathBuf>) {
+        self.viewer = file.as_ref().and_then(|file| {
+            FileViewer::new(&self.palette, self.pubsub_tx.clone(), file, self.tabsize).ok()
+        });
+
+        if self.file != file {
+            if let Some(watcher_tx) = &self.watcher_tx {
+                let _ = watcher_tx.send(file.clone());
+            }
+
+            self.file = file;
         }
     }
 
@@ -58,21 +90,15 @@ impl QuickView {
 
                     // We use the quick viewer only for regular files and directories
                     if entry.stat.is_file() || entry.stat.is_dir() {
-                        self.viewer = FileViewer::new(
-                            &self.palette,
-                            self.pubsub_tx.clone(),
-                            &entry.file,
-                            self.tabsize,
-                        )
-                        .ok();
+                        self.load_viewer(Some(entry.file.clone()));
                     } else {
-                        self.viewer = None;
+                        self.load_viewer(None);
                     }
                 }
             }
             _ => {
                 self.filename = String::from("");
-                self.viewer = None;
+                self.load_viewer(None);
             }
         };
     }
@@ -103,9 +129,15 @@ impl Component for QuickView {
 
                 self.update_quickview(entry.as_ref());
             }
-            PubSub::SelectedEntry(entry) => {
-                if self.enabled {
-                    self.update_quickview(entry.as_ref());
+            PubSub::SelectedEntry(entry) if self.enabled => {
+                self.update_quickview(entry.as_ref());
+            }
+            PubSub::ComponentThreadEvent => {
+                // Reload the viewer when the file being viewed has been changed externally
+                if let Ok(file) = self.file_changed_rx.try_recv() {
+                    if self.file.as_ref() == Some(&file) {
+                        self.load_viewer(Some(file));
+                    }
                 }
             }
             _ => (),
@@ -0,0 +1,42 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use atomicwrites::{AllowOverwrite, AtomicFile};
+use serde::{Deserialize, Serialize};
+
+use crate::fm::tabs::PanelTabsState;
+
+/// The state of the file manager that gets saved on exit, and restored on the next launch.
+///
+/// The panels are stored in their on-screen order, regardless of which one is hidden by the
+/// quick view.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub panels: Vec<PanelTabsState>,
+    pub panel_focus_position: usize,
+    pub quick_view: Option<usize>,
+    pub vertical: bool,
+    #[serde(default)]
+    pub miller: bool,
+}
+
+impl Session {
+    pub fn load(file: &Path) -> Option<Session> {
+        serde_json::from_reader(BufReader::new(File::open(file).ok()?)).ok()
+    }
+
+    pub fn save(&self, file: &Path) -> io::Result<()> {
+        AtomicFile::new(file, AllowOverwrite)
+            .write(|f| {
+                let mut writer = BufWriter::new(f);
+
+                serde_json::to_writer_pretty(&mut writer, self)?;
+
+                writer.flush()
+            })
+            .map_err(io::Error::from)
+    }
+}
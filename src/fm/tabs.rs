@@ -0,0 +1,353 @@
+use std::{
+    cell::RefCell,
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use ratatui::prelude::*;
+use serde::{Deserialize, Serialize};
+use termion::{event::*, raw::RawTerminal};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{Events, Inputs, PubSub},
+    component::{Component, Focus},
+    config::{Config, ListingMode},
+    fm::{
+        archive_mounter::ArchiveMounterCommand,
+        bookmarks::Bookmarks,
+        dir_sorts::DirSorts,
+        entry::{Entry, HiddenFiles, SortBy, SortDirs, SortOrder},
+        file_panel::FilePanel,
+        panel::{Panel, PanelComponent},
+    },
+    palette::Palette,
+    tilde_layout::tilde_layout,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabState {
+    pub cwd: PathBuf,
+    pub selected_file: Option<PathBuf>,
+    pub hidden_files: HiddenFiles,
+    pub file_filter: String,
+    pub sort_method: SortBy,
+    pub sort_order: SortOrder,
+    #[serde(default)]
+    pub sort_dirs: SortDirs,
+    pub tagged_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub listing_mode: ListingMode,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PanelTabsState {
+    pub tabs: Vec<TabState>,
+    pub active: usize,
+}
+
+pub struct Tabs {
+    palette: Rc<Palette>,
+    events_tx: Sender<Events>,
+    bookmarks: Rc<RefCell<Bookmarks>>,
+    dir_sorts: Rc<RefCell<DirSorts>>,
+    raw_output: Rc<RawTerminal<io::Stdout>>,
+    stop_inputs_tx: Sender<Inputs>,
+    stop_inputs_rx: Receiver<Inputs>,
+    pubsub_tx: Sender<PubSub>,
+    config: Rc<Config>,
+    archive_mounter_command_tx: Option<Sender<ArchiveMounterCommand>>,
+    tabs: Vec<FilePanel>,
+    active: usize,
+    focus: Focus,
+    tab_rects: Vec<Rect>,
+}
+
+impl Tabs {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        palette: &Rc<Palette>,
+        events_tx: &Sender<Events>,
+        bookmarks: &Rc<RefCell<Bookmarks>>,
+        dir_sorts: &Rc<RefCell<DirSorts>>,
+        raw_output: &Rc<RawTerminal<io::Stdout>>,
+        stop_inputs_tx: &Sender<Inputs>,
+        stop_inputs_rx: &Receiver<Inputs>,
+        pubsub_tx: Sender<PubSub>,
+        config: &Rc<Config>,
+        initial_path: &Path,
+        archive_mounter_command_tx: Option<Sender<ArchiveMounterCommand>>,
+        focus: Focus,
+        state: Option<&PanelTabsState>,
+        restore_cwd: bool,
+    ) -> Tabs {
+        let mut tabs = Tabs {
+            palette: Rc::clone(palette),
+            events_tx: events_tx.clone(),
+            bookmarks: Rc::clone(bookmarks),
+            dir_sorts: Rc::clone(dir_sorts),
+            raw_output: Rc::clone(raw_output),
+            stop_inputs_tx: stop_inputs_tx.clone(),
+            stop_inputs_rx: stop_inputs_rx.clone(),
+            pubsub_tx,
+            config: Rc::clone(config),
+            archive_mounter_command_tx,
+            tabs: Vec::new(),
+            active: 0,
+            focus,
+            tab_rects: Vec::new(),
+        };
+
+        match state {
+            Some(state) if !state.tabs.is_empty() => {
+                tabs.active = state.active.clamp(0, state.tabs.len() - 1);
+
+                for (i, tab_state) in state.tabs.iter().enumerate() {
+                    let mut tab = tabs.new_file_panel(
+                        &tab_state.cwd,
+                        match i == tabs.active {
+                            true => focus,
+                            false => Focus::Normal,
+                        },
+                    );
+
+                    tab.set_tab_state(tab_state);
+
+                    tabs.tabs.push(tab);
+                }
+
+                // Unless the whole session gets restored, the directory that fcd has been started
+                // from takes the place of the active tab
+                if !restore_cwd {
+                    tabs.tabs[tabs.active].chdir(initial_path, None);
+                }
+            }
+            _ => {
+                let tab = tabs.new_file_panel(initial_path, focus);
+
+                tabs.tabs.push(tab);
+            }
+        }
+
+        tabs
+    }
+
+    fn new_file_panel(&self, initial_path: &Path, focus: Focus) -> FilePanel {
+        FilePanel::new(
+            &self.palette,
+            &self.events_tx,
+            &self.bookmarks,
+            &self.dir_sorts,
+            &self.raw_output,
+            &self.stop_inputs_tx,
+            &self.stop_inputs_rx,
+            self.pubsub_tx.clone(),
+            &self.config,
+            initial_path,
+            self.archive_mounter_command_tx.clone(),
+            focus,
+        )
+    }
+
+    fn switch_tab(&mut self, new_active: usize) {
+        if new_active != self.active && new_active < self.tabs.len() {
+            self.tabs[self.active].change_focus(Focus::Normal);
+            self.active = new_active;
+            self.tabs[self.active].change_focus(self.focus);
+
+            if let Focus::Focused = self.focus {
+                self.pubsub_tx
+                    .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                    .unwrap();
+            }
+
+            self.pubsub_tx.send(PubSub::TabSwitched).unwrap();
+        }
+    }
+
+    fn new_tab(&mut self) {
+        let cwd = self.tabs[self.active]
+            .get_cwd()
+            .expect("BUG: The active tab has no working directory set");
+
+        let tab = self.new_file_panel(&cwd, Focus::Normal);
+
+        self.tabs.insert(self.active + 1, tab);
+        self.switch_tab(self.active + 1);
+    }
+
+    fn close_tab(&mut self) {
+        if self.tabs.len() > 1 {
+            self.tabs.remove(self.active);
+
+            self.active = self.active.clamp(0, self.tabs.len() - 1);
+            self.tabs[self.active].change_focus(self.focus);
+
+            if let Focus::Focused = self.focus {
+                self.pubsub_tx
+                    .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                    .unwrap();
+            }
+
+            // The archives that were open in the closed tab are not needed anymore
+            self.pubsub_tx.send(PubSub::TabSwitched).unwrap();
+        }
+    }
+
+    fn tab_label(&self, i: usize) -> String {
+        let cwd = self.tabs[i].get_shown_cwd().unwrap_or_default();
+
+        let name = match cwd.file_name() {
+            Some(file_name) => file_name.to_string_lossy().to_string(),
+            None => cwd.to_string_lossy().to_string(),
+        };
+
+        format!(" {}:{} ", i + 1, name)
+    }
+}
+
+impl Component for Tabs {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        match key {
+            Key::Alt('t') => self.new_tab(),
+            Key::Alt('w') => self.close_tab(),
+            Key::Alt('.') => self.switch_tab((self.active + 1) % self.tabs.len()),
+            Key::Alt(',') => self.switch_tab((self.active + self.tabs.len() - 1) % self.tabs.len()),
+            _ => return self.tabs[self.active].handle_key(key),
+        }
+
+        true
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        if let Some(i) = self
+            .tab_rects
+            .iter()
+            .position(|rect| rect.contains(mouse_position))
+        {
+            if let MouseButton::Left = button {
+                self.switch_tab(i);
+            }
+
+            return;
+        }
+
+        self.tabs[self.active].handle_mouse(button, mouse_position);
+    }
+
+    fn handle_pubsub(&mut self, event: &PubSub) {
+        for tab in self.tabs.iter_mut() {
+            tab.handle_pubsub(event);
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, focus: Focus) {
+        self.tab_rects.clear();
+
+        // The tab strip is shown only when there is more than one tab
+        if self.tabs.len() < 2 {
+            self.tabs[self.active].render(f, chunk, focus);
+            return;
+        }
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(*chunk);
+
+        // Give every tab the same maximum width, so that all the tabs fit in the strip
+        let max_width = ((sections[0].width as usize) / self.tabs.len()).max(1);
+
+        let mut x = sections[0].x;
+        let mut spans = Vec::new();
+        for i in 0..self.tabs.len() {
+            let label = tilde_layout(&self.tab_label(i), max_width);
+            let width = label.width() as u16;
+
+            self.tab_rects
+                .push(Rect::new(x, sections[0].y, width, sections[0].height));
+            x += width;
+
+            spans.push(Span::styled(
+                label,
+                match (i == self.active, focus) {
+                    (true, Focus::Focused) => self.palette.panel_reverse,
+                    (true, _) => self.palette.marked,
+                    (false, _) => self.palette.panel,
+                },
+            ));
+        }
+
+        f.render_widget(Line::from(spans).style(self.palette.panel), sections[0]);
+
+        self.tabs[self.active].render(f, &sections[1], focus);
+    }
+}
+
+impl Panel for Tabs {
+    fn change_focus(&mut self, focus: Focus) {
+        self.focus = focus;
+
+        self.tabs[self.active].change_focus(focus);
+    }
+
+    fn get_selected_entry(&self) -> Option<Entry> {
+        self.tabs[self.active].get_selected_entry()
+    }
+
+    fn get_cwd(&self) -> Option<PathBuf> {
+        self.tabs[self.active].get_cwd()
+    }
+
+    fn get_shown_cwd(&self) -> Option<PathBuf> {
+        self.tabs[self.active].get_shown_cwd()
+    }
+
+    fn get_old_cwd(&self) -> Option<PathBuf> {
+        self.tabs[self.active].get_old_cwd()
+    }
+
+    fn get_tagged_files(&self) -> Vec<Entry> {
+        self.tabs[self.active].get_tagged_files()
+    }
+
+    fn get_selected_files(&self) -> Vec<Entry> {
+        self.tabs[self.active].get_selected_files()
+    }
+
+    fn get_file_list(&self) -> Option<Vec<Entry>> {
+        self.tabs[self.active].get_file_list()
+    }
+
+    fn get_background_cwds(&self) -> Vec<PathBuf> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .filter(|(i, _tab)| *i != self.active)
+            .flat_map(|(_i, tab)| tab.get_cwd().into_iter().chain(tab.get_old_cwd()))
+            .collect()
+    }
+
+    fn get_tabs_state(&self) -> Option<PanelTabsState> {
+        Some(PanelTabsState {
+            tabs: self.tabs.iter().map(|tab| tab.get_tab_state()).collect(),
+            active: self.active,
+        })
+    }
+
+    fn chdir(&mut self, cwd: &Path, selected_file: Option<&Path>) {
+        self.tabs[self.active].chdir(cwd, selected_file);
+    }
+
+    fn reload(&mut self, selected_file: Option<&Path>) {
+        self.tabs[self.active].reload(selected_file);
+    }
+
+    fn set_tagged_files(&mut self, files: &[PathBuf]) {
+        self.tabs[self.active].set_tagged_files(files);
+    }
+}
+
+impl PanelComponent for Tabs {}
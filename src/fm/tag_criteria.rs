@@ -0,0 +1,456 @@
+use std::{
+    collections::HashMap,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use chrono::{Local, NaiveDate};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{fm::entry::Entry, fnmatch, stat::filemode};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileType {
+    #[default]
+    Any,
+    File,
+    Directory,
+    Symlink,
+}
+
+/// The criteria of the tag dialog, kept as typed by the user, so that they can be saved as a
+/// tag set and shown again in the dialog.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagCriteria {
+    pub name: String,
+    pub size: String,
+    pub mtime: String,
+    pub owner: String,
+    pub permissions: String,
+    pub file_type: FileType,
+    pub newer: bool,
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Range<T> {
+    Eq(T),
+    Lt(T),
+    Le(T),
+    Gt(T),
+    Ge(T),
+    Between(T, T),
+}
+
+impl<T: PartialOrd + Copy> Range<T> {
+    fn contains(&self, value: T) -> bool {
+        match *self {
+            Range::Eq(a) => value == a,
+            Range::Lt(a) => value < a,
+            Range::Le(a) => value <= a,
+            Range::Gt(a) => value > a,
+            Range::Ge(a) => value >= a,
+            Range::Between(a, b) if a <= b => (value >= a) && (value <= b),
+            Range::Between(a, b) => (value >= b) && (value <= a),
+        }
+    }
+}
+
+/// Parse a range of the form `VALUE`, `<VALUE`, `<=VALUE`, `>VALUE`, `>=VALUE` or
+/// `VALUE..VALUE`
+fn parse_range<T>(s: &str, parse_value: impl Fn(&str) -> Option<T>) -> Option<Range<T>> {
+    let s = s.trim();
+
+    if let Some(value) = s.strip_prefix("<=") {
+        parse_value(value.trim()).map(Range::Le)
+    } else if let Some(value) = s.strip_prefix(">=") {
+        parse_value(value.trim()).map(Range::Ge)
+    } else if let Some(value) = s.strip_prefix('<') {
+        parse_value(value.trim()).map(Range::Lt)
+    } else if let Some(value) = s.strip_prefix('>') {
+        parse_value(value.trim()).map(Range::Gt)
+    } else if let Some((a, b)) = s.split_once("..") {
+        parse_value(a.trim())
+            .zip(parse_value(b.trim()))
+            .map(|(a, b)| Range::Between(a, b))
+    } else {
+        parse_value(s).map(Range::Eq)
+    }
+}
+
+/// Split a string like `1.5M` into its number and its (lowercase) unit
+fn split_unit(s: &str) -> Option<(f64, String)> {
+    let i = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+
+    let value = s[..i].parse::<f64>().ok()?;
+
+    Some((value, s[i..].trim().to_lowercase()))
+}
+
+/// Parse a size in bytes, with an optional binary suffix (`K`, `M`, `G` or `T`)
+fn parse_size(s: &str) -> Option<u64> {
+    let (value, unit) = split_unit(s)?;
+
+    let multiplier: u64 = match unit.trim_end_matches("ib").trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => return None,
+    };
+
+    Some((value * (multiplier as f64)).round() as u64)
+}
+
+/// Parse an age, either as a duration with a unit (`s`, `m`, `h`, `d`, `w` or `y`, defaulting
+/// to days), or as the time elapsed since a date in the `YYYY-MM-DD` format
+fn parse_age(s: &str, now: SystemTime) -> Option<Duration> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let date = date
+            .and_hms_opt(0, 0, 0)?
+            .and_local_timezone(Local)
+            .earliest()?;
+
+        return Some(
+            now.duration_since(SystemTime::from(date))
+                .unwrap_or(Duration::ZERO),
+        );
+    }
+
+    let (value, unit) = split_unit(s)?;
+
+    let seconds: f64 = match unit.as_str() {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 60.0 * 60.0,
+        "" | "d" => 24.0 * 60.0 * 60.0,
+        "w" => 7.0 * 24.0 * 60.0 * 60.0,
+        "y" => 365.0 * 24.0 * 60.0 * 60.0,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(value * seconds).ok()
+}
+
+#[derive(Debug, Clone)]
+enum Permissions {
+    Mode(u32),
+    Glob(Regex),
+}
+
+/// Parse permissions, either in octal (like `644` or `4755`), or as a glob matched against the
+/// `ls -l` permissions (like `-rw*`)
+fn parse_permissions(s: &str) -> Option<Permissions> {
+    if (3..=4).contains(&s.len()) && s.chars().all(|c| c.is_digit(8)) {
+        u32::from_str_radix(s, 8).ok().map(Permissions::Mode)
+    } else {
+        build_glob(s).map(Permissions::Glob)
+    }
+}
+
+fn build_glob(pattern: &str) -> Option<Regex> {
+    Regex::new(&fnmatch::translate(pattern)).ok()
+}
+
+/// Return the entries matching all of the criteria (or none of them, if the criteria are
+/// inverted).
+///
+/// `other_panel` is the cwd and the file list of the other panel, needed to match the files
+/// newer than the file with the same relative path in the other panel.
+pub fn matching_files(
+    criteria: &TagCriteria,
+    cwd: &Path,
+    entries: &[Entry],
+    other_panel: Option<(&Path, &[Entry])>,
+) -> Result<Vec<PathBuf>, String> {
+    let now = SystemTime::now();
+
+    let name_re = match criteria.name.trim() {
+        "" => None,
+        name => Some(
+            RegexBuilder::new(name)
+                .build()
+                .map_err(|_| String::from("Invalid name regular expression"))?,
+        ),
+    };
+
+    let size_range = match criteria.size.trim() {
+        "" => None,
+        size => Some(parse_range(size, parse_size).ok_or_else(|| String::from("Invalid size"))?),
+    };
+
+    // A single age means "modified within the last ..."
+    let age_range = match criteria.mtime.trim() {
+        "" => None,
+        mtime => match parse_range(mtime, |s| parse_age(s, now)) {
+            Some(Range::Eq(age)) => Some(Range::Le(age)),
+            Some(range) => Some(range),
+            None => return Err(String::from("Invalid modification time")),
+        },
+    };
+
+    let (owner_re, group_re) = match criteria.owner.trim() {
+        "" => (None, None),
+        owner => {
+            let (owner, group) = match owner.split_once(':') {
+                Some((owner, group)) => (owner, Some(group)),
+                None => (owner, None),
+            };
+
+            let invalid_owner = || String::from("Invalid owner");
+
+            (
+                Some(build_glob(owner).ok_or_else(invalid_owner)?),
+                match group {
+                    Some(group) => Some(build_glob(group).ok_or_else(invalid_owner)?),
+                    None => None,
+                },
+            )
+        }
+    };
+
+    let permissions = match criteria.permissions.trim() {
+        "" => None,
+        permissions => Some(
+            parse_permissions(permissions).ok_or_else(|| String::from("Invalid permissions"))?,
+        ),
+    };
+
+    let other_entries: Option<HashMap<&Path, &Entry>> = other_panel.map(|(other_cwd, other)| {
+        other
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .file
+                    .strip_prefix(other_cwd)
+                    .ok()
+                    .map(|rel_file| (rel_file, entry))
+            })
+            .collect()
+    });
+
+    let is_match = |entry: &Entry| -> bool {
+        if let Some(re) = &name_re {
+            if !re.is_match(&entry.file_name) {
+                return false;
+            }
+        }
+
+        if let Some(range) = &size_range {
+            if entry.stat.is_dir() || !range.contains(entry.stat.len()) {
+                return false;
+            }
+        }
+
+        if let Some(range) = &age_range {
+            match entry.lstat.modified() {
+                Ok(mtime) => {
+                    if !range.contains(now.duration_since(mtime).unwrap_or(Duration::ZERO)) {
+                        return false;
+                    }
+                }
+                Err(_) => return false,
+            }
+        }
+
+        if let Some(re) = &owner_re {
+            if !re.is_match(&entry.owner) {
+                return false;
+            }
+        }
+
+        if let Some(re) = &group_re {
+            if !re.is_match(&entry.group) {
+                return false;
+            }
+        }
+
+        match &permissions {
+            Some(Permissions::Mode(mode)) if (entry.lstat.mode() & 0o7777) != *mode => {
+                return false;
+            }
+            Some(Permissions::Glob(re)) if !re.is_match(&filemode(entry.lstat.mode())) => {
+                return false;
+            }
+            _ => (),
+        }
+
+        let type_matches = match criteria.file_type {
+            FileType::Any => true,
+            FileType::File => entry.lstat.is_file(),
+            FileType::Directory => entry.lstat.is_dir(),
+            FileType::Symlink => entry.lstat.is_symlink(),
+        };
+
+        if !type_matches {
+            return false;
+        }
+
+        if criteria.newer {
+            let other_entry = entry.file.strip_prefix(cwd).ok().and_then(|rel_file| {
+                other_entries
+                    .as_ref()
+                    .and_then(|other_entries| other_entries.get(rel_file))
+            });
+
+            let newer = other_entry.is_some_and(|other_entry| {
+                match (entry.stat.modified(), other_entry.stat.modified()) {
+                    (Ok(mtime), Ok(other_mtime)) => mtime > other_mtime,
+                    _ => false,
+                }
+            });
+
+            if !newer {
+                return false;
+            }
+        }
+
+        true
+    };
+
+    Ok(entries
+        .iter()
+        .filter(|entry| is_match(entry) != criteria.invert)
+        .map(|entry| entry.file.clone())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_u64(s: &str) -> Option<u64> {
+        s.parse().ok()
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert!(matches!(parse_range("10", parse_u64), Some(Range::Eq(10))));
+        assert!(matches!(parse_range("<10", parse_u64), Some(Range::Lt(10))));
+        assert!(matches!(
+            parse_range("<= 10", parse_u64),
+            Some(Range::Le(10))
+        ));
+        assert!(matches!(parse_range(">10", parse_u64), Some(Range::Gt(10))));
+        assert!(matches!(
+            parse_range(" >=10 ", parse_u64),
+            Some(Range::Ge(10))
+        ));
+        assert!(matches!(
+            parse_range("1..5", parse_u64),
+            Some(Range::Between(1, 5))
+        ));
+        assert!(parse_range("abc", parse_u64).is_none());
+        assert!(parse_range("1..", parse_u64).is_none());
+        assert!(parse_range("<", parse_u64).is_none());
+    }
+
+    #[test]
+    fn test_range_contains() {
+        assert!(Range::Eq(5).contains(5));
+        assert!(!Range::Lt(5).contains(5));
+        assert!(Range::Le(5).contains(5));
+        assert!(!Range::Gt(5).contains(5));
+        assert!(Range::Ge(5).contains(5));
+        assert!(Range::Between(1, 5).contains(1));
+        assert!(Range::Between(1, 5).contains(5));
+        assert!(!Range::Between(1, 5).contains(6));
+
+        // The bounds can be given in any order
+        assert!(Range::Between(5, 1).contains(3));
+        assert!(!Range::Between(5, 1).contains(0));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size("100"), Some(100));
+        assert_eq!(parse_size("100b"), Some(100));
+        assert_eq!(parse_size("1K"), Some(1024));
+        assert_eq!(parse_size("1kb"), Some(1024));
+        assert_eq!(parse_size("1KiB"), Some(1024));
+        assert_eq!(parse_size("1.5M"), Some(3 << 19));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("1T"), Some(1 << 40));
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("1X"), None);
+        assert_eq!(parse_size("-1"), None);
+    }
+
+    #[test]
+    fn test_parse_age() {
+        let now = SystemTime::now();
+
+        assert_eq!(parse_age("30s", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_age("2m", now), Some(Duration::from_secs(2 * 60)));
+        assert_eq!(parse_age("1.5h", now), Some(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_age("3", now), Some(Duration::from_secs(3 * 86400)));
+        assert_eq!(parse_age("3d", now), Some(Duration::from_secs(3 * 86400)));
+        assert_eq!(parse_age("1w", now), Some(Duration::from_secs(7 * 86400)));
+        assert_eq!(parse_age("1y", now), Some(Duration::from_secs(365 * 86400)));
+        assert_eq!(parse_age("1q", now), None);
+        assert_eq!(parse_age("d", now), None);
+
+        // Too big for a Duration
+        assert_eq!(parse_age("999999999999y", now), None);
+        assert_eq!(parse_age(&"9".repeat(400), now), None);
+    }
+
+    #[test]
+    fn test_parse_age_date() {
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap();
+        let now = SystemTime::from(date) + Duration::from_secs(86400);
+
+        assert_eq!(
+            parse_age("2020-01-01", now),
+            Some(Duration::from_secs(86400))
+        );
+
+        // A date in the future is 0 seconds ago
+        assert_eq!(parse_age("2020-01-03", now), Some(Duration::ZERO));
+        assert_eq!(parse_age("2020-13-01", now), None);
+    }
+
+    #[test]
+    fn test_parse_permissions() {
+        assert!(matches!(
+            parse_permissions("644"),
+            Some(Permissions::Mode(0o644))
+        ));
+        assert!(matches!(
+            parse_permissions("4755"),
+            Some(Permissions::Mode(0o4755))
+        ));
+
+        // Not octal, or too long, so it's a glob
+        assert!(matches!(
+            parse_permissions("648"),
+            Some(Permissions::Glob(_))
+        ));
+        assert!(matches!(
+            parse_permissions("06444"),
+            Some(Permissions::Glob(_))
+        ));
+
+        match parse_permissions("-rw*") {
+            Some(Permissions::Glob(re)) => {
+                assert!(re.is_match("-rw-r--r--"));
+                assert!(!re.is_match("drwxr-xr-x"));
+            }
+            _ => panic!("-rw* is not a glob"),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use atomicwrites::{AllowOverwrite, AtomicFile};
+
+use crate::fm::tag_criteria::TagCriteria;
+
+/// The tag criteria saved by name from the tag dialog, remembered across launches.
+#[derive(Debug)]
+pub struct TagSets {
+    file: Option<PathBuf>,
+    data: BTreeMap<String, TagCriteria>,
+}
+
+impl TagSets {
+    pub fn new(file: Option<&Path>) -> TagSets {
+        let data = match file.map(File::open) {
+            Some(Ok(f)) => serde_json::from_reader(BufReader::new(f)).unwrap_or_default(),
+            _ => BTreeMap::new(),
+        };
+
+        TagSets {
+            file: file.map(PathBuf::from),
+            data,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<TagCriteria> {
+        self.data.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.data.keys().cloned().collect()
+    }
+
+    pub fn insert(&mut self, name: &str, criteria: &TagCriteria) {
+        self.data.insert(String::from(name), criteria.clone());
+        self.update_file();
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if self.data.remove(name).is_some() {
+            self.update_file();
+        }
+    }
+
+    fn update_file(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = AtomicFile::new(file, AllowOverwrite).write(|f| {
+                let mut writer = BufWriter::new(f);
+
+                serde_json::to_writer(&mut writer, &self.data)?;
+
+                writer.flush()
+            });
+        }
+    }
+}
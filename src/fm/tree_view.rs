@@ -0,0 +1,684 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crossbeam_channel::Sender;
+use ratatui::{prelude::*, widgets::*};
+use termion::event::*;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{PubSub, MIDDLE_BORDER_SET},
+    component::{Component, Focus},
+    fm::{
+        app::{human_readable_size, LABELS},
+        entry::{
+            common_parent, filter_file_list, get_file_list, sort_entries, Entry, HiddenFiles,
+            SortBy, SortDirs, SortOrder,
+        },
+        panel::{Panel, PanelComponent},
+    },
+    palette::Palette,
+    tilde_layout::tilde_layout,
+};
+
+#[derive(Debug, Clone)]
+struct TreeNode {
+    entry: Entry,
+    depth: usize,
+    expanded: bool,
+}
+
+#[derive(Debug)]
+pub struct TreeView {
+    palette: Rc<Palette>,
+    pubsub_tx: Sender<PubSub>,
+    root: PathBuf,
+    hidden_files: HiddenFiles,
+    sort_method: SortBy,
+    sort_order: SortOrder,
+    sort_dirs: SortDirs,
+    nodes: Vec<TreeNode>,
+    tagged_files: Vec<Entry>,
+    cursor_position: usize,
+    first_line: usize,
+    focus: Focus,
+    rect: Rect,
+}
+
+impl TreeView {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        palette: &Rc<Palette>,
+        pubsub_tx: Sender<PubSub>,
+        root: &Path,
+        selected_file: Option<&Path>,
+        hidden_files: HiddenFiles,
+        sort_method: SortBy,
+        sort_order: SortOrder,
+        sort_dirs: SortDirs,
+        focus: Focus,
+    ) -> TreeView {
+        let mut tree_view = TreeView {
+            palette: Rc::clone(palette),
+            pubsub_tx,
+            root: PathBuf::from(root),
+            hidden_files,
+            sort_method,
+            sort_order,
+            sort_dirs,
+            nodes: Vec::new(),
+            tagged_files: Vec::new(),
+            cursor_position: 0,
+            first_line: 0,
+            focus,
+            rect: Rect::default(),
+        };
+
+        tree_view.nodes = tree_view.list_dir(root, 0);
+        tree_view.select_file(selected_file);
+
+        tree_view
+    }
+
+    fn list_dir(&self, dir: &Path, depth: usize) -> Vec<TreeNode> {
+        let file_list = get_file_list(dir, &self.palette, None).unwrap_or_default();
+        let mut file_list = filter_file_list(&file_list, self.hidden_files, "");
+
+        file_list.sort_unstable_by(|a, b| {
+            sort_entries(a, b, self.sort_method, self.sort_order, self.sort_dirs)
+        });
+
+        file_list
+            .into_iter()
+            .map(|entry| TreeNode {
+                entry,
+                depth,
+                expanded: false,
+            })
+            .collect()
+    }
+
+    /// Rebuild the tree from scratch, expanding again the directories in `expanded`.
+    fn build_tree(&self, dir: &Path, depth: usize, expanded: &HashSet<PathBuf>) -> Vec<TreeNode> {
+        let mut nodes = Vec::new();
+
+        for mut node in self.list_dir(dir, depth) {
+            let file = node.entry.file.clone();
+
+            node.expanded = node.entry.stat.is_dir() && expanded.contains(&file);
+
+            let is_expanded = node.expanded;
+
+            nodes.push(node);
+
+            if is_expanded {
+                nodes.extend(self.build_tree(&file, depth + 1, expanded));
+            }
+        }
+
+        nodes
+    }
+
+    fn select_file(&mut self, selected_file: Option<&Path>) {
+        self.cursor_position = self.clamp_cursor(match selected_file {
+            Some(file) => self
+                .nodes
+                .iter()
+                .position(|node| node.entry.file == file)
+                .unwrap_or(self.cursor_position),
+            None => 0,
+        });
+
+        self.clamp_first_line();
+
+        if let Focus::Focused = self.focus {
+            self.pubsub_tx
+                .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                .unwrap();
+        }
+    }
+
+    /// The index just past the last descendant of the node at `i`
+    fn subtree_end(&self, i: usize) -> usize {
+        let depth = self.nodes[i].depth;
+
+        self.nodes
+            .iter()
+            .skip(i + 1)
+            .position(|node| node.depth <= depth)
+            .map(|pos| i + 1 + pos)
+            .unwrap_or(self.nodes.len())
+    }
+
+    fn parent_index(&self, i: usize) -> Option<usize> {
+        let depth = self.nodes[i].depth.checked_sub(1)?;
+
+        self.nodes[..i].iter().rposition(|node| node.depth == depth)
+    }
+
+    fn expand(&mut self, i: usize) {
+        let node = &self.nodes[i];
+
+        if node.entry.stat.is_dir() && !node.expanded {
+            let children = self.list_dir(&node.entry.file, node.depth + 1);
+
+            self.nodes[i].expanded = true;
+            self.nodes.splice((i + 1)..(i + 1), children);
+        }
+    }
+
+    fn collapse(&mut self, i: usize) {
+        if self.nodes[i].expanded {
+            let end = self.subtree_end(i);
+
+            self.nodes[i].expanded = false;
+            self.nodes.drain((i + 1)..end);
+
+            if self.cursor_position > i {
+                self.move_cursor(i);
+            }
+
+            self.clamp_first_line();
+        }
+    }
+
+    /// Make the parent of the root directory the new root, keeping the current tree expanded
+    /// below it
+    fn root_up(&mut self) {
+        if let Some(parent) = self.root.parent().map(PathBuf::from) {
+            let selected_file = self.get_selected_file();
+            let old_root = std::mem::replace(&mut self.root, parent);
+
+            let mut old_nodes = std::mem::take(&mut self.nodes);
+
+            for node in old_nodes.iter_mut() {
+                node.depth += 1;
+            }
+
+            self.nodes = self.list_dir(&self.root, 0);
+
+            match self
+                .nodes
+                .iter()
+                .position(|node| node.entry.file == old_root)
+            {
+                Some(i) => {
+                    self.nodes[i].expanded = true;
+                    self.nodes.splice((i + 1)..(i + 1), old_nodes);
+
+                    self.select_file(selected_file.as_deref());
+                }
+                None => self.select_file(Some(&old_root)),
+            }
+        }
+    }
+
+    fn get_selected_file(&self) -> Option<PathBuf> {
+        self.nodes
+            .get(self.cursor_position)
+            .map(|node| node.entry.file.clone())
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.nodes.len().saturating_sub(1))
+    }
+
+    fn clamp_first_line(&mut self) {
+        if (self.first_line + (self.rect.height as usize)) > self.nodes.len() {
+            self.first_line = self.nodes.len().saturating_sub(self.rect.height as usize);
+        }
+    }
+
+    fn move_cursor(&mut self, new_cursor_pos: usize) {
+        let old_cursor_position = self.cursor_position;
+
+        self.cursor_position = self.clamp_cursor(new_cursor_pos);
+
+        if self.cursor_position != old_cursor_position {
+            if let Focus::Focused = self.focus {
+                self.pubsub_tx
+                    .send(PubSub::SelectedEntry(self.get_selected_entry()))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn tag_toggle(&mut self) {
+        if let Some(node) = self.nodes.get(self.cursor_position) {
+            if let Some(i) = self.tagged_files.iter().position(|x| *x == node.entry) {
+                self.tagged_files.swap_remove(i);
+            } else {
+                self.tagged_files.push(node.entry.clone());
+            }
+        }
+    }
+}
+
+impl Component for TreeView {
+    fn handle_key(&mut self, key: &Key) -> bool {
+        let mut key_handled = true;
+
+        match key {
+            Key::Up | Key::Char('k') => self.move_cursor(self.cursor_position.saturating_sub(1)),
+            Key::Down | Key::Char('j') => self.move_cursor(self.cursor_position.saturating_add(1)),
+            Key::Home | Key::CtrlHome | Key::Char('g') => self.move_cursor(0),
+            Key::End | Key::CtrlEnd | Key::Char('G') => self.move_cursor(self.nodes.len()),
+            Key::PageUp | Key::Ctrl('b') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.first_line = self.first_line.saturating_sub(rect_height);
+                self.move_cursor(self.cursor_position.saturating_sub(rect_height));
+            }
+            Key::PageDown | Key::Ctrl('f') => {
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                self.first_line = self.first_line.saturating_add(rect_height);
+                self.clamp_first_line();
+                self.move_cursor(self.cursor_position.saturating_add(rect_height));
+            }
+            Key::Right | Key::Char('l') => {
+                if let Some(node) = self.nodes.get(self.cursor_position) {
+                    match node.expanded {
+                        true => self.move_cursor(self.cursor_position + 1),
+                        false => self.expand(self.cursor_position),
+                    }
+                }
+            }
+            Key::Char('\n') => {
+                if let Some(node) = self.nodes.get(self.cursor_position) {
+                    match node.expanded {
+                        true => self.collapse(self.cursor_position),
+                        false => self.expand(self.cursor_position),
+                    }
+                }
+            }
+            Key::Left | Key::Char('h') => {
+                if let Some(node) = self.nodes.get(self.cursor_position) {
+                    if node.expanded {
+                        self.collapse(self.cursor_position);
+                    } else {
+                        match self.parent_index(self.cursor_position) {
+                            Some(i) => self.move_cursor(i),
+                            None => self.root_up(),
+                        }
+                    }
+                } else {
+                    self.root_up();
+                }
+            }
+            Key::Backspace => {
+                self.hidden_files = match self.hidden_files {
+                    HiddenFiles::Show => HiddenFiles::Hide,
+                    HiddenFiles::Hide => HiddenFiles::Show,
+                };
+
+                self.reload(self.get_selected_file().as_deref());
+            }
+            Key::Insert | Key::Char(' ') => {
+                self.tag_toggle();
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('t') => {
+                if let Some(node) = self.nodes.get(self.cursor_position) {
+                    if !self.tagged_files.contains(&node.entry) {
+                        self.tagged_files.push(node.entry.clone());
+                    }
+                }
+
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('u') => {
+                if let Some(node) = self.nodes.get(self.cursor_position) {
+                    if let Some(i) = self.tagged_files.iter().position(|x| *x == node.entry) {
+                        self.tagged_files.swap_remove(i);
+                    }
+                }
+
+                self.move_cursor(self.cursor_position.saturating_add(1));
+            }
+            Key::Char('U') => self.tagged_files.clear(),
+            Key::Ctrl('r') => self.pubsub_tx.send(PubSub::Reload).unwrap(),
+            Key::F(8) | Key::Char('8') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    let question = match selected_files.len() {
+                        1 => format!("Delete {}?", selected_files[0].file_name),
+                        n => format!("Delete {} files/directories?", n),
+                    };
+
+                    self.pubsub_tx
+                        .send(PubSub::Question(
+                            String::from("Delete"),
+                            question,
+                            Box::new(PubSub::Rm(
+                                common_parent(&selected_files).unwrap_or_else(|| self.root.clone()),
+                                selected_files,
+                            )),
+                        ))
+                        .unwrap();
+                }
+            }
+            Key::F(5) | Key::Char('5') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    self.pubsub_tx
+                        .send(PubSub::Cp(
+                            common_parent(&selected_files).unwrap_or_else(|| self.root.clone()),
+                            selected_files,
+                        ))
+                        .unwrap();
+                }
+            }
+            Key::F(6) | Key::Char('6') => {
+                let selected_files = self.get_selected_files();
+
+                if !selected_files.is_empty() {
+                    self.pubsub_tx
+                        .send(PubSub::Mv(
+                            common_parent(&selected_files).unwrap_or_else(|| self.root.clone()),
+                            selected_files,
+                        ))
+                        .unwrap();
+                }
+            }
+            _ => key_handled = false,
+        }
+
+        key_handled
+    }
+
+    fn handle_mouse(&mut self, button: MouseButton, mouse_position: Position) {
+        match button {
+            MouseButton::Left | MouseButton::Right if self.rect.contains(mouse_position) => {
+                let new_cursor_position =
+                    self.first_line + ((mouse_position.y - self.rect.y) as usize);
+
+                if new_cursor_position < self.nodes.len() {
+                    self.move_cursor(new_cursor_position);
+
+                    if let MouseButton::Right = button {
+                        self.tag_toggle();
+                    }
+                }
+            }
+            MouseButton::WheelUp => {
+                self.first_line = self.first_line.saturating_sub(1);
+
+                let rect_height = (self.rect.height as usize).saturating_sub(1);
+
+                if (self.cursor_position - self.first_line) > rect_height {
+                    self.move_cursor(self.cursor_position.saturating_sub(1));
+                }
+            }
+            MouseButton::WheelDown => {
+                self.first_line = self.first_line.saturating_add(1);
+                self.clamp_first_line();
+
+                if self.first_line > self.cursor_position {
+                    self.move_cursor(self.first_line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_pubsub(&mut self, event: &PubSub) {
+        if let PubSub::Reload = event {
+            self.reload(self.get_selected_file().as_deref());
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, chunk: &Rect, focus: Focus) {
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(*chunk);
+
+        let upper_block = Block::default()
+            .title_top(
+                Line::from(vec![
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                    Span::styled(
+                        tilde_layout(
+                            &format!(" (Tree) {} ", self.root.to_string_lossy()),
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        match focus {
+                            Focus::Focused => self.palette.panel_reverse,
+                            _ => self.palette.panel,
+                        },
+                    ),
+                    Span::raw(symbols::line::NORMAL.horizontal),
+                ])
+                .left_aligned(),
+            )
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .style(self.palette.panel);
+
+        let upper_inner = upper_block.inner(sections[0]);
+        let upper_height = (upper_inner.height as usize).saturating_sub(1);
+
+        self.rect = upper_inner;
+        self.clamp_first_line();
+
+        if self.first_line > self.cursor_position {
+            self.first_line = self.cursor_position;
+        }
+
+        if (self.cursor_position - self.first_line) > upper_height {
+            self.first_line = self.cursor_position.saturating_sub(upper_height);
+        }
+
+        f.render_widget(upper_block, sections[0]);
+
+        let items: Vec<ListItem> = self
+            .nodes
+            .iter()
+            .skip(self.first_line)
+            .take(upper_inner.height.into())
+            .enumerate()
+            .map(|(i, node)| {
+                let entry = &node.entry;
+
+                let marker = match (entry.stat.is_dir(), node.expanded) {
+                    (true, true) => "\u{25be} ",
+                    (true, false) => "\u{25b8} ",
+                    (false, _) => "  ",
+                };
+
+                let prefix = format!("{}{}", "  ".repeat(node.depth), marker);
+
+                let filename_max_width = (upper_inner.width as usize)
+                    .saturating_sub(prefix.width())
+                    .saturating_sub(entry.shown_size.width())
+                    .saturating_sub(1);
+
+                let is_selected = self.first_line + i == self.cursor_position;
+
+                let filename = if is_selected && !matches!(focus, Focus::Focused) {
+                    tilde_layout(
+                        &std::iter::once('\u{2192}')
+                            .chain(entry.label.chars().skip(1))
+                            .collect::<String>(),
+                        filename_max_width,
+                    )
+                } else {
+                    tilde_layout(&entry.label, filename_max_width)
+                };
+
+                let filename_width = filename.width();
+
+                Span::styled(
+                    format!(
+                        "{}{}{:width$} {}",
+                        &prefix,
+                        &filename,
+                        "",
+                        &entry.shown_size,
+                        width = filename_max_width.saturating_sub(filename_width)
+                    ),
+                    match (
+                        self.tagged_files.contains(entry),
+                        is_selected,
+                        matches!(focus, Focus::Focused),
+                    ) {
+                        (true, true, true) => self.palette.markselect,
+                        (true, true, false) => self.palette.marked,
+                        (true, false, _) => self.palette.marked,
+                        (false, true, true) => self.palette.selected,
+                        (false, _, _) => entry.style,
+                    },
+                )
+                .into()
+            })
+            .collect();
+
+        let items = List::new(items).highlight_style(match focus {
+            Focus::Focused => self.palette.selected_bg,
+            _ => Style::default(),
+        });
+
+        let mut state = ListState::default();
+        state.select(Some(self.cursor_position - self.first_line));
+
+        f.render_stateful_widget(items, upper_inner, &mut state);
+
+        let lower_block = Block::default()
+            .title_top(
+                Line::from(match self.tagged_files.is_empty() {
+                    true => Span::raw(symbols::line::NORMAL.horizontal),
+                    false => Span::styled(
+                        tilde_layout(
+                            &format!(
+                                " {} in {} file{} ",
+                                human_readable_size(
+                                    self.tagged_files
+                                        .iter()
+                                        .filter(|entry| !entry.stat.is_dir())
+                                        .filter_map(|entry| entry.size)
+                                        .sum()
+                                ),
+                                self.tagged_files.len(),
+                                if self.tagged_files.len() == 1 {
+                                    ""
+                                } else {
+                                    "s"
+                                }
+                            ),
+                            chunk.width.saturating_sub(4).into(),
+                        ),
+                        self.palette.marked,
+                    ),
+                })
+                .centered(),
+            )
+            .borders(Borders::ALL)
+            .border_set(MIDDLE_BORDER_SET)
+            .style(self.palette.panel);
+
+        let lower_inner = lower_block.inner(sections[1]);
+
+        f.render_widget(lower_block, sections[1]);
+
+        if let Some(node) = self.nodes.get(self.cursor_position) {
+            f.render_widget(
+                Block::new()
+                    .title_top(
+                        Line::from(Span::raw(tilde_layout(
+                            &node.entry.details,
+                            lower_inner.width.into(),
+                        )))
+                        .left_aligned(),
+                    )
+                    .style(self.palette.panel),
+                lower_inner,
+            );
+        }
+    }
+}
+
+impl Panel for TreeView {
+    fn change_focus(&mut self, focus: Focus) {
+        self.focus = focus;
+
+        if let Focus::Focused = focus {
+            self.pubsub_tx
+                .send(PubSub::ButtonLabels(
+                    LABELS.iter().map(|&label| String::from(label)).collect(),
+                ))
+                .unwrap();
+        }
+    }
+
+    fn get_selected_entry(&self) -> Option<Entry> {
+        self.nodes
+            .get(self.cursor_position)
+            .map(|node| node.entry.clone())
+    }
+
+    fn get_cwd(&self) -> Option<PathBuf> {
+        // The working directory is the one containing the selected entry
+        Some(
+            self.nodes
+                .get(self.cursor_position)
+                .and_then(|node| node.entry.file.parent())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| self.root.clone()),
+        )
+    }
+
+    fn get_tagged_files(&self) -> Vec<Entry> {
+        let mut tagged_files: Vec<Entry> = self
+            .tagged_files
+            .iter()
+            .filter(|entry| {
+                // The files inside a tagged directory are already part of the selection
+                !self.tagged_files.iter().any(|dir| {
+                    dir.file != entry.file && dir.stat.is_dir() && entry.file.starts_with(&dir.file)
+                })
+            })
+            .cloned()
+            .collect();
+
+        tagged_files.sort_unstable_by(|a, b| a.file.cmp(&b.file));
+
+        tagged_files
+    }
+
+    fn get_selected_files(&self) -> Vec<Entry> {
+        match self.tagged_files.is_empty() {
+            true => self.get_selected_entry().into_iter().collect(),
+            false => self.get_tagged_files(),
+        }
+    }
+
+    fn reload(&mut self, selected_file: Option<&Path>) {
+        if let Some(root) = self.root.ancestors().find(|d| d.is_dir()) {
+            self.root = PathBuf::from(root);
+        }
+
+        let expanded: HashSet<PathBuf> = self
+            .nodes
+            .iter()
+            .filter(|node| node.expanded)
+            .map(|node| node.entry.file.clone())
+            .collect();
+
+        self.nodes = self.build_tree(&self.root, 0, &expanded);
+
+        // The tagged files may be in collapsed directories, so check them on the file system
+        self.tagged_files
+            .retain(|entry| fs::symlink_metadata(&entry.file).is_ok());
+
+        self.select_file(selected_file);
+    }
+}
+
+impl PanelComponent for TreeView {}
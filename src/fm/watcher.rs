@@ -0,0 +1,100 @@
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+
+// How often the watcher thread checks for new events
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// A change gets notified once the watched path has been quiet for DEBOUNCE, or at most MAX_DELAY
+// after its first event, so that directories that never stop changing (like build directories)
+// still get refreshed, but only once in a while
+const DEBOUNCE: Duration = Duration::from_millis(300);
+const MAX_DELAY: Duration = Duration::from_millis(2000);
+
+fn watch_mask() -> WatchMask {
+    WatchMask::CREATE
+        | WatchMask::DELETE
+        | WatchMask::MODIFY
+        | WatchMask::ATTRIB
+        | WatchMask::MOVED_FROM
+        | WatchMask::MOVED_TO
+        | WatchMask::DELETE_SELF
+        | WatchMask::MOVE_SELF
+}
+
+/// Start a thread that watches a path with inotify, and calls `on_change` when it changes.
+///
+/// The path to watch (or `None` to stop watching) is sent through the returned channel, and the
+/// thread exits when the channel gets dropped. Returns `None` if inotify is not available.
+pub fn start<F>(on_change: F) -> Option<Sender<Option<PathBuf>>>
+where
+    F: Fn(&Path) + Send + 'static,
+{
+    let mut inotify = Inotify::init().ok()?;
+    let (watch_tx, watch_rx) = crossbeam_channel::unbounded();
+
+    thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        let mut watched: Option<(PathBuf, WatchDescriptor)> = None;
+        let mut first_event: Option<Instant> = None;
+        let mut last_event = Instant::now();
+
+        loop {
+            match watch_rx.recv_timeout(POLL_INTERVAL) {
+                Ok(path) => {
+                    // We're only interested in the latest path in the queue
+                    let path: Option<PathBuf> = watch_rx.try_iter().last().unwrap_or(path);
+
+                    if let Some((_, wd)) = watched.take() {
+                        let _ = inotify.watches().remove(wd);
+                    }
+
+                    first_event = None;
+
+                    watched = path.and_then(|path| {
+                        let wd = inotify.watches().add(&path, watch_mask()).ok()?;
+
+                        Some((path, wd))
+                    });
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+
+                // When the main thread exits, the channel returns an error
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            // Drain all the pending events, ignoring the ones of the paths watched previously
+            while let Ok(events) = inotify.read_events(&mut buffer) {
+                let changed = events
+                    .into_iter()
+                    .any(|event| matches!(&watched, Some((_, wd)) if event.wd == *wd));
+
+                if changed {
+                    last_event = Instant::now();
+                    first_event.get_or_insert(last_event);
+                }
+            }
+
+            if let (Some(first), Some((path, wd))) = (first_event, &mut watched) {
+                if last_event.elapsed() >= DEBOUNCE || first.elapsed() >= MAX_DELAY {
+                    first_event = None;
+
+                    on_change(path);
+
+                    // The watched file may have been replaced by a new one (like editors do when
+                    // saving), so watch the path again
+                    if let Ok(new_wd) = inotify.watches().add(&*path, watch_mask()) {
+                        *wd = new_wd;
+                    }
+                }
+            }
+        }
+    });
+
+    Some(watch_tx)
+}
@@ -0,0 +1,102 @@
+//! The resumable copy, move and delete engine of fcd, usable without the TUI.
+//!
+//! A job goes through two phases:
+//!
+//! 1. [`engine::dirscan::dirscan`] expands the selected entries into the full list
+//!    of files to process;
+//! 2. [`engine::cp_mv::cp_mv`] or [`engine::rm::rm`] process that list.
+//!
+//! Both phases report their progress by calling the `on_info` callback at most every
+//! 50ms, and can be controlled (suspended, skipped or aborted) by sending events to the
+//! `ev_rx` channel.
+//!
+//! When a [`engine::database::DataBase`] file is given to `cp_mv`, every step is
+//! recorded there, so that if the process gets interrupted the job can be resumed
+//! where it left off: [`engine::database::DataBase::get_pending_jobs`] returns the
+//! interrupted jobs, and calling `cp_mv` again with the file list returned by
+//! [`engine::database::DataBase::get_file_list`] completes the job.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::{
+//!     fs,
+//!     os::unix::fs::MetadataExt,
+//!     path::{Path, PathBuf},
+//!     process,
+//! };
+//!
+//! use fcd::engine::{
+//!     cp_mv::{cp_mv, CpMvInfo, RetryPolicy},
+//!     database::{
+//!         DBEntriesEntry, DBJobEntry, DBJobOperation, DBJobStatus, DataBase, OnConflict,
+//!     },
+//!     dirscan::{dirscan, ReadMetadata},
+//!     fs_ops::RealFs,
+//! };
+//!
+//! let file = PathBuf::from("/home/user/photos");
+//! let lstat = fs::symlink_metadata(&file).unwrap();
+//!
+//! let mut job = DBJobEntry {
+//!     id: 0,
+//!     pid: process::id(),
+//!     operation: DBJobOperation::Cp,
+//!     cwd: PathBuf::from("/home/user"),
+//!     dest: Some(PathBuf::from("/mnt/backup")),
+//!     on_conflict: Some(OnConflict::RenameCopy),
+//!     replace_first_path: false,
+//!     status: DBJobStatus::Dirscan,
+//!     entries: vec![DBEntriesEntry {
+//!         id: 0,
+//!         job_id: 0,
+//!         file,
+//!         is_file: lstat.is_file(),
+//!         is_dir: lstat.is_dir(),
+//!         is_symlink: lstat.is_symlink(),
+//!         size: lstat.len(),
+//!         uid: lstat.uid(),
+//!         gid: lstat.gid(),
+//!     }],
+//!     archives: Vec::new(),
+//! };
+//!
+//! let db_file = Path::new("/home/user/.local/state/backup.db");
+//! let mut db = DataBase::new(db_file).unwrap();
+//! db.new_job(&mut job);
+//!
+//! let (_ev_tx, ev_rx) = crossbeam_channel::unbounded();
+//! let mut files = dirscan(
+//!     &job.cwd,
+//!     &job.entries,
+//!     &[],
+//!     ReadMetadata::Yes,
+//!     ev_rx,
+//!     &mut |info| println!("Scanned {} files", info.num_files),
+//!     &RealFs,
+//! )
+//! .unwrap();
+//! db.set_file_list(job.id, &mut files);
+//!
+//! let (_ev_tx, ev_rx) = crossbeam_channel::unbounded();
+//! let result = cp_mv(
+//!     job.id,
+//!     job.operation,
+//!     &job.cwd,
+//!     job.dest.as_deref().unwrap(),
+//!     job.on_conflict.unwrap(),
+//!     job.replace_first_path,
+//!     &files,
+//!     ev_rx,
+//!     &mut |info: CpMvInfo| println!("{}", info.cur_source.display()),
+//!     Some(db_file),
+//!     &[],
+//!     RetryPolicy::default(),
+//!     &RealFs,
+//! );
+//!
+//! println!("{}", result.status);
+//! ```
+
+pub mod engine;
+pub mod shutil;
@@ -7,7 +7,7 @@ use std::{
     rc::Rc,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use ratatui::prelude::*;
 use termion::raw::IntoRawMode;
 
@@ -22,7 +22,6 @@ mod dlg_error;
 mod fm;
 mod fnmatch;
 mod palette;
-mod shutil;
 mod stat;
 mod template;
 mod terminal_restorer;
@@ -33,7 +32,7 @@ mod widgets;
 use crate::{
     app::{init_events, Action, App},
     config::load_config,
-    fm::bookmarks::Bookmarks,
+    fm::{bookmarks::Bookmarks, dir_sorts::DirSorts},
     palette::{get_monochrome_palette, get_palette},
     terminal_restorer::{TerminalRestorer, ENTER_MOUSE_SEQUENCE, EXIT_MOUSE_SEQUENCE},
 };
@@ -68,6 +67,10 @@ struct Cli {
     /// set tab size for the file viewer
     #[arg(short, long, default_value_t = 0)]
     tabsize: u8,
+
+    /// Save and restore the named session
+    #[arg(short, long, value_name = "NAME")]
+    session: Option<String>,
 }
 
 fn initialize_panic_handler() -> Result<()> {
@@ -103,6 +106,12 @@ fn initialize_panic_handler() -> Result<()> {
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if let Some(session) = &cli.session {
+        if session.is_empty() || session.contains('/') {
+            bail!("invalid session name: {:?}", session);
+        }
+    }
+
     let mut config = load_config().context("failed to load config")?;
 
     let palette = Rc::new(match cli.monochrome {
@@ -172,6 +181,12 @@ fn main() -> Result<()> {
                 bookmarks.borrow_mut().insert('h', &home_dir);
             }
 
+            let dir_sorts_path = xdg::BaseDirectories::with_prefix(crate_name!())
+                .ok()
+                .and_then(|xdg_dirs| xdg_dirs.place_state_file("dir_sorts.json").ok());
+
+            let dir_sorts = Rc::new(RefCell::new(DirSorts::new(dir_sorts_path.as_deref())));
+
             let initial_path = match PathBuf::from(env::var("PWD").unwrap_or(String::from("."))) {
                 cwd if cwd.is_absolute() => cwd.clean(),
                 _ => env::current_dir().context("failed to get current working directory")?,
@@ -189,17 +204,47 @@ fn main() -> Result<()> {
                 })
             });
 
+            let session_file = xdg::BaseDirectories::with_prefix(crate_name!())
+                .ok()
+                .and_then(|xdg_dirs| {
+                    match &cli.session {
+                        Some(session) => {
+                            xdg_dirs.place_state_file(format!("sessions/{}.json", session))
+                        }
+                        None => xdg_dirs.place_state_file("session.json"),
+                    }
+                    .ok()
+                });
+
+            let history_file = xdg::BaseDirectories::with_prefix(crate_name!())
+                .ok()
+                .and_then(|xdg_dirs| xdg_dirs.place_state_file("history").ok());
+
+            let frecency_file = xdg::BaseDirectories::with_prefix(crate_name!())
+                .ok()
+                .and_then(|xdg_dirs| xdg_dirs.place_state_file("frecency.json").ok());
+
+            let tag_sets_file = xdg::BaseDirectories::with_prefix(crate_name!())
+                .ok()
+                .and_then(|xdg_dirs| xdg_dirs.place_state_file("tag_sets.json").ok());
+
             Box::new(fm::app::App::new(
                 &config,
                 &palette,
                 &events_tx,
                 &bookmarks,
+                &dir_sorts,
                 &raw_output,
                 &stop_inputs_tx,
                 &stop_inputs_rx,
                 &initial_path,
                 cli.printwd.as_deref(),
                 db_file.as_deref(),
+                session_file.as_deref(),
+                history_file.as_deref(),
+                frecency_file.as_deref(),
+                tag_sets_file.as_deref(),
+                cli.session.is_some() || config.options.restore_session,
                 cli.vertical,
                 tab_size,
             )?) as Box<dyn App>
@@ -35,6 +35,12 @@ pub struct Palette {
     pub special: Style,
     pub archive: Style,
 
+    pub git_modified: Style,
+    pub git_staged: Style,
+    pub git_untracked: Style,
+    pub git_ignored: Style,
+    pub git_conflicted: Style,
+
     pub lineno: Style,
     pub hex_even: Style,
     pub hex_odd: Style,
@@ -126,6 +132,12 @@ pub fn get_palette(config: &Config) -> Palette {
         special: Style::default().fg(config.file_manager.special_fg),
         archive: Style::default().fg(config.file_manager.archive_fg),
 
+        git_modified: Style::default().fg(config.git.modified_fg),
+        git_staged: Style::default().fg(config.git.staged_fg),
+        git_untracked: Style::default().fg(config.git.untracked_fg),
+        git_ignored: Style::default().fg(config.git.ignored_fg),
+        git_conflicted: Style::default().fg(config.git.conflicted_fg),
+
         lineno: Style::default().fg(config.viewer.lineno_fg),
         hex_even: Style::default().fg(config.viewer.hex_even_fg),
         hex_odd: Style::default().fg(config.viewer.hex_odd_fg),
@@ -177,6 +189,12 @@ pub fn get_monochrome_palette() -> Palette {
         special: Style::default(),
         archive: Style::default(),
 
+        git_modified: Style::default(),
+        git_staged: Style::default(),
+        git_untracked: Style::default(),
+        git_ignored: Style::default(),
+        git_conflicted: Style::default(),
+
         lineno: Style::default().add_modifier(Modifier::BOLD),
         hex_even: Style::default(),
         hex_odd: Style::default(),
@@ -28,6 +28,7 @@ pub struct DirViewer {
     filename_str: String,
     file_list: Vec<Entry>,
     first_line: usize,
+    selected_line: Option<usize>,
 
     expression: Option<Regex>,
     lines_with_matches: Vec<bool>,
@@ -50,6 +51,7 @@ impl DirViewer {
             filename_str: String::from(filename_str),
             file_list,
             first_line: 0,
+            selected_line: None,
 
             expression: None,
             lines_with_matches: Vec::new(),
@@ -71,6 +73,10 @@ impl DirViewer {
         }
     }
 
+    pub fn select_file(&mut self, file: &Path) {
+        self.selected_line = self.file_list.iter().position(|entry| entry.file == file);
+    }
+
     pub fn send_updated_position(&mut self) {
         let current_line = self.first_line + (self.rect.height as usize);
 
@@ -390,6 +396,18 @@ impl Component for DirViewer {
         let old_first_line = self.first_line;
 
         self.rect = *chunk;
+
+        // Keep the selected line visible
+        if let Some(selected_line) = self.selected_line {
+            let height = (self.rect.height as usize).max(1);
+
+            if selected_line < self.first_line {
+                self.first_line = selected_line;
+            } else if selected_line >= (self.first_line + height) {
+                self.first_line = selected_line + 1 - height;
+            }
+        }
+
         self.clamp_first_line();
 
         if (self.rect.height != old_height) || (self.first_line != old_first_line) {
@@ -410,7 +428,10 @@ impl Component for DirViewer {
                 let filename = tilde_layout(&entry.label, filename_max_width);
                 let filename_width = filename.width();
 
-                let normal_style = entry.style;
+                let normal_style = match self.selected_line == Some(self.first_line + i) {
+                    true => self.palette.selected,
+                    false => entry.style,
+                };
 
                 let highlighted_style = if (self.first_line + i) == self.search_pos {
                     self.palette.markselect
@@ -95,15 +95,11 @@ impl Component for DlgGoto {
                 Key::Down | Key::Char('j') => {
                     self.section_focus_position = 1;
                 }
-                Key::Left | Key::Char('h') => {
-                    if self.section_focus_position == 1 {
-                        self.button_focus_position = 0;
-                    }
+                Key::Left | Key::Char('h') if self.section_focus_position == 1 => {
+                    self.button_focus_position = 0;
                 }
-                Key::Right | Key::Char('l') => {
-                    if self.section_focus_position == 1 {
-                        self.button_focus_position = 1;
-                    }
+                Key::Right | Key::Char('l') if self.section_focus_position == 1 => {
+                    self.button_focus_position = 1;
                 }
                 Key::Ctrl('c') => key_handled = false,
                 Key::Ctrl('l') => key_handled = false,
@@ -122,15 +122,11 @@ impl Component for DlgHexSearch {
                 Key::Char('\t') => {
                     self.section_focus_position = (self.section_focus_position + 1) % 3;
                 }
-                Key::Up | Key::Char('k') => {
-                    if self.section_focus_position > 0 {
-                        self.section_focus_position -= 1;
-                    }
+                Key::Up | Key::Char('k') if self.section_focus_position > 0 => {
+                    self.section_focus_position -= 1;
                 }
-                Key::Down | Key::Char('j') => {
-                    if (self.section_focus_position + 1) < 3 {
-                        self.section_focus_position += 1;
-                    }
+                Key::Down | Key::Char('j') if (self.section_focus_position + 1) < 3 => {
+                    self.section_focus_position += 1;
                 }
                 Key::Left | Key::Char('h') => match self.section_focus_position {
                     0 => (),
@@ -13,7 +13,7 @@ use termion::event::*;
 use crate::{
     app::PubSub,
     component::{Component, Focus},
-    fm::entry::{get_file_list, sort_by_function, SortBy, SortOrder},
+    fm::entry::{get_file_list, sort_entries, SortBy, SortDirs, SortOrder},
     palette::Palette,
     viewer::{
         dir_viewer::DirViewer,
@@ -46,7 +46,7 @@ impl FileViewer {
                 // TODO: It would be nice to use the same hidden file filter, sort method and sort
                 // order of the other panel when using the file viewer as a quick preview
                 file_list.sort_unstable_by(|a, b| {
-                    sort_by_function(SortBy::Name)(a, b, SortOrder::Normal)
+                    sort_entries(a, b, SortBy::Name, SortOrder::Normal, SortDirs::First)
                 });
 
                 Box::new(DirViewer::new(
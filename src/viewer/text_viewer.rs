@@ -103,6 +103,7 @@ pub struct TextViewer {
     filename: PathBuf,
     filename_str: String,
     tabsize: u8,
+    #[allow(dead_code)]
     data: Vec<u8>,
     line_offset: Vec<usize>,
     lines: Vec<String>,